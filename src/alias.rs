@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+// Deep enough for aliases built on aliases, shallow enough to stop a cycle quickly
+pub const MAX_ALIAS_DEPTH: usize = 8;
+
+/// Aliases expand the first word of a line into a longer command line
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Aliases {
+    aliases: BTreeMap<String, String>,
+}
+
+impl Aliases {
+    pub fn new(aliases: BTreeMap<String, String>) -> Aliases {
+        Aliases { aliases }
+    }
+
+    pub fn define(&mut self, name: &str, expansion: &str) -> Result<(), String> {
+        if name.is_empty() || name.starts_with(':') || name.contains(char::is_whitespace) {
+            return Err(format!("Invalid alias name '{name}'"));
+        }
+        if expansion.trim().is_empty() {
+            return Err(format!("Alias '{name}' needs an expansion"));
+        }
+        self.aliases
+            .insert(name.to_string(), expansion.trim().to_string());
+        Ok(())
+    }
+
+    /// Remove an alias, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.aliases.remove(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.aliases.iter()
+    }
+
+    pub fn to_map(&self) -> BTreeMap<String, String> {
+        self.aliases.clone()
+    }
+
+    /// Expand aliases at the start of `line`, keeping the words after the alias.
+    /// Fails when expansions keep producing aliases, e.g. for `a -> b` and `b -> a`.
+    pub fn expand(&self, line: &str) -> Result<String, String> {
+        let mut line = line.to_string();
+        for _ in 0..MAX_ALIAS_DEPTH {
+            let (first, rest) = line.split_once(' ').unwrap_or((&line, ""));
+            let Some(expansion) = self.aliases.get(first) else {
+                return Ok(line);
+            };
+            line = match rest {
+                "" => expansion.clone(),
+                rest => format!("{expansion} {rest}"),
+            };
+        }
+        match self
+            .aliases
+            .contains_key(line.split(' ').next().unwrap_or_default())
+        {
+            true => Err(format!(
+                "Alias expansion nested deeper than {MAX_ALIAS_DEPTH} levels, check for a cycle"
+            )),
+            false => Ok(line),
+        }
+    }
+
+    /// Print form, one `name = expansion` line per alias
+    pub fn describe(&self) -> String {
+        self.aliases
+            .iter()
+            .map(|(name, expansion)| format!("{name} = {expansion}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn define_use_and_remove_round_trip() {
+        let mut aliases = Aliases::default();
+        aliases.define("day", "/time set day").unwrap();
+        assert_eq!(aliases.expand("day").unwrap(), "/time set day");
+        assert_eq!(aliases.expand("/list").unwrap(), "/list");
+        assert!(aliases.remove("day"));
+        assert!(!aliases.remove("day"));
+        assert_eq!(aliases.expand("day").unwrap(), "day");
+    }
+
+    #[test]
+    fn keeps_the_words_after_the_alias() {
+        let mut aliases = Aliases::default();
+        aliases.define("g", "/give @p").unwrap();
+        aliases.define("gd", "g minecraft:diamond").unwrap();
+        assert_eq!(
+            aliases.expand("gd 64").unwrap(),
+            "/give @p minecraft:diamond 64"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_names_and_empty_expansions() {
+        let mut aliases = Aliases::default();
+        assert!(aliases.define(":q", "/stop").is_err());
+        assert!(aliases.define("two words", "/stop").is_err());
+        assert!(aliases.define("q", "  ").is_err());
+    }
+
+    #[test]
+    fn stops_expanding_a_cycle() {
+        let mut aliases = Aliases::default();
+        aliases.define("a", "b").unwrap();
+        aliases.define("b", "a").unwrap();
+        assert!(aliases.expand("a").unwrap_err().contains("cycle"));
+    }
+}
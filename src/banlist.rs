@@ -0,0 +1,138 @@
+use clap::ValueEnum;
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BanEntry {
+    pub name: String,
+    pub source: String,
+    pub date: Option<String>,
+    pub reason: String,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BanSortKey {
+    Name,
+    Source,
+    Date,
+    Reason,
+}
+
+/// Parse `/banlist` output. Entries read `<name> was banned by <source>: <reason>`, where
+/// some servers append ` on <date>` to the source, and are often run together over RCON.
+/// Returns None when the body doesn't look like a ban list.
+pub fn parse_banlist(body: &str) -> Option<Vec<BanEntry>> {
+    let re_header = Regex::new(r"^There are (?:no bans|\d+ ban\(s\):)").unwrap();
+    let re_entry = Regex::new(
+        r"(?P<name>\d{1,3}(?:\.\d{1,3}){3}|[A-Za-z0-9_]{1,16}) was banned by (?P<source>[^:]+?)(?: on (?P<date>[^:]+))?: ",
+    )
+    .unwrap();
+
+    let body = body.trim();
+    let header = re_header.find(body)?;
+    let entries_str = &body[header.end()..];
+    let matches: Vec<_> = re_entry.captures_iter(entries_str).collect();
+    let mut entries = Vec::new();
+    for (idx, cap) in matches.iter().enumerate() {
+        let reason_start = cap.get(0).unwrap().end();
+        let reason_end = matches
+            .get(idx + 1)
+            .map(|next| next.get(0).unwrap().start())
+            .unwrap_or(entries_str.len());
+        entries.push(BanEntry {
+            name: cap["name"].to_string(),
+            source: cap["source"].to_string(),
+            date: cap.name("date").map(|date| date.as_str().to_string()),
+            reason: entries_str[reason_start..reason_end].trim().to_string(),
+        });
+    }
+    Some(entries)
+}
+
+/// Print ban entries as an aligned table, optionally sorted by one column
+pub fn format_banlist(mut entries: Vec<BanEntry>, sort: Option<BanSortKey>) -> String {
+    if entries.is_empty() {
+        return "There are no bans".to_string();
+    }
+    if let Some(key) = sort {
+        entries.sort_by(|a, b| match key {
+            BanSortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            BanSortKey::Source => a.source.cmp(&b.source),
+            BanSortKey::Date => a.date.cmp(&b.date),
+            BanSortKey::Reason => a.reason.cmp(&b.reason),
+        });
+    }
+    let rows: Vec<[String; 4]> = entries
+        .into_iter()
+        .map(|entry| {
+            [
+                entry.name,
+                entry.source,
+                entry.date.unwrap_or_else(|| "-".to_string()),
+                entry.reason,
+            ]
+        })
+        .collect();
+    let header = ["Name", "Source", "Date", "Reason"].map(str::to_string);
+    let mut widths = [0; 4];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|row| {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            line.trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BANLIST: &str = "There are 2 ban(s):Zed was banned by Server: Griefing the spawn\
+1.2.3.4 was banned by Alex on 2024-01-05: spam";
+
+    #[test]
+    fn parses_entries_run_together() {
+        let entries = parse_banlist(BANLIST).unwrap();
+        assert_eq!(
+            entries,
+            [
+                BanEntry {
+                    name: "Zed".to_string(),
+                    source: "Server".to_string(),
+                    date: None,
+                    reason: "Griefing the spawn".to_string(),
+                },
+                BanEntry {
+                    name: "1.2.3.4".to_string(),
+                    source: "Alex".to_string(),
+                    date: Some("2024-01-05".to_string()),
+                    reason: "spam".to_string(),
+                },
+            ]
+        );
+        assert_eq!(parse_banlist("There are no bans"), Some(vec![]));
+        assert_eq!(parse_banlist("Unknown command"), None);
+    }
+
+    #[test]
+    fn formats_a_sorted_table() {
+        let entries = parse_banlist(BANLIST).unwrap();
+        assert_eq!(
+            format_banlist(entries, Some(BanSortKey::Source)),
+            "Name     Source  Date        Reason\n\
+             1.2.3.4  Alex    2024-01-05  spam\n\
+             Zed      Server  -           Griefing the spawn"
+        );
+    }
+}
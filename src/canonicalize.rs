@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use crate::mc_commands::{EFFECT_IDS, ENCHANTMENT_IDS, ITEM_IDS};
+use crate::tokenizer::tokenize;
+use crate::{locate_argument, Argument};
+
+/// Bundled IDs an argument takes. Blocks are looked up among the items, which share the
+/// IDs of the blocks they place.
+fn ids_for(arg: &Argument) -> &'static [&'static str] {
+    match arg.name() {
+        Some("item" | "block") => ITEM_IDS,
+        Some("effect") => EFFECT_IDS,
+        Some("enchantment") => ENCHANTMENT_IDS,
+        _ => &[],
+    }
+}
+
+/// Canonical `minecraft:` form of one of `ids` typed with any casing or without namespace
+fn canonical_id(ids: &[&str], id: &str) -> Option<String> {
+    let lower = id.to_ascii_lowercase();
+    let bare = lower.strip_prefix("minecraft:").unwrap_or(&lower);
+    ids.contains(&bare).then(|| format!("minecraft:{bare}"))
+}
+
+/// Rewrite bundled IDs typed without namespace or with another casing to their
+/// `minecraft:` form, where the command's usage takes an item, block, effect or
+/// enchantment. Returns the rewritten command and the `(original, canonical)` pairs that
+/// changed. Trailing NBT or block state (`{...}`, `[...]`) is kept as typed, and other
+/// arguments are left untouched, so player names and messages are sent as typed.
+pub fn canonicalize_ids(
+    cmd: &str,
+    commands: &HashMap<String, Vec<Argument>>,
+) -> (String, Vec<(String, String)>) {
+    let mut changes = Vec::new();
+    let words = tokenize(cmd);
+    let Some((args, len)) = (1..=words.len()).rev().find_map(|len| {
+        commands
+            .get(&words[..len].join(" "))
+            .map(|args| (args, len))
+    }) else {
+        return (cmd.to_string(), changes);
+    };
+    let rewritten: Vec<String> = words
+        .iter()
+        .enumerate()
+        .map(|(idx, word)| {
+            let ids = match idx
+                .checked_sub(len)
+                .and_then(|idx| locate_argument(args, idx))
+            {
+                Some((arg, 0)) => ids_for(arg),
+                _ => &[],
+            };
+            let id_end = word.find(['{', '[']).unwrap_or(word.len());
+            let (id, rest) = word.split_at(id_end);
+            match canonical_id(ids, id) {
+                Some(canonical) if canonical != id => {
+                    changes.push((id.to_string(), canonical.clone()));
+                    canonical + rest
+                }
+                _ => word.to_string(),
+            }
+        })
+        .collect();
+    (rewritten.join(" "), changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::help_parser::parse_commands;
+    use crate::mc_commands::apply_curated;
+
+    fn commands() -> HashMap<String, Vec<Argument>> {
+        let help = "/give <targets> <item> [<count>]\n/say <message>\n/effect (give|clear)";
+        let mut commands = parse_commands(help.to_string(), "/");
+        apply_curated(&mut commands, "/");
+        commands
+    }
+
+    #[test]
+    fn rewrites_ids_in_item_and_effect_positions() {
+        let (cmd, changes) =
+            canonicalize_ids("/give diamond diamond_sword{Damage:1} 2", &commands());
+        assert_eq!(cmd, "/give diamond minecraft:diamond_sword{Damage:1} 2");
+        assert_eq!(
+            changes,
+            [(
+                "diamond_sword".to_string(),
+                "minecraft:diamond_sword".to_string()
+            )]
+        );
+        let (cmd, _) = canonicalize_ids("/effect give @p speed", &commands());
+        assert_eq!(cmd, "/effect give @p minecraft:speed");
+    }
+
+    #[test]
+    fn folds_the_casing_of_known_ids() {
+        let (cmd, changes) = canonicalize_ids("/give @p DIAMOND_SWORD", &commands());
+        assert_eq!(cmd, "/give @p minecraft:diamond_sword");
+        assert_eq!(
+            changes,
+            [(
+                "DIAMOND_SWORD".to_string(),
+                "minecraft:diamond_sword".to_string()
+            )]
+        );
+        let (cmd, _) = canonicalize_ids("/give @p Minecraft:Stone", &commands());
+        assert_eq!(cmd, "/give @p minecraft:stone");
+    }
+
+    #[test]
+    fn leaves_other_positions_and_unknown_ids_alone() {
+        for typed in [
+            "/say diamond",
+            "/say DIAMOND_SWORD",
+            "/give DIAMOND diamond_block_of_nothing",
+            "/give @p minecraft:diamond",
+        ] {
+            let (cmd, changes) = canonicalize_ids(typed, &commands());
+            assert_eq!(cmd, typed);
+            assert!(changes.is_empty());
+        }
+    }
+
+    #[test]
+    fn follows_the_command_char_of_the_usages() {
+        let commands = parse_commands("!give <targets> <item>".to_string(), "!");
+        let (cmd, _) = canonicalize_ids("!give @p stone", &commands);
+        assert_eq!(cmd, "!give @p minecraft:stone");
+    }
+}
@@ -0,0 +1,440 @@
+use anyhow::{Context, Result};
+use clap::{ArgAction, Parser, ValueEnum};
+use regex::Regex;
+use rustyline::{CompletionType, Config};
+use std::path::{Path, PathBuf};
+
+use crate::banlist::BanSortKey;
+use crate::color_scheme::ColorSchemeName;
+use crate::edition::Edition;
+use crate::format::Locale;
+use crate::mc_commands::NamespaceStyle;
+use crate::proxy::{self, Proxy};
+use crate::{grep, rcon, script};
+
+/// Minecraft RCON CLI
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+    /// Server address (host:port), also read from MC_RCON_ADDRESS
+    #[arg(short, long)]
+    pub address: Option<String>,
+
+    /// Read the server address from a file
+    #[arg(long, value_name = "PATH", conflicts_with = "address")]
+    pub address_file: Option<PathBuf>,
+
+    /// RCON password, also read from MC_RCON_PASSWORD
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// Read the RCON password from a file, e.g. a mounted container secret
+    #[arg(long, value_name = "PATH", conflicts_with = "password")]
+    pub password_file: Option<PathBuf>,
+
+    /// Run the commands in this file instead of starting the interactive shell
+    #[arg(long, value_name = "PATH")]
+    pub command_file: Option<PathBuf>,
+
+    /// Value for a ${NAME} placeholder in the command file (falls back to the environment)
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = script::parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// Print how long each command took
+    #[arg(long)]
+    pub timings: bool,
+
+    /// Disable colored output
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Print each command before its response
+    #[arg(long)]
+    pub echo: bool,
+
+    /// Print response bodies without any formatting
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Words that exit the shell (case-insensitive, without a leading slash)
+    #[arg(long, value_delimiter = ',', default_value = "exit,quit")]
+    pub quit_keywords: Vec<String>,
+
+    /// Remove ANSI escape sequences from typed or pasted commands before sending them
+    #[arg(long, action = ArgAction::Set, default_value_t = true, value_name = "BOOL")]
+    pub strip_ansi_from_input: bool,
+
+    /// Expand $NAME and ${NAME} in commands from the environment before sending them,
+    /// $$ for a literal $
+    #[arg(long)]
+    pub expand_env: bool,
+
+    /// Prompt shown in the interactive shell, `{status}` shows the last command's outcome
+    #[arg(long, default_value = "> ")]
+    pub prompt: String,
+
+    /// Rewrite known IDs in item, block, effect and enchantment arguments to their
+    /// `minecraft:` form before sending
+    #[arg(long)]
+    pub canonicalize_ids: bool,
+
+    /// Ring the terminal bell when a command takes longer than this many milliseconds
+    #[arg(long, value_name = "MS")]
+    pub notify_after: Option<u64>,
+
+    /// Also show a desktop notification (requires the desktop-notifications feature)
+    #[arg(long, requires = "notify_after")]
+    pub notify_desktop: bool,
+
+    /// How Tab completion presents candidates
+    #[arg(long, value_enum, default_value_t = CompletionStyle::List)]
+    pub completion_style: CompletionStyle,
+
+    /// Sort the /banlist table by this column
+    #[arg(long, value_enum)]
+    pub sort: Option<BanSortKey>,
+
+    /// Give up waiting for a command's response after this many seconds and reconnect
+    /// before the next command
+    #[arg(long, value_name = "SECONDS")]
+    pub command_timeout: Option<f64>,
+
+    /// Reconnect before the next command when none was sent for this many seconds, for
+    /// servers or firewalls that silently drop idle connections
+    #[arg(long, value_name = "SECONDS")]
+    pub reconnect_on_idle: Option<f64>,
+
+    /// Only print response lines matching this regular expression
+    #[arg(long, value_name = "PATTERN", value_parser = grep::parse_pattern)]
+    pub grep: Option<Regex>,
+
+    /// Print the lines that don't match --grep instead
+    #[arg(long, requires = "grep")]
+    pub invert: bool,
+
+    /// Character the server expects before command names, empty for none
+    #[arg(long, value_name = "CHAR", default_value = "/", value_parser = parse_command_char)]
+    pub command_char: String,
+
+    /// Append every command and its response to this file
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Re-send the commands recorded in a --log-file transcript instead of starting the shell
+    #[arg(long, value_name = "LOGFILE", conflicts_with = "command_file")]
+    pub replay: Option<PathBuf>,
+
+    /// Milliseconds to wait between replayed commands
+    #[arg(long, value_name = "MS", requires = "replay")]
+    pub replay_delay: Option<u64>,
+
+    /// Listen on this address for JSON requests to run commands, instead of starting the shell
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["command_file", "replay"])]
+    pub serve: Option<String>,
+
+    /// Let --serve listen on an address other hosts can reach. Requests aren't
+    /// authenticated, so anyone who can connect can run commands.
+    #[arg(long, requires = "serve")]
+    pub serve_allow_remote: bool,
+
+    /// Listen on this Unix domain socket and run the lines written to it as commands,
+    /// instead of starting the shell. Each reply ends with an empty line.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["command_file", "replay", "serve"]
+    )]
+    pub control_socket: Option<PathBuf>,
+
+    /// File the shell history is loaded from and saved to [default: ~/.mc_rcon_history]
+    #[arg(long, value_name = "PATH")]
+    pub history_file: Option<PathBuf>,
+
+    /// Keep only the most recent N history entries, in memory and in the history file.
+    /// Overrides the config file's `max_history` [default: 1000]
+    #[arg(long, value_name = "N")]
+    pub max_history: Option<usize>,
+
+    /// Don't add a command to the history when it repeats the previous one
+    #[arg(long, action = ArgAction::Set, default_value_t = true, value_name = "BOOL")]
+    pub history_ignore_dups: bool,
+
+    /// Authenticate with an empty password instead of asking for one
+    #[arg(long, conflicts_with_all = ["password", "password_file"])]
+    pub no_auth: bool,
+
+    /// Also write each command's response to its own file in this directory
+    #[arg(long, value_name = "DIR")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Only write responses to --output-dir, not to stdout
+    #[arg(long, requires = "output_dir")]
+    pub output_only_file: bool,
+
+    /// Config file with aliases [default: ~/.config/minecraft-fancy-rcon-cli/config.toml]
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Server edition, selects how /help is parsed and the bundled command list
+    #[arg(long, value_enum, default_value_t = Edition::Java)]
+    pub edition: Edition,
+
+    /// Connect to a server from the config file's profiles
+    #[arg(long, value_name = "NAME", conflicts_with = "address")]
+    pub profile: Option<String>,
+
+    /// Choose a profile from a menu, even when an address is configured otherwise
+    #[arg(long, conflicts_with_all = ["address", "profile"])]
+    pub select_profile: bool,
+
+    /// Ask before sending the same command again within 2 seconds of the last one
+    #[arg(long)]
+    pub guard_duplicates: bool,
+
+    /// Warn about commands longer than this many bytes before sending them, and ask in the
+    /// shell. The vanilla server takes up to 1446.
+    #[arg(long, value_name = "BYTES", default_value_t = rcon::MAX_REQUEST_PAYLOAD)]
+    pub max_command_length: usize,
+
+    /// Don't fetch /help at startup, complete from the bundled command list instead
+    #[arg(long)]
+    pub no_help_fetch: bool,
+
+    /// Connect through a SOCKS5 proxy, as socks5://[user:password@]host[:port]
+    #[arg(long, value_name = "URL", value_parser = proxy::parse_proxy)]
+    pub proxy: Option<Proxy>,
+
+    /// Offer item, entity and effect IDs with the minecraft: namespace, without it, or both
+    #[arg(long, value_enum, default_value_t = NamespaceStyle::Explicit)]
+    pub namespace_style: NamespaceStyle,
+
+    /// Show the command being typed on a line below the input, with placeholders for the
+    /// arguments still missing
+    #[arg(long)]
+    pub preview: bool,
+
+    /// Show where each completion candidate came from: help, fallback, curated, ids,
+    /// spec, server or alias
+    #[arg(long)]
+    pub debug_completion: bool,
+
+    /// Don't show progress while fetching a paginated /help, the responses to a profile's
+    /// on_connect commands, or the summary after a batch of commands
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Print only the first N lines of a response in the shell, :more shows the rest
+    #[arg(long, value_name = "N")]
+    pub max_output_lines: Option<usize>,
+
+    /// Also append everything printed to stdout to this file, without colors
+    #[arg(long, value_name = "PATH")]
+    pub tee: Option<PathBuf>,
+
+    /// Keep the ANSI color codes in the --tee file
+    #[arg(long, requires = "tee")]
+    pub tee_ansi: bool,
+
+    /// Check the address, connection, password and /help step by step instead of
+    /// starting the shell
+    #[arg(long, conflicts_with_all = ["command_file", "replay", "serve", "control_socket"])]
+    pub doctor: bool,
+
+    /// TOML or JSON file with argument candidates and command usages for completion
+    #[arg(long, value_name = "PATH")]
+    pub completion_spec: Option<PathBuf>,
+
+    /// Send a read-only command like /list again once when the server answers it with an
+    /// empty response
+    #[arg(long)]
+    pub retry_empty: bool,
+
+    /// Print server errors on one line as sent, instead of the message and the
+    /// failing input on separate lines
+    #[arg(long)]
+    pub compact_errors: bool,
+
+    /// Print /help responses as the server sent them, without starting a new line at
+    /// each usage
+    #[arg(long)]
+    pub no_newline_fix: bool,
+
+    /// Language of the server's messages, to also recognize its translated errors
+    #[arg(long, value_enum, default_value_t = Locale::En)]
+    pub locale: Locale,
+
+    /// Text put before every command sent, e.g. `/execute as @p run `. The command's own
+    /// prefix is dropped, the text should bring one.
+    #[arg(long, value_name = "TEXT")]
+    pub prepend: Option<String>,
+
+    /// Text put after every command sent
+    #[arg(long, value_name = "TEXT")]
+    pub append: Option<String>,
+
+    /// Send /save-all before any command matching this regular expression, `^/?stop\b`
+    /// when given without one. Can be repeated.
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_parser = grep::parse_pattern,
+        num_args = 0..=1,
+        default_missing_value = r"^/?stop\b"
+    )]
+    pub autosave_before: Vec<Regex>,
+
+    /// Print the settings in effect after the config file, profile, environment and
+    /// flags are resolved, with passwords masked, and exit without connecting
+    #[arg(long)]
+    pub print_config: bool,
+
+    /// In the shell, ask before sending a command with a value that isn't one of the
+    /// choices its usage lists, like `/difficulty hardest`
+    #[arg(long)]
+    pub validate_args: bool,
+
+    /// Connect and authenticate again up to this many times, waiting longer each time,
+    /// when the server rejects the password or drops the connection, as it may while
+    /// still starting
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub auth_retries: u32,
+
+    /// Print, log and save responses with the text matching this regular expression
+    /// replaced by `***`, IPv4 addresses when given without one. Can be repeated.
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_parser = grep::parse_pattern,
+        num_args = 0..=1,
+        default_missing_value = r"\b(?:\d{1,3}\.){3}\d{1,3}\b"
+    )]
+    pub redact: Vec<Regex>,
+
+    /// With --command-file, --replay or commands piped to stdin, stop at the first
+    /// command that fails or gets an error response and exit with status 1
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Colors for commands, suggestions, errors and --grep matches
+    #[arg(long, value_enum, value_name = "NAME", default_value_t = ColorSchemeName::Default)]
+    pub color_scheme: ColorSchemeName,
+}
+
+fn parse_command_char(s: &str) -> Result<String, String> {
+    match s.chars().count() {
+        0 | 1 => Ok(s.to_string()),
+        _ => Err(format!("expected a single character, got `{s}`")),
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionStyle {
+    /// List all candidates below the prompt
+    List,
+    /// Replace the word in place, cycling through candidates on repeated Tab
+    Cycle,
+}
+
+impl From<CompletionStyle> for CompletionType {
+    fn from(style: CompletionStyle) -> CompletionType {
+        match style {
+            CompletionStyle::List => CompletionType::List,
+            CompletionStyle::Cycle => CompletionType::Circular,
+        }
+    }
+}
+
+/// History entries kept when neither --max-history nor the config file sets a limit
+pub const DEFAULT_MAX_HISTORY: usize = 1000;
+
+/// Line editor configuration for the interactive shell, `config_max_history` being the
+/// config file's `max_history`
+pub fn editor_config(cli: &Cli, config_max_history: Option<usize>) -> Result<Config> {
+    let max_history = cli
+        .max_history
+        .or(config_max_history)
+        .unwrap_or(DEFAULT_MAX_HISTORY);
+    Ok(Config::builder()
+        .completion_type(cli.completion_style.into())
+        .max_history_size(max_history)?
+        .history_ignore_dups(cli.history_ignore_dups)?
+        .build())
+}
+
+/// Where the shell history is persisted, None when there is no home directory to default to
+pub fn history_path(cli: &Cli) -> Option<PathBuf> {
+    cli.history_file.clone().or_else(|| {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".mc_rcon_history"))
+    })
+}
+
+pub const ADDRESS_ENV: &str = "MC_RCON_ADDRESS";
+pub const PASSWORD_ENV: &str = "MC_RCON_PASSWORD";
+
+/// Read a value stored in a file, without the trailing newline
+pub fn read_value_file(path: &Path) -> Result<String> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(contents.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Resolve a setting from, in order of precedence, its flag, its file flag and the
+/// environment. None means the caller should fall back further (e.g. prompt).
+pub fn resolve_value(
+    flag: Option<&str>,
+    file: Option<&Path>,
+    env_value: Option<String>,
+) -> Result<Option<String>> {
+    if let Some(value) = flag {
+        return Ok(Some(value.to_string()));
+    }
+    if let Some(path) = file {
+        return read_value_file(path).map(Some);
+    }
+    Ok(env_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_history_flag_overrides_the_config_file() {
+        let parse = |args: &[&str]| Cli::parse_from([&["minecraft-fancy-rcon-cli"], args].concat());
+        let config_limit =
+            |cli: &Cli, config| editor_config(cli, config).unwrap().max_history_size();
+        assert_eq!(config_limit(&parse(&[]), None), DEFAULT_MAX_HISTORY);
+        assert_eq!(config_limit(&parse(&[]), Some(50)), 50);
+        assert_eq!(config_limit(&parse(&["--max-history", "10"]), Some(50)), 10);
+    }
+
+    #[test]
+    fn cycle_style_completes_in_place() {
+        let cli = Cli::parse_from(["minecraft-fancy-rcon-cli", "--completion-style", "cycle"]);
+        let config = editor_config(&cli, None).unwrap();
+        assert_eq!(config.completion_type(), CompletionType::Circular);
+        let cli = Cli::parse_from(["minecraft-fancy-rcon-cli"]);
+        let config = editor_config(&cli, None).unwrap();
+        assert_eq!(config.completion_type(), CompletionType::List);
+    }
+
+    #[test]
+    fn secrets_come_from_the_flag_then_the_file_then_the_environment() {
+        let path = std::env::temp_dir().join(format!("mc-rcon-{}-secret", std::process::id()));
+        std::fs::write(&path, "hunter2\r\n").unwrap();
+        let env = || Some("from-env".to_string());
+        assert_eq!(
+            resolve_value(Some("flag"), Some(&path), env()).unwrap(),
+            Some("flag".to_string())
+        );
+        assert_eq!(
+            resolve_value(None, Some(&path), env()).unwrap(),
+            Some("hunter2".to_string())
+        );
+        assert_eq!(
+            resolve_value(None, None, env()).unwrap(),
+            Some("from-env".to_string())
+        );
+        assert!(resolve_value(None, Some(Path::new("/nonexistent/secret")), None).is_err());
+    }
+}
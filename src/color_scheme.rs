@@ -0,0 +1,125 @@
+use clap::ValueEnum;
+
+/// The presets for `--color-scheme`
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSchemeName {
+    #[default]
+    Default,
+    /// The Solarized accent colors, in 24-bit color
+    Solarized,
+    /// Bold, dim and reverse video only, for telling things apart without color
+    Mono,
+    /// Bright colors, errors on a red background
+    HighContrast,
+}
+
+/// SGR parameters, e.g. `32` for green, for each kind of highlighted text. An empty
+/// string leaves the text as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorScheme {
+    /// Known command names in the input line
+    pub command: &'static str,
+    /// Other names after the command prefix, like plugin commands missing from /help
+    pub unknown_command: &'static str,
+    /// Command names in hints and completion candidates
+    pub suggestion: &'static str,
+    /// The message of a server error
+    pub error: &'static str,
+    /// Text matched by `--grep` and `:grep`
+    pub grep_match: &'static str,
+}
+
+impl ColorScheme {
+    pub fn preset(name: ColorSchemeName) -> ColorScheme {
+        match name {
+            ColorSchemeName::Default => ColorScheme {
+                command: "32",         // green
+                unknown_command: "34", // blue
+                suggestion: "33",      // yellow
+                error: "31",           // red
+                grep_match: "1;31",
+            },
+            ColorSchemeName::Solarized => ColorScheme {
+                command: "38;2;133;153;0",
+                unknown_command: "38;2;38;139;210",
+                suggestion: "38;2;181;137;0",
+                error: "38;2;220;50;47",
+                grep_match: "1;38;2;203;75;22",
+            },
+            ColorSchemeName::Mono => ColorScheme {
+                command: "1",
+                unknown_command: "2",
+                suggestion: "",
+                error: "1",
+                grep_match: "7",
+            },
+            ColorSchemeName::HighContrast => ColorScheme {
+                command: "1;92",
+                unknown_command: "1;94",
+                suggestion: "1;93",
+                error: "1;97;41",
+                grep_match: "1;30;103",
+            },
+        }
+    }
+
+    /// `text` in the given colors, followed by a reset
+    pub fn paint(code: &str, text: &str) -> String {
+        match code {
+            "" => text.to_string(),
+            _ => format!("\x1b[{code}m{text}\x1b[0m"),
+        }
+    }
+}
+
+impl Default for ColorScheme {
+    fn default() -> ColorScheme {
+        ColorScheme::preset(ColorSchemeName::Default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_paint_commands_suggestions_and_errors() {
+        let painted = |name| {
+            let scheme = ColorScheme::preset(name);
+            [scheme.command, scheme.suggestion, scheme.error]
+                .map(|code| ColorScheme::paint(code, "x"))
+        };
+        assert_eq!(
+            painted(ColorSchemeName::Default),
+            ["\x1b[32mx\x1b[0m", "\x1b[33mx\x1b[0m", "\x1b[31mx\x1b[0m"]
+        );
+        assert_eq!(
+            painted(ColorSchemeName::Solarized),
+            [
+                "\x1b[38;2;133;153;0mx\x1b[0m",
+                "\x1b[38;2;181;137;0mx\x1b[0m",
+                "\x1b[38;2;220;50;47mx\x1b[0m"
+            ]
+        );
+        assert_eq!(
+            painted(ColorSchemeName::Mono),
+            ["\x1b[1mx\x1b[0m", "x", "\x1b[1mx\x1b[0m"]
+        );
+        assert_eq!(
+            painted(ColorSchemeName::HighContrast),
+            [
+                "\x1b[1;92mx\x1b[0m",
+                "\x1b[1;93mx\x1b[0m",
+                "\x1b[1;97;41mx\x1b[0m"
+            ]
+        );
+    }
+
+    #[test]
+    fn the_default_scheme_is_the_default_preset() {
+        assert_eq!(
+            ColorScheme::default(),
+            ColorScheme::preset(ColorSchemeName::default())
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::display::{self, DisplayOptions};
+use crate::format::{format_response, is_error_response};
+use crate::mc_commands;
+use crate::rcon::{self, RconError};
+use crate::session::Session;
+use crate::tokenizer::argument_words;
+use crate::{locate_argument, Argument};
+
+/// A command's response, and how it is shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandResult {
+    pub body: String,
+    /// The body formatted for display, or as is with `raw`
+    pub formatted: String,
+    pub is_error: bool,
+    pub elapsed: Duration,
+}
+
+/// Send a command and format its response the same way for every mode
+pub fn run_command(
+    session: &mut Session,
+    options: &DisplayOptions,
+    cmd: &str,
+) -> Result<CommandResult, RconError> {
+    let start = Instant::now();
+    // Only read-only commands are safe to send twice
+    let body = match options.retry_empty && mc_commands::is_query_command(cmd) {
+        true => rcon::retry_empty(|| session.send_command(cmd)),
+        false => session.send_command(cmd),
+    }?;
+    let elapsed = start.elapsed();
+    let formatted = match options.raw {
+        true => body.clone(),
+        false => format_response(options, cmd, &body),
+    };
+    let formatted = display::redact(&formatted, &options.redact);
+    Ok(CommandResult {
+        is_error: is_error_response(&body, options.locale),
+        body,
+        formatted,
+        elapsed,
+    })
+}
+
+/// Wrap a command in the `--prepend` and `--append` texts. The prepended text replaces the
+/// command's `command_char`, so `/say hi` becomes `/execute as @p run say hi`.
+pub fn wrap(cmd: &str, prepend: Option<&str>, append: Option<&str>, command_char: &str) -> String {
+    let mut wrapped = match prepend {
+        Some(prepend) => format!("{prepend}{}", cmd.strip_prefix(command_char).unwrap_or(cmd)),
+        None => cmd.to_string(),
+    };
+    wrapped.push_str(append.unwrap_or_default());
+    wrapped
+}
+
+/// Whether the world should be saved before sending `cmd`, because it matches one of the
+/// `--autosave-before` patterns
+pub fn needs_autosave(cmd: &str, patterns: &[Regex]) -> bool {
+    patterns.iter().any(|pattern| pattern.is_match(cmd))
+}
+
+/// Why `cmd` can't be right when `--validate-args` is set: the first word given for an
+/// argument with a fixed set of values, like a choice or a game mode, that isn't one of
+/// them. Commands with several
+/// forms and `/execute` are not checked.
+pub fn invalid_choice(
+    commands: &HashMap<String, Vec<Argument>>,
+    cmd: &str,
+    command_char: &str,
+) -> Option<String> {
+    let words: Vec<&str> = argument_words(cmd)
+        .into_iter()
+        .filter(|word| !word.is_empty())
+        .collect();
+    let name = words.first()?.strip_prefix(command_char)?;
+    if name == "execute" || mc_commands::overloads(name).is_some() {
+        return None;
+    }
+    let (args, command_words) = (1..=words.len()).rev().find_map(|len| {
+        commands
+            .get(&words[..len].join(" "))
+            .map(|args| (args, len))
+    })?;
+    for (index, word) in words[command_words..].iter().enumerate() {
+        let (arg, _) = locate_argument(args, index)?;
+        let choices: Vec<&str> = match arg {
+            Argument::RequiredChoice(choices) | Argument::OptionalChoice(choices) => {
+                choices.iter().map(String::as_str).collect()
+            }
+            Argument::Required(name) | Argument::Optional(name) => {
+                mc_commands::fixed_values(name).to_vec()
+            }
+        };
+        // Sets with a placeholder in them, like (<respectTeams>|under), take any value
+        let fixed = !choices.is_empty() && choices.iter().all(|choice| !choice.contains('<'));
+        if fixed && !choices.contains(word) {
+            return Some(format!(
+                "{word} is not a valid value, expected {}",
+                choices.join("|")
+            ));
+        }
+    }
+    None
+}
+
+/// Run `cmd` in the `:dim` dimension with `/execute in`, when one is set
+pub fn in_dimension(cmd: &str, dimension: Option<&str>, command_char: &str) -> String {
+    match dimension {
+        Some(dimension) => {
+            let prepend = format!("{command_char}execute in {dimension} run ");
+            wrap(cmd, Some(&prepend), None, command_char)
+        }
+        None => cmd.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::cli::Cli;
+    use crate::rcon::test_server::{client_for, packet};
+    use clap::Parser;
+
+    const UNKNOWN: &str = "Unknown or incomplete command, see below for error  /fly<--[HERE]";
+
+    fn options(args: &[&str]) -> DisplayOptions {
+        let cli = Cli::parse_from(std::iter::once("mc-rcon").chain(args.iter().copied()));
+        DisplayOptions::from_cli(&cli)
+    }
+
+    fn session() -> Session {
+        Session::new(client_for(|id, body| match body {
+            "/fly" => packet(id, UNKNOWN.as_bytes()),
+            _ => packet(id, b"There are 0 of a max of 20 players online: "),
+        }))
+    }
+
+    #[test]
+    fn results_carry_the_body_and_its_formatting() {
+        let mut session = session();
+        let result = run_command(&mut session, &options(&[]), "/list").unwrap();
+        assert_eq!(result.body, "There are 0 of a max of 20 players online: ");
+        assert_eq!(result.formatted, result.body);
+        assert!(!result.is_error);
+        let result = run_command(&mut session, &options(&[]), "/fly").unwrap();
+        assert_eq!(result.body, UNKNOWN);
+        assert_eq!(
+            result.formatted,
+            "Unknown or incomplete command, see below for error\n/fly<--[HERE]"
+        );
+        assert!(result.is_error);
+    }
+
+    #[test]
+    fn raw_results_are_not_formatted() {
+        let result = run_command(&mut session(), &options(&["--raw"]), "/fly").unwrap();
+        assert_eq!(result.formatted, UNKNOWN);
+        assert!(result.is_error);
+    }
+
+    #[test]
+    fn wraps_commands_in_the_prepend_and_append_texts() {
+        assert_eq!(
+            wrap("/say hi", Some("/execute as @p run "), None, "/"),
+            "/execute as @p run say hi"
+        );
+        assert_eq!(wrap("/say hi", None, Some(" !"), "/"), "/say hi !");
+        assert_eq!(
+            wrap("!say hi", Some("!sudo "), Some(" --ok"), "!"),
+            "!sudo say hi --ok"
+        );
+        assert_eq!(wrap("/list", None, None, "/"), "/list");
+    }
+
+    #[test]
+    fn autosaves_only_before_matching_commands() {
+        let patterns = [crate::grep::parse_pattern(r"^/?stop\b").unwrap()];
+        assert!(needs_autosave("/stop", &patterns));
+        assert!(needs_autosave("stop", &patterns));
+        assert!(!needs_autosave("/stopsound @a", &patterns));
+        assert!(!needs_autosave("/stop", &[]));
+    }
+
+    #[test]
+    fn warns_about_values_outside_a_choice_set() {
+        let commands = crate::help_parser::parse_commands(
+            "/difficulty [peaceful|easy|normal|hard]\n/weather (clear|rain|thunder) [<duration>]\n\
+/spreadplayers <center> (<respectTeams>|under)"
+                .to_string(),
+            "/",
+        );
+        assert_eq!(
+            invalid_choice(&commands, "/weather snow", "/"),
+            Some("snow is not a valid value, expected clear|rain|thunder".to_string())
+        );
+        assert_eq!(invalid_choice(&commands, "/weather rain 600", "/"), None);
+        assert_eq!(invalid_choice(&commands, "/difficulty hard", "/"), None);
+        assert!(invalid_choice(&commands, "/difficulty nightmare", "/").is_some());
+        assert_eq!(
+            invalid_choice(&commands, "/spreadplayers 0 true", "/"),
+            None
+        );
+        assert_eq!(invalid_choice(&commands, "/unknown x", "/"), None);
+    }
+
+    #[test]
+    fn accepts_named_and_numeric_game_modes() {
+        let commands =
+            crate::help_parser::parse_commands("/gamemode <gamemode> [<target>]".to_string(), "/");
+        assert_eq!(invalid_choice(&commands, "/gamemode 1 @p", "/"), None);
+        assert_eq!(invalid_choice(&commands, "/gamemode creative", "/"), None);
+        assert_eq!(
+            invalid_choice(&commands, "/gamemode 4", "/"),
+            Some(
+                "4 is not a valid value, expected survival|creative|adventure|spectator|0|1|2|3"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn runs_commands_in_the_session_dimension() {
+        assert_eq!(
+            in_dimension("/setblock ~ ~ ~ stone", Some("minecraft:the_nether"), "/"),
+            "/execute in minecraft:the_nether run setblock ~ ~ ~ stone"
+        );
+        assert_eq!(
+            in_dimension("!list", Some("minecraft:the_end"), "!"),
+            "!execute in minecraft:the_end run list"
+        );
+        assert_eq!(in_dimension("/list", None, "/"), "/list");
+    }
+
+    #[test]
+    fn redacts_the_formatted_response() {
+        let mut session = Session::new(client_for(|id, _| {
+            packet(
+                id,
+                b"There are 1 ban(s):10.0.0.5 was banned by Server: spam",
+            )
+        }));
+        let result = run_command(&mut session, &options(&["--redact"]), "/banlist ips").unwrap();
+        assert!(!result.formatted.contains("10.0.0.5"));
+        assert!(result.formatted.contains("***"));
+        // The body is kept as received, for the error checks
+        assert!(result.body.contains("10.0.0.5"));
+    }
+}
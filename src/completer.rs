@@ -0,0 +1,1306 @@
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::{Hint, Hinter};
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{CompletionType, Context as RustyContext, Helper};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::alias::Aliases;
+use crate::color_scheme::ColorScheme;
+use crate::edition::Edition;
+use crate::execute::{self, ExecuteStep};
+use crate::mc_commands::{self, NamespaceStyle};
+use crate::server_cache::ServerCache;
+use crate::tokenizer::{argument_words, tokenize};
+use crate::{locate_argument, template, Argument};
+
+/// Where a completion candidate came from, shown with --debug-completion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateSource {
+    /// The server's /help
+    Help,
+    /// The bundled command list used when /help gave nothing
+    Fallback,
+    /// The bundled command shapes and argument values
+    Curated,
+    /// A bundled ID list
+    Ids,
+    /// --completion-spec
+    Spec,
+    /// Learned from the server's responses, like player names from /list
+    Server,
+    Alias,
+}
+
+impl CandidateSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            CandidateSource::Help => "help",
+            CandidateSource::Fallback => "fallback",
+            CandidateSource::Curated => "curated",
+            CandidateSource::Ids => "ids",
+            CandidateSource::Spec => "spec",
+            CandidateSource::Server => "server",
+            CandidateSource::Alias => "alias",
+        }
+    }
+}
+
+/// Record `source` for the commands that `after` adds or changes compared to `before`
+pub fn record_sources(
+    sources: &mut HashMap<String, CandidateSource>,
+    before: &HashMap<String, Vec<Argument>>,
+    after: &HashMap<String, Vec<Argument>>,
+    source: CandidateSource,
+) {
+    for (name, args) in after {
+        if before.get(name) != Some(args) {
+            sources.insert(name.clone(), source);
+        }
+    }
+}
+
+pub struct MinecraftCompleter {
+    pub commands: HashMap<String, Vec<Argument>>,
+    pub cache: ServerCache,
+    pub no_color: bool,
+    pub completion_type: CompletionType,
+    /// Prefix the server expects before command names, may be empty
+    pub command_char: String,
+    /// The word the last completion matched against, emphasized in the candidate list
+    pub typed_word: RefCell<String>,
+    pub aliases: Aliases,
+    pub namespace_style: NamespaceStyle,
+    /// Selects the gamerules offered
+    pub edition: Edition,
+    pub colors: ColorScheme,
+    /// Show the command being built on a line below the input, as with --preview
+    pub preview: bool,
+    /// Where each command's usage came from
+    pub sources: HashMap<String, CandidateSource>,
+    /// Show each candidate's source in the list, as with --debug-completion
+    pub debug_completion: bool,
+}
+
+impl MinecraftCompleter {
+    /// Names that can be typed first, leaving out the subcommand levels
+    fn command_names(&self) -> impl Iterator<Item = &String> {
+        self.commands
+            .keys()
+            .filter(|name| !name.contains(' '))
+            .chain(self.aliases.iter().map(|(name, _)| name))
+    }
+
+    fn command_source(&self, name: &str) -> CandidateSource {
+        match self.aliases.get(name) {
+            Some(_) => CandidateSource::Alias,
+            None => self
+                .sources
+                .get(name)
+                .copied()
+                .unwrap_or(CandidateSource::Help),
+        }
+    }
+
+    /// Where a candidate for `arg` of `command` came from. Choices come with the usage,
+    /// other values are looked up in the lists they could have come from.
+    fn argument_source(&self, command: &str, arg: &Argument, candidate: &str) -> CandidateSource {
+        let Some(name) = arg.name() else {
+            return self.command_source(command);
+        };
+        let bare = candidate.strip_prefix("minecraft:").unwrap_or(candidate);
+        if self.cache.candidates(name).iter().any(|c| c == candidate) {
+            CandidateSource::Server
+        } else if self.cache.overrides.contains_key(name) {
+            CandidateSource::Spec
+        } else if mc_commands::argument_ids(name).contains(&bare) {
+            CandidateSource::Ids
+        } else {
+            CandidateSource::Curated
+        }
+    }
+
+    /// A candidate's display, with its source for --debug-completion
+    fn tag(&self, display: String, source: CandidateSource) -> String {
+        match self.debug_completion {
+            true => format!("{display} [{}]", source.label()),
+            false => display,
+        }
+    }
+
+    /// The deepest command or subcommand the completed words lead to, and how many
+    /// words it spans
+    fn resolve_command(&self, words: &[&str]) -> Option<(&Vec<Argument>, usize)> {
+        (1..words.len()).rev().find_map(|len| {
+            self.commands
+                .get(&words[..len].join(" "))
+                .map(|args| (args, len))
+        })
+    }
+
+    /// Complete the end of `input`, returning where the replaced word starts
+    fn complete_input(&self, input: &str) -> (usize, Vec<Pair>) {
+        let words = argument_words(input);
+        *self.typed_word.borrow_mut() = words.last().unwrap_or(&"").to_string();
+        match words.len() {
+            // No suggestions on empty input
+            0 => (0, Vec::new()),
+            // Complete command name
+            1 => {
+                let candidates = self
+                    .command_names()
+                    .filter(|cmd_name| cmd_name.starts_with(input))
+                    .map(|cmd_name| Pair {
+                        display: self.tag(cmd_name.clone(), self.command_source(cmd_name)),
+                        replacement: cmd_name.clone() + " ",
+                    })
+                    .collect();
+                (0, candidates)
+            }
+            _ if self.aliases.get(words[0]).is_some() => self.complete_alias(input, words[0]),
+            _ if self.is_execute(words[0]) => self.complete_execute(input, &words),
+            _ if self.overloads(words[0]).is_some() => self.complete_overloads(input, &words),
+            // Try to match command
+            _ => {
+                match self.resolve_command(&words) {
+                    Some((args, command_words)) => {
+                        // Complete argument
+                        let command = words[..command_words].join(" ");
+                        let mut pairs = Vec::new();
+                        let (word, typed) = words[command_words..].split_last().unwrap();
+                        // If there are too many input arguments, return no suggestions
+                        let Some((arg, candidates)) = self.candidates_at(args, typed) else {
+                            return (0, Vec::new());
+                        };
+                        for candidate in candidates {
+                            if matches_word(&candidate, word) {
+                                let label = arg.name().and_then(|name| {
+                                    mc_commands::candidate_label(name, &candidate)
+                                });
+                                let display = match label {
+                                    Some(label) => format!("{candidate} ({label})"),
+                                    None => candidate.clone(),
+                                };
+                                let source = self.argument_source(&command, arg, &candidate);
+                                pairs.push(Pair {
+                                    display: self.tag(display, source),
+                                    replacement: candidate + " ",
+                                });
+                            }
+                        }
+                        // Accept a lone match right away and list what can follow it. Cycling
+                        // completion would jump straight to the first follow-up instead.
+                        if let ([only], CompletionType::List) =
+                            (pairs.as_slice(), self.completion_type)
+                        {
+                            // The accepted word may also lead into a subcommand
+                            let mut next_words = words[..words.len() - 1].to_vec();
+                            next_words.extend(tokenize(only.replacement.trim_end()));
+                            next_words.push("");
+                            let next_candidates =
+                                self.resolve_command(&next_words).and_then(|(args, len)| {
+                                    let typed = &next_words[len..next_words.len() - 1];
+                                    let command = next_words[..len].join(" ");
+                                    self.candidates_at(args, typed)
+                                        .map(|(arg, candidates)| (command, arg, candidates))
+                                });
+                            if let Some((command, arg, next_candidates)) = next_candidates {
+                                let next_pairs: Vec<Pair> = next_candidates
+                                    .into_iter()
+                                    .map(|candidate| Pair {
+                                        replacement: format!("{}{candidate} ", only.replacement),
+                                        display: self.tag(
+                                            candidate.clone(),
+                                            self.argument_source(&command, arg, &candidate),
+                                        ),
+                                    })
+                                    .collect();
+                                if !next_pairs.is_empty() {
+                                    // Listing them must not insert more than the accepted
+                                    // word, like the `@` that selectors share, so the
+                                    // word is offered on its own too then
+                                    let shared = common_prefix_len(
+                                        next_pairs.iter().map(|pair| pair.replacement.as_str()),
+                                    );
+                                    let only = only.clone();
+                                    pairs = next_pairs;
+                                    if shared > only.replacement.len() {
+                                        pairs.insert(0, only);
+                                    }
+                                    // The follow-ups weren't matched against anything
+                                    self.typed_word.borrow_mut().clear();
+                                }
+                            }
+                        }
+                        (input.len() - words.last().unwrap().len(), pairs)
+                    }
+                    None => (0, Vec::new()),
+                }
+            }
+        }
+    }
+
+    /// Candidates for the argument word following the `typed` ones, including those that
+    /// depend on the word typed for the argument before it
+    fn candidates_at<'a>(
+        &self,
+        args: &'a [Argument],
+        typed: &[&str],
+    ) -> Option<(&'a Argument, Vec<String>)> {
+        let (arg, offset) = locate_argument(args, typed.len())?;
+        let mut candidates = arg.candidates(offset, &self.cache, self.namespace_style);
+        if arg.name() == Some("rule") {
+            let rules = mc_commands::gamerules(self.edition);
+            candidates.extend(rules.iter().map(|(rule, _)| rule.to_string()));
+        }
+        if let (Some(name), 0, Some((previous_word, _))) = (arg.name(), offset, typed.split_last())
+        {
+            let previous = locate_argument(args, typed.len() - 1).and_then(|(arg, _)| arg.name());
+            // Only the values the typed rule takes, e.g. no true or false for a number
+            if let (Some("rule"), Some(value)) = (
+                previous,
+                mc_commands::gamerule_value(self.edition, previous_word),
+            ) {
+                candidates = value.candidates().iter().map(|v| v.to_string()).collect();
+            }
+            if let Some(previous) = previous {
+                candidates.extend(mc_commands::dependent_candidates(
+                    previous,
+                    previous_word,
+                    name,
+                ));
+            }
+        }
+        Some((arg, candidates))
+    }
+
+    /// Complete an aliased line as the line it expands to
+    fn complete_alias(&self, input: &str, alias: &str) -> (usize, Vec<Pair>) {
+        let Ok(expansion) = self.aliases.expand(alias) else {
+            return (0, Vec::new());
+        };
+        let rest = &input[alias.len()..];
+        let (start, pairs) = self.complete_input(&format!("{expansion}{rest}"));
+        match start.checked_sub(expansion.len()) {
+            Some(start) => (alias.len() + start, pairs),
+            // Only the words typed after the alias can be completed
+            None => (0, Vec::new()),
+        }
+    }
+
+    fn overloads(&self, name: &str) -> Option<Vec<Vec<Argument>>> {
+        if !self.commands.contains_key(name) {
+            return None;
+        }
+        mc_commands::overloads(name.strip_prefix(self.command_char.as_str())?)
+    }
+
+    /// Complete from every form of the command that the typed words still fit
+    fn complete_overloads(&self, input: &str, words: &[&str]) -> (usize, Vec<Pair>) {
+        let (word, typed) = words[1..].split_last().unwrap();
+        let mut candidates: Vec<String> = Vec::new();
+        for args in self.overloads(words[0]).unwrap_or_default() {
+            let fits = typed.iter().enumerate().all(|(idx, typed_word)| {
+                locate_argument(&args, idx).is_some_and(|(arg, _)| arg.accepts(typed_word))
+            });
+            if !fits {
+                continue;
+            }
+            if let Some((arg, offset)) = locate_argument(&args, typed.len()) {
+                for candidate in arg.candidates(offset, &self.cache, self.namespace_style) {
+                    if !candidates.contains(&candidate) {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+        let pairs = candidates
+            .into_iter()
+            .filter(|candidate| matches_word(candidate, word))
+            .map(|candidate| Pair {
+                display: self.tag(candidate.clone(), CandidateSource::Curated),
+                replacement: candidate + " ",
+            })
+            .collect();
+        (input.len() - word.len(), pairs)
+    }
+
+    fn is_execute(&self, name: &str) -> bool {
+        name.strip_prefix(self.command_char.as_str()) == Some("execute")
+            && self.commands.contains_key(name)
+    }
+
+    /// Complete `/execute` clauses, and the command after `run` like a line of its own
+    fn complete_execute(&self, input: &str, words: &[&str]) -> (usize, Vec<Pair>) {
+        let word = words.last().unwrap();
+        match execute::complete_clauses(&words[1..], &self.cache, self.namespace_style) {
+            ExecuteStep::Candidates(candidates) => {
+                let pairs = candidates
+                    .into_iter()
+                    .filter(|candidate| matches_word(candidate, word))
+                    .map(|candidate| Pair {
+                        display: self.tag(candidate.clone(), CandidateSource::Curated),
+                        replacement: candidate + " ",
+                    })
+                    .collect();
+                (input.len() - word.len(), pairs)
+            }
+            ExecuteStep::Run(index) => {
+                // The command after `run` is written without the command prefix
+                let start = words[1 + index].as_ptr() as usize - input.as_ptr() as usize;
+                let prefix = &self.command_char;
+                let (sub_start, mut pairs) =
+                    self.complete_input(&format!("{prefix}{}", &input[start..]));
+                if sub_start < prefix.len() {
+                    for pair in &mut pairs {
+                        pair.display = pair.display[prefix.len()..].to_string();
+                        pair.replacement = pair.replacement[prefix.len()..].to_string();
+                    }
+                    let typed = self.typed_word.borrow()[prefix.len()..].to_string();
+                    *self.typed_word.borrow_mut() = typed;
+                    (start, pairs)
+                } else {
+                    (start + sub_start - prefix.len(), pairs)
+                }
+            }
+            ExecuteStep::Unknown => (0, Vec::new()),
+        }
+    }
+}
+
+impl Completer for MinecraftCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &RustyContext<'_>,
+    ) -> Result<(usize, Vec<Pair>), ReadlineError> {
+        let (start, mut pairs) = self.complete_input(&line[..pos]);
+        sort_candidates(&mut pairs, &self.typed_word.borrow());
+        Ok((start, pairs))
+    }
+}
+
+/// Text shown after the input: a completion that the right arrow accepts, and for
+/// --preview the command being built on the line below
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandHint {
+    text: String,
+    completion_len: usize,
+}
+
+impl CommandHint {
+    pub fn new(completion: Option<String>, preview: Option<String>) -> Option<CommandHint> {
+        let completion = completion.unwrap_or_default();
+        let completion_len = completion.len();
+        let text = match preview {
+            Some(preview) => format!("{completion}\n{PREVIEW_LABEL}{preview}"),
+            None if completion.is_empty() => return None,
+            None => completion,
+        };
+        Some(CommandHint {
+            text,
+            completion_len,
+        })
+    }
+
+    /// The command on the preview line
+    pub fn preview(&self) -> Option<&str> {
+        self.text
+            .split_once('\n')
+            .map(|(_, preview)| &preview[PREVIEW_LABEL.len()..])
+    }
+}
+
+const PREVIEW_LABEL: &str = "=> ";
+
+impl Hint for CommandHint {
+    fn display(&self) -> &str {
+        &self.text
+    }
+
+    fn completion(&self) -> Option<&str> {
+        (self.completion_len > 0).then(|| &self.text[..self.completion_len])
+    }
+}
+
+impl MinecraftCompleter {
+    /// The rest of the command name, or an explanation of the number expected next
+    fn completion_hint(&self, line: &str) -> Option<String> {
+        if !line.contains(' ') {
+            return self
+                .command_names()
+                .find(|cmd_name| cmd_name.starts_with(line))
+                .map(|cmd_name| cmd_name[line.len()..].to_string());
+        }
+        // Numbers can't be listed, so explain the one expected next instead.
+        // TODO: Add support for hinting other arguments
+        let mut words = argument_words(line);
+        if words.pop() != Some("") {
+            return None;
+        }
+        words[0] = &words[0][self.command_char.len()..];
+        mc_commands::numeric_hint(&words).map(str::to_string)
+    }
+}
+
+impl Hinter for MinecraftCompleter {
+    type Hint = CommandHint;
+    fn hint(&self, line: &str, _pos: usize, _ctx: &RustyContext<'_>) -> Option<CommandHint> {
+        if line.is_empty() || line == self.command_char || !line.starts_with(&self.command_char) {
+            return None;
+        }
+        let preview = match self.preview {
+            true => template::preview(&self.commands, line),
+            false => None,
+        };
+        CommandHint::new(self.completion_hint(line), preview)
+    }
+}
+
+impl Highlighter for MinecraftCompleter {
+    fn highlight_candidate<'c>(
+        &self,
+        candidate: &'c str,
+        _completion: rustyline::CompletionType,
+    ) -> Cow<'c, str> {
+        if self.no_color {
+            return Cow::Borrowed(candidate);
+        }
+        let Some((start, end)) = matched_range(candidate, &self.typed_word.borrow()) else {
+            return Cow::Owned(highlight_command(self, candidate, true));
+        };
+        let emphasized = format!(
+            "{}\x1b[1;4m{}\x1b[22;24m{}", // bold and underlined
+            &candidate[..start],
+            &candidate[start..end],
+            &candidate[end..]
+        );
+        if self.commands.contains_key(candidate) {
+            Cow::Owned(ColorScheme::paint(self.colors.suggestion, &emphasized))
+        } else {
+            Cow::Owned(emphasized)
+        }
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        match hint.split_once('\n') {
+            Some((completion, preview)) if !self.no_color => {
+                let preview = &preview[PREVIEW_LABEL.len()..];
+                Cow::Owned(format!(
+                    "{completion}\n{PREVIEW_LABEL}{}",
+                    highlight_command(self, preview, true)
+                ))
+            }
+            _ => Cow::Borrowed(hint),
+        }
+    }
+
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if self.no_color {
+            return Cow::Borrowed(line);
+        }
+        Cow::Owned(highlight_command(self, line, false))
+    }
+}
+
+/// Length in bytes of the prefix all the strings share
+fn common_prefix_len<'a>(mut strings: impl Iterator<Item = &'a str>) -> usize {
+    let Some(first) = strings.next() else {
+        return 0;
+    };
+    strings.fold(first.len(), |len, s| {
+        first[..len]
+            .char_indices()
+            .zip(s.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(len.min(s.len()), |((idx, _), _)| idx)
+    })
+}
+
+/// Namespaced IDs also match when typed without the namespace
+fn matches_word(candidate: &str, word: &str) -> bool {
+    let bare = candidate.strip_prefix("minecraft:").unwrap_or(candidate);
+    candidate.starts_with(word) || bare.starts_with(word)
+}
+
+/// Order candidates the same way on every run: the typed word itself, then those it
+/// starts, then those matched without their namespace, each alphabetically
+fn sort_candidates(pairs: &mut [Pair], typed: &str) {
+    pairs.sort_by_cached_key(|pair| {
+        let candidate = pair.replacement.trim_end();
+        let rank = match candidate {
+            _ if candidate == typed => 0,
+            _ if candidate.starts_with(typed) => 1,
+            _ => 2,
+        };
+        (rank, pair.display.clone())
+    });
+}
+
+/// Byte range of the candidate that the typed word matched, either its start or, for
+/// namespaced IDs, the start of the bare ID
+pub fn matched_range(candidate: &str, typed: &str) -> Option<(usize, usize)> {
+    if typed.is_empty() {
+        return None;
+    }
+    if candidate.starts_with(typed) {
+        return Some((0, typed.len()));
+    }
+    let bare = candidate.strip_prefix("minecraft:")?;
+    let start = candidate.len() - bare.len();
+    bare.starts_with(typed)
+        .then(|| (start, start + typed.len()))
+}
+
+/// Whether an unknown first word could still be a command, like one a plugin left out
+/// of /help. Without a command prefix any word could.
+fn is_plausible_command(completer: &MinecraftCompleter, word: &str) -> bool {
+    !completer.command_char.is_empty()
+        && word.len() > completer.command_char.len()
+        && word.starts_with(&completer.command_char)
+}
+
+pub fn highlight_command(completer: &MinecraftCompleter, s: &str, is_suggestion: bool) -> String {
+    let mut colored = String::new();
+
+    let words: Vec<&str> = s.split_whitespace().collect();
+    if words.is_empty() {
+        return s.to_string();
+    }
+    let command_found = completer
+        .commands
+        .iter()
+        .any(|(cmd_name, _)| cmd_name == words[0])
+        || completer.aliases.get(words[0]).is_some();
+
+    if command_found {
+        let code = match is_suggestion {
+            true => completer.colors.suggestion,
+            false => completer.colors.command,
+        };
+        colored.push_str(&ColorScheme::paint(code, words[0]));
+    } else if is_plausible_command(completer, words[0]) {
+        colored.push_str(&ColorScheme::paint(
+            completer.colors.unknown_command,
+            words[0],
+        ));
+    } else {
+        colored.push_str(words[0]);
+    }
+    colored.push_str(&s[words[0].len()..]);
+    colored
+}
+
+impl Validator for MinecraftCompleter {
+    fn validate(
+        &self,
+        _ctx: &mut ValidationContext<'_>,
+    ) -> Result<ValidationResult, ReadlineError> {
+        Ok(ValidationResult::Valid(None))
+    }
+}
+
+impl Helper for MinecraftCompleter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::help_parser;
+    use rustyline::history::DefaultHistory;
+
+    const HELP: &str = "/gamemode <gamemode> [<target>]/give <targets> <item> [<count>]\
+/scoreboard (objectives|players)/time (add|query|set)/list [uuids]";
+
+    /// Commands in neither `HELP` nor the bundled list
+    const MORE_HELP: &str = "/forceload (add|remove|query)\n/datapack (list|enable|disable)\n\
+/setidletimeout <minutes>\n/tellraw <targets> <message>\n/pvp <enabled>\n/keepdrops <player> [true|false]";
+
+    fn completer() -> MinecraftCompleter {
+        let help = [
+            help_parser::split_help_entries(HELP, "/").as_str(),
+            Edition::Java.fallback_help(),
+            MORE_HELP,
+        ]
+        .join("\n");
+        let mut commands = help_parser::parse_commands(help, "/");
+        mc_commands::apply_curated(&mut commands, "/");
+        crate::spec::CompletionSpec::bundled().apply_known(&mut commands, "/");
+        MinecraftCompleter {
+            commands,
+            cache: ServerCache::default(),
+            no_color: true,
+            completion_type: CompletionType::List,
+            command_char: "/".to_string(),
+            typed_word: RefCell::default(),
+            aliases: Aliases::default(),
+            namespace_style: NamespaceStyle::default(),
+            edition: Edition::default(),
+            colors: ColorScheme::default(),
+            preview: false,
+            sources: HashMap::new(),
+            debug_completion: false,
+        }
+    }
+
+    /// The replacements offered for the end of `line`, sorted
+    fn replacements(completer: &MinecraftCompleter, line: &str) -> Vec<String> {
+        let history = DefaultHistory::new();
+        let ctx = RustyContext::new(&history);
+        let (_, pairs) = completer.complete(line, line.len(), &ctx).unwrap();
+        let mut replacements: Vec<String> =
+            pairs.into_iter().map(|pair| pair.replacement).collect();
+        replacements.sort();
+        replacements
+    }
+
+    #[test]
+    fn lone_match_lists_what_follows_it() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/scoreboard o"),
+            [
+                "objectives add ",
+                "objectives list ",
+                "objectives modify ",
+                "objectives remove ",
+                "objectives setdisplay ",
+            ]
+        );
+    }
+
+    #[test]
+    fn lone_match_is_offered_when_follow_ups_share_more_than_it() {
+        // Every selector starts with @, listing only them would insert it
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/gamemode surv"),
+            [
+                "survival ",
+                "survival @a ",
+                "survival @e ",
+                "survival @p ",
+                "survival @r ",
+                "survival @s ",
+            ]
+        );
+    }
+
+    #[test]
+    fn completes_spawn_positions_and_angles() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/spawnpoint @p "),
+            ["^ ^ ^ ", "~ ~ ~ "]
+        );
+        assert_eq!(
+            replacements(&completer, "/setworldspawn "),
+            ["^ ^ ^ ", "~ ~ ~ "]
+        );
+        assert_eq!(
+            replacements(&completer, "/setworldspawn ~ ~ ~ "),
+            ["-90 ", "0 ", "180 ", "90 "]
+        );
+    }
+
+    #[test]
+    fn unique_choice_continues_into_the_next_argument() {
+        // Before spectator mode, `s` could only be survival
+        let mut completer = completer();
+        completer.commands = help_parser::parse_commands(
+            "/gamemode (survival|creative|adventure) [<target>]".to_string(),
+            "/",
+        );
+        completer.cache.players = vec!["Steve".to_string()];
+        assert_eq!(
+            replacements(&completer, "/gamemode s"),
+            [
+                "survival @a ",
+                "survival @e ",
+                "survival @p ",
+                "survival @r ",
+                "survival @s ",
+                "survival Steve ",
+            ]
+        );
+    }
+
+    #[test]
+    fn continues_after_an_accepted_choice() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/gamemode s"),
+            ["spectator ", "survival "]
+        );
+        assert_eq!(
+            replacements(&completer, "/gamemode survival "),
+            ["@a ", "@e ", "@p ", "@r ", "@s "]
+        );
+    }
+
+    #[test]
+    fn completes_ban_targets_from_the_cache() {
+        let mut completer = completer();
+        completer.cache.players = vec!["Steve".to_string()];
+        completer.cache.banned_ips = vec!["1.2.3.4".to_string()];
+        assert_eq!(replacements(&completer, "/ban-ip "), ["Steve "]);
+        assert_eq!(replacements(&completer, "/pardon-ip "), ["1.2.3.4 "]);
+    }
+
+    #[test]
+    fn quoted_arguments_count_as_one_word() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, r#"/give @p "minecraft:diamond" "#),
+            ["1 ", "16 ", "32 ", "64 "]
+        );
+        assert!(replacements(&completer, r#"/give @p "a "#).is_empty());
+    }
+
+    #[test]
+    fn completes_summon_entities_and_nbt() {
+        let completer = completer();
+        let entities = replacements(&completer, "/summon ");
+        assert!(entities.contains(&"minecraft:zombie ".to_string()));
+        let nbt = replacements(&completer, "/summon minecraft:zombie ~ ~ ~ ");
+        assert!(nbt.contains(&"{NoAI:1b} ".to_string()));
+        assert!(nbt.contains(&"{} ".to_string()));
+    }
+
+    #[test]
+    fn completes_with_another_command_char() {
+        let mut completer = completer();
+        completer.command_char = "!".to_string();
+        completer.commands =
+            help_parser::parse_commands("!give <targets> <item> [<count>]".to_string(), "!");
+        assert_eq!(replacements(&completer, "!gi"), ["!give "]);
+        assert!(replacements(&completer, "/gi").is_empty());
+        assert_eq!(
+            replacements(&completer, "!give @p diamond "),
+            ["1 ", "16 ", "32 ", "64 "]
+        );
+    }
+
+    #[test]
+    fn completes_scoreboard_subcommands_and_operations() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/scoreboard players "),
+            [
+                "add ",
+                "enable ",
+                "get ",
+                "list ",
+                "operation ",
+                "remove ",
+                "reset ",
+                "set "
+            ]
+        );
+        assert_eq!(
+            replacements(&completer, "/scoreboard players operation @p obj "),
+            ["%= ", "*= ", "+= ", "-= ", "/= ", "< ", "= ", "> ", ">< "]
+        );
+    }
+
+    #[test]
+    fn typed_choice_moves_on_to_the_next_argument() {
+        let completer = completer();
+        for line in ["/gamemode survival ", "/gamemode survival  "] {
+            let offered = replacements(&completer, line);
+            assert!(!offered.contains(&"survival ".to_string()), "{line:?}");
+            assert!(offered.contains(&"@p ".to_string()), "{line:?}");
+        }
+        assert!(replacements(&completer, "/gamemode survival @p ").is_empty());
+    }
+
+    #[test]
+    fn highlights_the_typed_part_of_candidates() {
+        assert_eq!(matched_range("survival", "sur"), Some((0, 3)));
+        assert_eq!(matched_range("minecraft:diamond", "dia"), Some((10, 13)));
+        assert_eq!(
+            matched_range("minecraft:diamond", "minecraft:d"),
+            Some((0, 11))
+        );
+        assert_eq!(matched_range("survival", ""), None);
+        assert_eq!(matched_range("creative", "sur"), None);
+    }
+
+    #[test]
+    fn completes_the_command_after_execute_run() {
+        let completer = completer();
+        let after_run = replacements(&completer, "/execute as @p at @s run ");
+        assert!(after_run.contains(&"give ".to_string()));
+        assert_eq!(
+            replacements(&completer, "/execute as @p at @s run give @p diamond "),
+            ["1 ", "16 ", "32 ", "64 "]
+        );
+    }
+
+    #[test]
+    fn labels_numeric_game_modes() {
+        let completer = completer();
+        assert_eq!(
+            displays(&completer, "/gamemode 1"),
+            ["1 (creative)", "@a", "@e", "@p", "@r", "@s"]
+        );
+    }
+
+    #[test]
+    fn effect_subcommands_take_their_own_arguments() {
+        let completer = completer();
+        assert_eq!(replacements(&completer, "/effect "), ["clear ", "give "]);
+        assert!(replacements(&completer, "/effect clear ").contains(&"@a ".to_string()));
+        assert!(
+            replacements(&completer, "/effect clear @a ").contains(&"minecraft:speed ".to_string())
+        );
+        assert_eq!(
+            replacements(&completer, "/effect give @p minecraft:speed 30 1 "),
+            ["false ", "true "]
+        );
+        assert!(replacements(&completer, "/effect clear @p minecraft:speed ").is_empty());
+    }
+
+    #[test]
+    fn teleport_completion_follows_the_overload_in_play() {
+        let completer = completer();
+        let first = replacements(&completer, "/tp ");
+        assert!(first.contains(&"@p ".to_string()));
+        assert!(first.contains(&"~ ~ ~ ".to_string()));
+        let after_target = replacements(&completer, "/tp @p ");
+        assert!(after_target.contains(&"@s ".to_string()));
+        assert!(after_target.contains(&"~ ~ ~ ".to_string()));
+        assert_eq!(
+            replacements(&completer, "/tp @p ~ ~ ~ "),
+            ["facing ", "~ ~ "]
+        );
+        assert_eq!(replacements(&completer, "/tp 1 2 "), ["^ ", "~ "]);
+    }
+
+    #[test]
+    fn hints_what_a_number_means() {
+        let completer = completer();
+        assert_eq!(
+            completer.completion_hint("/setidletimeout "),
+            Some("<minutes (0 disables)>".to_string())
+        );
+        assert_eq!(
+            completer.completion_hint("/time add "),
+            Some("<time (ticks, or with a unit like 1d, 30s)>".to_string())
+        );
+        assert_eq!(completer.completion_hint("/time add 5"), None);
+        assert_eq!(completer.completion_hint("/gi"), Some("ve".to_string()));
+    }
+
+    #[test]
+    fn completes_ids_in_the_namespace_style() {
+        let mut completer = completer();
+        let offered = |completer: &MinecraftCompleter, line| {
+            let offered = replacements(completer, line);
+            (
+                offered.contains(&"minecraft:diamond_sword ".to_string()),
+                offered.contains(&"diamond_sword ".to_string()),
+            )
+        };
+        assert_eq!(
+            offered(&completer, "/give @p minecraft:diamond_"),
+            (true, false)
+        );
+        // Typing the ID without namespace still finds it
+        assert_eq!(offered(&completer, "/give @p diamond_"), (true, false));
+        completer.namespace_style = NamespaceStyle::Bare;
+        assert_eq!(offered(&completer, "/give @p diamond_"), (false, true));
+        assert_eq!(
+            offered(&completer, "/give @p minecraft:diamond_"),
+            (false, false)
+        );
+        completer.namespace_style = NamespaceStyle::Both;
+        assert_eq!(offered(&completer, "/give @p diamond_"), (true, true));
+        assert_eq!(
+            offered(&completer, "/give @p minecraft:diamond_"),
+            (true, false)
+        );
+    }
+
+    #[test]
+    fn completes_boolean_arguments() {
+        let completer = completer();
+        assert_eq!(replacements(&completer, "/pvp "), ["false ", "true "]);
+        assert_eq!(
+            replacements(&completer, "/keepdrops @p "),
+            ["false ", "true "]
+        );
+        assert_eq!(replacements(&completer, "/keepdrops @p t"), ["true "]);
+    }
+
+    #[test]
+    fn completes_enchantment_levels_up_to_the_max() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/enchant @p minecraft:sharpness "),
+            ["1 ", "2 ", "3 ", "4 ", "5 "]
+        );
+        assert_eq!(
+            replacements(&completer, "/enchant @p minecraft:unbreaking "),
+            ["1 ", "2 ", "3 "]
+        );
+        assert_eq!(
+            replacements(&completer, "/enchant @p minecraft:mending "),
+            ["1 "]
+        );
+    }
+
+    #[test]
+    fn completes_from_a_completion_spec() {
+        let spec: crate::spec::CompletionSpec = toml::from_str(
+            "[arguments]\nspell = [\"fireball\", \"heal\"]\nitem = [\"mymod:ruby\", \"mymod:sapphire\"]\n\
+[commands]\n\"/spell\" = \"(cast|list)\"\n\"/spell cast\" = \"<targets> <spell>\"",
+        )
+        .unwrap();
+        let mut completer = completer();
+        spec.apply(&mut completer.commands, "/");
+        completer.cache.overrides = spec.arguments;
+        assert_eq!(replacements(&completer, "/spell "), ["cast ", "list "]);
+        assert_eq!(
+            replacements(&completer, "/spell cast @p "),
+            ["fireball ", "heal "]
+        );
+        assert_eq!(
+            replacements(&completer, "/give @p "),
+            ["mymod:ruby ", "mymod:sapphire "]
+        );
+    }
+
+    #[test]
+    fn completes_forceload_subcommands_and_chunks() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/forceload "),
+            ["add ", "query ", "remove "]
+        );
+        assert_eq!(
+            replacements(&completer, "/forceload add "),
+            ["~ ~ ", "~ ~ ~ ~ "]
+        );
+        assert_eq!(
+            replacements(&completer, "/forceload remove "),
+            ["all ", "~ ~ "]
+        );
+    }
+
+    #[test]
+    fn gamerules_follow_the_edition() {
+        let mut completer = completer();
+        let rules = replacements(&completer, "/gamerule ");
+        assert!(rules.contains(&"keepInventory ".to_string()));
+        assert!(!rules.contains(&"showcoordinates ".to_string()));
+        assert_eq!(
+            replacements(&completer, "/gamerule keepInventory "),
+            ["false ", "true "]
+        );
+        completer.edition = Edition::Bedrock;
+        let rules = replacements(&completer, "/gamerule ");
+        assert!(rules.contains(&"showcoordinates ".to_string()));
+        assert!(!rules.contains(&"keepInventory ".to_string()));
+        assert_eq!(
+            replacements(&completer, "/gamerule showcoordinates "),
+            ["false ", "true "]
+        );
+    }
+
+    #[test]
+    fn completes_datapack_names_from_the_cache() {
+        let mut completer = completer();
+        assert_eq!(
+            replacements(&completer, "/datapack "),
+            ["disable ", "enable ", "list "]
+        );
+        assert!(replacements(&completer, "/datapack enable ").is_empty());
+        completer.cache.available_datapacks = vec!["bundle".to_string(), "trade".to_string()];
+        completer.cache.enabled_datapacks = vec!["vanilla".to_string(), "castle".to_string()];
+        assert_eq!(
+            replacements(&completer, "/datapack enable "),
+            ["bundle ", "trade "]
+        );
+        assert_eq!(
+            replacements(&completer, "/datapack disable "),
+            ["castle ", "vanilla "]
+        );
+        assert_eq!(
+            replacements(&completer, "/datapack list "),
+            ["available ", "enabled "]
+        );
+    }
+
+    #[test]
+    fn sorts_exact_then_prefix_matches_then_the_rest() {
+        let pair = |candidate: &str| Pair {
+            display: candidate.to_string(),
+            replacement: format!("{candidate} "),
+        };
+        let mut pairs = vec![
+            pair("minecraft:stone"),
+            pair("stone_bricks"),
+            pair("stone"),
+            pair("minecraft:stone_bricks"),
+            pair("stone_axe"),
+        ];
+        sort_candidates(&mut pairs, "stone");
+        let order: Vec<&str> = pairs.iter().map(|pair| pair.display.as_str()).collect();
+        assert_eq!(
+            order,
+            [
+                "stone",
+                "stone_axe",
+                "stone_bricks",
+                "minecraft:stone",
+                "minecraft:stone_bricks"
+            ]
+        );
+    }
+
+    #[test]
+    fn completion_order_is_the_same_every_time() {
+        let completer = completer();
+        let history = DefaultHistory::new();
+        let ctx = RustyContext::new(&history);
+        let order = || {
+            let (_, pairs) = completer.complete("/t", 2, &ctx).unwrap();
+            pairs
+                .into_iter()
+                .map(|pair| pair.replacement)
+                .collect::<Vec<_>>()
+        };
+        let first = order();
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_eq!(first, sorted);
+        assert_eq!(order(), first);
+    }
+
+    #[test]
+    fn completes_title_subcommands_and_text_components() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/title @p "),
+            [
+                "actionbar ",
+                "clear ",
+                "reset ",
+                "subtitle ",
+                "times ",
+                "title "
+            ]
+        );
+        assert_eq!(
+            replacements(&completer, "/title @p title "),
+            ["{\"text\":\"\"} "]
+        );
+        assert_eq!(
+            replacements(&completer, "/title @p actionbar "),
+            ["{\"text\":\"\"} "]
+        );
+        assert!(replacements(&completer, "/title @p clear ").is_empty());
+    }
+
+    #[test]
+    fn completes_nested_grammar_levels() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/data "),
+            ["get ", "merge ", "modify ", "remove "]
+        );
+        assert_eq!(
+            replacements(&completer, "/data get "),
+            ["block ", "entity ", "storage "]
+        );
+        assert!(replacements(&completer, "/data get entity ").contains(&"@s ".to_string()));
+        assert_eq!(
+            replacements(&completer, "/data modify entity @p Inventory "),
+            ["append ", "insert ", "merge ", "prepend ", "set "]
+        );
+        assert_eq!(
+            replacements(&completer, "/loot give @p "),
+            ["fish ", "kill ", "loot ", "mine "]
+        );
+    }
+
+    #[test]
+    fn completes_clear_items_and_counts() {
+        let completer = completer();
+        assert!(replacements(&completer, "/clear ").contains(&"@a ".to_string()));
+        assert!(replacements(&completer, "/clear @a ").contains(&"minecraft:stone ".to_string()));
+        let items = replacements(&completer, "/clear @a minecraft:");
+        assert!(items.contains(&"minecraft:stone ".to_string()));
+        assert!(items.iter().all(|item| item.starts_with("minecraft:")));
+        assert_eq!(
+            replacements(&completer, "/clear @a minecraft:stone "),
+            ["0 ", "1 ", "16 ", "32 ", "64 "]
+        );
+    }
+
+    #[test]
+    fn completes_advancement_keywords_and_ids() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/advancement "),
+            ["grant ", "revoke "]
+        );
+        assert_eq!(
+            replacements(&completer, "/advancement revoke @p "),
+            ["everything ", "from ", "only ", "through ", "until "]
+        );
+        let ids = replacements(&completer, "/advancement grant @p only minecraft:story/");
+        assert!(ids.contains(&"minecraft:story/mine_stone ".to_string()));
+        assert!(ids.iter().all(|id| id.starts_with("minecraft:story/")));
+    }
+
+    #[test]
+    fn completes_particles_then_coordinates() {
+        let completer = completer();
+        let particles = replacements(&completer, "/particle minecraft:fl");
+        assert!(particles.contains(&"minecraft:flame ".to_string()));
+        assert!(particles
+            .iter()
+            .all(|particle| particle.starts_with("minecraft:fl")));
+        assert!(
+            replacements(&completer, "/particle minecraft:flame ").contains(&"~ ~ ~ ".to_string())
+        );
+    }
+
+    #[test]
+    fn completes_attribute_ids_then_operations() {
+        let completer = completer();
+        let ids = replacements(&completer, "/attribute @p ");
+        assert!(ids.contains(&"minecraft:generic.max_health ".to_string()));
+        assert!(ids.iter().all(|id| id.starts_with("minecraft:")));
+        assert_eq!(
+            replacements(&completer, "/attribute @p minecraft:generic.armor "),
+            ["base ", "get ", "modifier "]
+        );
+    }
+
+    #[test]
+    fn hint_puts_the_preview_below_the_completion() {
+        let hint =
+            CommandHint::new(Some("e".to_string()), Some("/give @p <item>".to_string())).unwrap();
+        assert_eq!(hint.completion(), Some("e"));
+        assert_eq!(hint.preview(), Some("/give @p <item>"));
+        assert_eq!(hint.display(), "e\n=> /give @p <item>");
+        assert_eq!(CommandHint::new(None, None), None);
+        let mut completer = completer();
+        completer.preview = true;
+        let history = DefaultHistory::new();
+        let ctx = RustyContext::new(&history);
+        let hint = completer.hint("/give @p ", 9, &ctx).unwrap();
+        assert_eq!(hint.preview(), Some("/give @p <item>"));
+    }
+
+    /// The displays listed for the end of `line`, sorted
+    fn displays(completer: &MinecraftCompleter, line: &str) -> Vec<String> {
+        let history = DefaultHistory::new();
+        let ctx = RustyContext::new(&history);
+        let (_, pairs) = completer.complete(line, line.len(), &ctx).unwrap();
+        let mut displays: Vec<String> = pairs.into_iter().map(|pair| pair.display).collect();
+        displays.sort();
+        displays
+    }
+
+    #[test]
+    fn debug_completion_tags_candidates_with_their_source() {
+        let mut completer = completer();
+        let help = help_parser::parse_commands(help_parser::split_help_entries(HELP, "/"), "/");
+        record_sources(
+            &mut completer.sources,
+            &HashMap::new(),
+            &completer.commands,
+            CandidateSource::Curated,
+        );
+        record_sources(
+            &mut completer.sources,
+            &HashMap::new(),
+            &help,
+            CandidateSource::Help,
+        );
+        completer.debug_completion = true;
+        completer.cache.players = vec!["Steve".to_string()];
+        let names = displays(&completer, "/ti");
+        assert!(names.contains(&"/time [help]".to_string()));
+        assert!(names.contains(&"/title [curated]".to_string()));
+        assert!(displays(&completer, "/give ").contains(&"Steve [server]".to_string()));
+        assert!(displays(&completer, "/give @p minecraft:stone")
+            .contains(&"minecraft:stone [ids]".to_string()));
+    }
+
+    #[test]
+    fn completes_schedule_keywords_and_spec_function_ids() {
+        let spec: crate::spec::CompletionSpec =
+            toml::from_str("[arguments]\nfunction = [\"mypack:init\", \"mypack:tick\"]").unwrap();
+        let mut completer = completer();
+        completer.cache.overrides = spec.arguments;
+        assert_eq!(
+            replacements(&completer, "/schedule "),
+            ["clear ", "function "]
+        );
+        assert_eq!(
+            replacements(&completer, "/schedule function "),
+            ["mypack:init ", "mypack:tick "]
+        );
+        assert_eq!(
+            replacements(&completer, "/schedule function mypack:init 1d "),
+            ["append ", "replace "]
+        );
+        assert_eq!(
+            replacements(&completer, "/function mypack:"),
+            ["mypack:init ", "mypack:tick "]
+        );
+    }
+
+    #[test]
+    fn highlights_known_unknown_and_plain_words_apart() {
+        let completer = completer();
+        let colors = &completer.colors;
+        assert_eq!(
+            highlight_command(&completer, "/give @p stone", false),
+            format!("{} @p stone", ColorScheme::paint(colors.command, "/give"))
+        );
+        assert_eq!(
+            highlight_command(&completer, "/give", true),
+            ColorScheme::paint(colors.suggestion, "/give")
+        );
+        assert_eq!(
+            highlight_command(&completer, "/myplugin reload", false),
+            format!(
+                "{} reload",
+                ColorScheme::paint(colors.unknown_command, "/myplugin")
+            )
+        );
+        assert_eq!(
+            highlight_command(&completer, "hello there", false),
+            "hello there"
+        );
+    }
+
+    #[test]
+    fn offers_named_and_numeric_game_modes() {
+        let completer = completer();
+        assert_eq!(
+            replacements(&completer, "/gamemode "),
+            [
+                "0 ",
+                "1 ",
+                "2 ",
+                "3 ",
+                "adventure ",
+                "creative ",
+                "spectator ",
+                "survival ",
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::palette::KeyBinding;
+use crate::profile::Profile;
+
+/// Settings read from the TOML config file
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Alias name to the command line it expands to
+    pub aliases: BTreeMap<String, String>,
+    /// Named servers to pick from with --profile
+    pub profiles: BTreeMap<String, Profile>,
+    /// Commands recorded with `:macro record`, by macro name
+    pub macros: BTreeMap<String, Vec<String>>,
+    /// Commands inserted or run by a key pressed at an empty prompt, by key name
+    pub keys: BTreeMap<String, KeyBinding>,
+    /// History entries to keep, unless --max-history is given
+    pub max_history: Option<usize>,
+}
+
+/// `$XDG_CONFIG_HOME/minecraft-fancy-rcon-cli/config.toml`, falling back to `~/.config`
+pub fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("minecraft-fancy-rcon-cli").join("config.toml"))
+}
+
+impl Config {
+    /// Load the config file, a missing file is an empty config
+    pub fn load(path: &Path) -> Result<Config> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        toml::from_str(&contents).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_max_history() {
+        let config: Config =
+            toml::from_str("max_history = 50\n[aliases]\nday = \"/time set day\"").unwrap();
+        assert_eq!(config.max_history, Some(50));
+        assert_eq!(config.aliases["day"], "/time set day");
+        assert_eq!(toml::from_str::<Config>("").unwrap().max_history, None);
+    }
+
+    #[test]
+    fn macros_survive_a_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("mc-rcon-{}-config", std::process::id()))
+            .join("config.toml");
+        let mut config = Config::default();
+        config.macros.insert(
+            "night".to_string(),
+            vec!["/time set night".to_string(), "/weather clear".to_string()],
+        );
+        config.save(&path).unwrap();
+        assert_eq!(Config::load(&path).unwrap().macros, config.macros);
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+use crate::rcon::{RconClient, RconError};
+
+/// Outcome of the most recent command, shown by the `{status}` prompt placeholder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// Connected, no command sent yet
+    Connected,
+    /// The last command succeeded
+    Ok,
+    /// The server answered the last command with an error
+    Error,
+    /// Sending the last command failed, the connection looks dropped
+    Dropped,
+    /// The connection was re-established and the last command succeeded
+    Reconnected,
+}
+
+impl ConnectionState {
+    /// State after sending a command. `response` is None when sending failed, and
+    /// otherwise tells whether the server answered with an error.
+    pub fn after_command(response: Option<bool>, reconnected: bool) -> ConnectionState {
+        match response {
+            None => ConnectionState::Dropped,
+            Some(true) => ConnectionState::Error,
+            Some(false) if reconnected => ConnectionState::Reconnected,
+            Some(false) => ConnectionState::Ok,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ConnectionState::Connected => "connected",
+            ConnectionState::Ok => "ok",
+            ConnectionState::Error => "error",
+            ConnectionState::Dropped => "dropped",
+            ConnectionState::Reconnected => "reconnected",
+        }
+    }
+
+    /// A colored dot, or the state's label when colors are disabled
+    pub fn indicator(self, color: bool) -> String {
+        if !color {
+            return self.label().to_string();
+        }
+        let code = match self {
+            ConnectionState::Connected | ConnectionState::Ok => "32", // green
+            ConnectionState::Error => "31",                           // red
+            ConnectionState::Dropped => "90",                         // gray
+            ConnectionState::Reconnected => "33",                     // yellow
+        };
+        format!("\x1b[{code}m●\x1b[0m")
+    }
+}
+
+/// Whether a connection last used at `last_used` has been idle for longer than
+/// `--reconnect-on-idle` allows, and should be replaced before the next command
+pub fn is_idle(last_used: Instant, now: Instant, threshold: Option<Duration>) -> bool {
+    threshold.is_some_and(|threshold| now.saturating_duration_since(last_used) > threshold)
+}
+
+/// Send `cmd`, and replace the client with a new connection from `reconnect` when the
+/// error leaves it unusable, so the next command doesn't read a stale response
+pub fn send_or_reconnect(
+    client: &mut RconClient,
+    cmd: &str,
+    reconnect: impl FnOnce() -> anyhow::Result<RconClient>,
+) -> Result<String, RconError> {
+    let result = client.send_command(cmd);
+    if let Err(e) = &result {
+        if e.breaks_connection() {
+            match reconnect() {
+                Ok(new_client) => *client = new_client,
+                Err(e) => eprintln!("Error: Reconnecting failed: {e}"),
+            }
+        }
+    }
+    result
+}
+
+/// Fill the `{status}` placeholder of a prompt template
+pub fn render_prompt(template: &str, state: ConnectionState, color: bool) -> String {
+    template.replace("{status}", &state.indicator(color))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnects_only_after_the_idle_threshold() {
+        let last_used = Instant::now();
+        let threshold = Some(Duration::from_secs(60));
+        assert!(!is_idle(last_used, last_used, threshold));
+        assert!(!is_idle(
+            last_used,
+            last_used + Duration::from_secs(60),
+            threshold
+        ));
+        assert!(is_idle(
+            last_used,
+            last_used + Duration::from_secs(61),
+            threshold
+        ));
+        assert!(!is_idle(
+            last_used,
+            last_used + Duration::from_secs(3600),
+            None
+        ));
+    }
+
+    #[test]
+    fn prompt_shows_the_state_of_the_last_command() {
+        let state = ConnectionState::after_command(Some(false), true);
+        assert_eq!(state, ConnectionState::Reconnected);
+        assert_eq!(
+            ConnectionState::after_command(Some(true), true),
+            ConnectionState::Error
+        );
+        assert_eq!(
+            ConnectionState::after_command(None, false),
+            ConnectionState::Dropped
+        );
+        assert_eq!(render_prompt("{status} > ", state, false), "reconnected > ");
+        assert_eq!(
+            render_prompt("{status} > ", ConnectionState::Ok, true),
+            "\x1b[32m●\x1b[0m > "
+        );
+    }
+}
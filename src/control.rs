@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use std::ffi::CString;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+use crate::connection;
+use crate::rcon::{RconClient, RconError};
+use crate::serve::Reconnect;
+
+/// The socket file, removed by the signal handler
+static SOCKET_PATH: OnceLock<CString> = OnceLock::new();
+
+/// One reply: the response body, or `Error: ` and the message, ended by an empty line.
+/// Empty lines inside the body are left out so the end can't be mistaken.
+pub fn format_reply(result: Result<String, RconError>) -> String {
+    let text = match result {
+        Ok(body) => body,
+        Err(e) => format!("Error: {e}"),
+    };
+    let mut reply: String = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .flat_map(|line| [line, "\n"])
+        .collect();
+    reply.push('\n');
+    reply
+}
+
+/// Run every non-empty line read as a command and write the reply, until the reader
+/// closes
+pub fn handle_lines(
+    reader: impl BufRead,
+    mut writer: impl Write,
+    mut send: impl FnMut(&str) -> Result<String, RconError>,
+) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        let cmd = line.trim();
+        if cmd.is_empty() {
+            continue;
+        }
+        writer.write_all(format_reply(send(cmd)).as_bytes())?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn handle_connection(
+    client: &Mutex<RconClient>,
+    reconnect: &Reconnect,
+    stream: UnixStream,
+) -> io::Result<()> {
+    let writer = stream.try_clone()?;
+    handle_lines(BufReader::new(stream), writer, |cmd| {
+        connection::send_or_reconnect(&mut client.lock().unwrap(), cmd, reconnect)
+    })
+}
+
+/// Removes the socket file when the server stops with an error
+struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+extern "C" fn remove_and_exit(signal: libc::c_int) {
+    if let Some(path) = SOCKET_PATH.get() {
+        // SAFETY: unlink and _exit are async-signal-safe, and the path is never freed
+        unsafe { libc::unlink(path.as_ptr()) };
+    }
+    unsafe { libc::_exit(128 + signal) };
+}
+
+/// Listen on a Unix domain socket at `path` and run the lines written to it as commands.
+/// A socket file left behind by an earlier run is replaced.
+pub fn run(path: &Path, client: RconClient, reconnect: Box<Reconnect>) -> Result<()> {
+    if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        std::fs::remove_file(path)
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {}", path.display()))?;
+    let _socket_file = SocketFile(path.to_path_buf());
+    if let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) {
+        let _ = SOCKET_PATH.set(c_path);
+        let handler = remove_and_exit as extern "C" fn(libc::c_int);
+        // SAFETY: the handler only calls async-signal-safe functions
+        unsafe {
+            libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+            libc::signal(libc::SIGTERM, handler as libc::sighandler_t);
+        }
+    }
+    println!("Running commands written to {}", path.display());
+    let client = Arc::new(Mutex::new(client));
+    let reconnect: Arc<Reconnect> = Arc::from(reconnect);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                continue;
+            }
+        };
+        let client = Arc::clone(&client);
+        let reconnect = Arc::clone(&reconnect);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(&client, &*reconnect, stream) {
+                eprintln!("Error: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn replies_end_with_an_empty_line() {
+        assert_eq!(format_reply(Ok("a\n\nb".to_string())), "a\nb\n\n");
+        assert_eq!(
+            format_reply(Err(RconError::Timeout(Duration::from_secs(2)))),
+            "Error: No response within 2 s\n\n"
+        );
+    }
+
+    #[test]
+    fn runs_each_non_empty_line() {
+        let mut sent = Vec::new();
+        let mut output = Vec::new();
+        handle_lines(&b"/list\n\n /seed \n"[..], &mut output, |cmd| {
+            sent.push(cmd.to_string());
+            Ok(format!("ran {cmd}"))
+        })
+        .unwrap();
+        assert_eq!(sent, ["/list", "/seed"]);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "ran /list\n\nran /seed\n\n"
+        );
+    }
+}
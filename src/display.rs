@@ -0,0 +1,296 @@
+use regex::Regex;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::banlist::BanSortKey;
+use crate::color_scheme::ColorScheme;
+use crate::format::Locale;
+use crate::Cli;
+
+/// Output settings that can be toggled at runtime with `:set`
+#[derive(Debug, Clone, Default)]
+pub struct DisplayOptions {
+    pub timings: bool,
+    pub no_color: bool,
+    pub echo: bool,
+    pub raw: bool,
+    /// Not toggleable with `:set`
+    pub notify_after: Option<Duration>,
+    pub notify_desktop: bool,
+    pub banlist_sort: Option<BanSortKey>,
+    pub grep: Option<Regex>,
+    pub grep_invert: bool,
+    pub output_dir: Option<PathBuf>,
+    pub output_only_file: bool,
+    pub max_output_lines: Option<usize>,
+    pub retry_empty: bool,
+    pub compact_errors: bool,
+    pub no_newline_fix: bool,
+    /// Prefix of command names, for telling which command a response belongs to
+    pub command_char: String,
+    pub locale: Locale,
+    pub colors: ColorScheme,
+    /// Masked in everything printed, logged or written to --output-dir
+    pub redact: Vec<Regex>,
+}
+
+pub const OPTION_NAMES: &[&str] = &["timings", "no-color", "echo", "raw"];
+
+impl DisplayOptions {
+    pub fn from_cli(cli: &Cli) -> DisplayOptions {
+        DisplayOptions {
+            timings: cli.timings,
+            no_color: cli.no_color,
+            echo: cli.echo,
+            raw: cli.raw,
+            notify_after: cli.notify_after.map(Duration::from_millis),
+            notify_desktop: cli.notify_desktop,
+            banlist_sort: cli.sort,
+            grep: cli.grep.clone(),
+            grep_invert: cli.invert,
+            output_dir: cli.output_dir.clone(),
+            output_only_file: cli.output_only_file,
+            max_output_lines: cli.max_output_lines,
+            retry_empty: cli.retry_empty,
+            compact_errors: cli.compact_errors,
+            no_newline_fix: cli.no_newline_fix,
+            command_char: cli.command_char.clone(),
+            locale: cli.locale,
+            colors: ColorScheme::preset(cli.color_scheme),
+            redact: cli.redact.clone(),
+        }
+    }
+
+    fn option_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "timings" => Some(&mut self.timings),
+            "no-color" => Some(&mut self.no_color),
+            "echo" => Some(&mut self.echo),
+            "raw" => Some(&mut self.raw),
+            _ => None,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: bool) -> Result<(), String> {
+        let option = self.option_mut(name).ok_or_else(|| {
+            format!(
+                "Unknown option '{name}', expected one of: {}",
+                OPTION_NAMES.join(", ")
+            )
+        })?;
+        *option = value;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<bool> {
+        match name {
+            "timings" => Some(self.timings),
+            "no-color" => Some(self.no_color),
+            "echo" => Some(self.echo),
+            "raw" => Some(self.raw),
+            _ => None,
+        }
+    }
+
+    /// One `name on|off` line per option
+    pub fn describe(&self) -> String {
+        OPTION_NAMES
+            .iter()
+            .map(|name| {
+                let value = if self.get(name) == Some(true) {
+                    "on"
+                } else {
+                    "off"
+                };
+                format!("{name} {value}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Replace what any of `patterns` matches with `***`, for `--redact`
+pub fn redact(text: &str, patterns: &[Regex]) -> String {
+    patterns.iter().fold(text.to_string(), |text, pattern| {
+        pattern.replace_all(&text, "***").into_owned()
+    })
+}
+
+/// Keep the first `max` lines of `text`, with a footer saying how many were left out.
+/// The left out lines are returned separately, for `:more`.
+pub fn truncate_lines(text: &str, max: usize) -> (String, Option<String>) {
+    let lines: Vec<&str> = text.lines().collect();
+    if lines.len() <= max {
+        return (text.to_string(), None);
+    }
+    let (shown, rest) = lines.split_at(max);
+    let mut truncated = shown.join("\n");
+    if !truncated.is_empty() {
+        truncated.push('\n');
+    }
+    truncated.push_str(&format!("... ({} more lines, :more to show)", rest.len()));
+    (truncated, Some(rest.join("\n")))
+}
+
+/// Remove ANSI escape sequences: CSI (`ESC [ ... final`), OSC (`ESC ] ... BEL`) and
+/// two-character escapes
+pub fn strip_ansi(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' {
+                        chars.next(); // the `\` of the string terminator
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    stripped
+}
+
+/// Width of `s` on screen, not counting ANSI escape sequences
+pub fn visible_width(s: &str) -> usize {
+    strip_ansi(s).chars().count()
+}
+
+// Space between columns
+const COLUMN_GAP: usize = 2;
+
+/// Arrange `items` in as many columns as fit in `width`, filled top to bottom like `ls`.
+/// Colored items are aligned by their visible width.
+pub fn columns(items: &[String], width: usize) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+    let widths: Vec<usize> = items.iter().map(|item| visible_width(item)).collect();
+    let column_widths = |rows: usize| -> Vec<usize> {
+        widths
+            .chunks(rows)
+            .map(|column| column.iter().copied().max().unwrap_or(0))
+            .collect()
+    };
+    // The fewest rows whose columns fit, one column when even that doesn't
+    let rows = (1..=items.len())
+        .find(|&rows| {
+            let column_widths = column_widths(rows);
+            column_widths.iter().sum::<usize>() + COLUMN_GAP * (column_widths.len() - 1) <= width
+        })
+        .unwrap_or(items.len());
+    let column_widths = column_widths(rows);
+    let mut lines = Vec::with_capacity(rows);
+    for row in 0..rows {
+        let mut line = String::new();
+        for (column, column_width) in column_widths.iter().enumerate() {
+            let Some(item) = items.get(column * rows + row) else {
+                break;
+            };
+            if column > 0 {
+                line.push_str(&" ".repeat(COLUMN_GAP));
+            }
+            line.push_str(item);
+            // No padding after the last item of the row
+            if items.get((column + 1) * rows + row).is_some() {
+                line.push_str(&" ".repeat(column_width - widths[column * rows + row]));
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggles_options_by_name() {
+        let mut options = DisplayOptions::default();
+        options.set("timings", true).unwrap();
+        options.set("no-color", true).unwrap();
+        assert_eq!(options.get("timings"), Some(true));
+        assert_eq!(
+            options.describe(),
+            "timings on\nno-color on\necho off\nraw off"
+        );
+        let e = options.set("colour", true).unwrap_err();
+        assert_eq!(
+            e,
+            "Unknown option 'colour', expected one of: timings, no-color, echo, raw"
+        );
+    }
+
+    #[test]
+    fn strips_escape_sequences() {
+        assert_eq!(strip_ansi("\x1b[31m/say\x1b[0m hi"), "/say hi");
+        assert_eq!(strip_ansi("\x1b]0;title\x07/list"), "/list");
+        assert_eq!(strip_ansi("\x1b]8;;http://x\x1b\\/seed"), "/seed");
+        assert_eq!(strip_ansi("\x1bc/stop"), "/stop");
+    }
+
+    #[test]
+    fn truncates_after_the_line_limit() {
+        assert_eq!(truncate_lines("a\nb", 2), ("a\nb".to_string(), None));
+        assert_eq!(
+            truncate_lines("a\nb\nc\nd", 2),
+            (
+                "a\nb\n... (2 more lines, :more to show)".to_string(),
+                Some("c\nd".to_string())
+            )
+        );
+        assert_eq!(
+            truncate_lines("a\nb", 0),
+            (
+                "... (2 more lines, :more to show)".to_string(),
+                Some("a\nb".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn arranges_colored_names_in_columns() {
+        let items: Vec<String> = ["give", "\x1b[32mkill\x1b[0m", "list", "say", "teleport"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(visible_width(&items[1]), 4);
+        assert_eq!(
+            columns(&items, 20),
+            "give  list  teleport\n\x1b[32mkill\x1b[0m  say"
+        );
+        // Too narrow for two columns, one name per line
+        assert_eq!(columns(&items, 5).lines().count(), 5);
+        assert_eq!(columns(&[], 80), "");
+    }
+
+    #[test]
+    fn redacts_every_pattern() {
+        let patterns = [
+            Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").unwrap(),
+            Regex::new("Steve").unwrap(),
+        ];
+        assert_eq!(
+            redact("Steve joined from 10.0.0.5, then 192.168.1.20", &patterns),
+            "*** joined from ***, then ***"
+        );
+        assert_eq!(redact("nothing secret", &[]), "nothing secret");
+    }
+}
@@ -0,0 +1,227 @@
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::edition::Edition;
+use crate::help_parser;
+use crate::proxy::Proxy;
+use crate::rcon::{RconClient, RconError};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of one diagnostic step, with what it found or why it failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<String, String>,
+    pub elapsed: Duration,
+    /// What to look at when the step fails
+    pub hint: &'static str,
+}
+
+/// `PASS`/`FAIL`, the step and its timing, then the details and, on failure, the hint
+pub fn format_check(check: &Check) -> String {
+    let elapsed = check.elapsed.as_millis();
+    match &check.result {
+        Ok(detail) => format!("PASS  {} ({elapsed} ms): {detail}", check.name),
+        Err(e) => format!(
+            "FAIL  {} ({elapsed} ms): {e}\n      Hint: {}",
+            check.name, check.hint
+        ),
+    }
+}
+
+/// Process exit code for a doctor run: 0 when every check passed
+pub fn exit_code(checks: &[Check]) -> i32 {
+    match checks.iter().all(|check| check.result.is_ok()) {
+        true => 0,
+        false => 1,
+    }
+}
+
+/// Settings the checks depend on
+pub struct Target<'a> {
+    pub addr: &'a str,
+    pub password: &'a str,
+    pub proxy: Option<&'a Proxy>,
+    pub command_char: &'a str,
+    pub edition: Edition,
+}
+
+/// Run one step and record it, returning its value for the steps after it
+fn step<T>(
+    checks: &mut Vec<Check>,
+    name: &'static str,
+    hint: &'static str,
+    run: impl FnOnce() -> Result<(T, String), String>,
+) -> Option<T> {
+    let start = Instant::now();
+    let result = run();
+    let elapsed = start.elapsed();
+    let (value, result) = match result {
+        Ok((value, detail)) => (Some(value), Ok(detail)),
+        Err(e) => (None, Err(e)),
+    };
+    checks.push(Check {
+        name,
+        result,
+        elapsed,
+        hint,
+    });
+    value
+}
+
+/// Check each step of getting to a working shell in order, stopping at the first failure
+/// since the later steps depend on it
+pub fn run(target: &Target) -> Vec<Check> {
+    let mut checks = Vec::new();
+    // Through a proxy the server address is only resolved by the proxy
+    let direct = match target.proxy {
+        Some(_) => None,
+        None => match step(
+            &mut checks,
+            "Resolve address",
+            "Check the host name, and give the port as host:port (25575 by default)",
+            || {
+                let addr: SocketAddr = target
+                    .addr
+                    .to_socket_addrs()
+                    .map_err(|e| e.to_string())?
+                    .next()
+                    .ok_or_else(|| "No addresses found".to_string())?;
+                Ok((addr, addr.to_string()))
+            },
+        ) {
+            Some(addr) => Some(addr),
+            None => return checks,
+        },
+    };
+    let stream = step(
+        &mut checks,
+        "Connect",
+        "Make sure enable-rcon=true is set in server.properties and rcon.port is reachable",
+        || {
+            let stream = match (target.proxy, direct) {
+                (Some(proxy), _) => proxy.connect(target.addr),
+                (None, Some(addr)) => TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT),
+                (None, None) => unreachable!("the address was resolved"),
+            }
+            .map_err(|e| e.to_string())?;
+            let detail = match target.proxy {
+                Some(proxy) => format!("{} through {proxy}", target.addr),
+                None => target.addr.to_string(),
+            };
+            Ok((stream, detail))
+        },
+    );
+    let Some(stream) = stream else {
+        return checks;
+    };
+    let mut client = RconClient::from_stream(stream);
+    if let Err(e) = client.set_read_timeout(Some(CONNECT_TIMEOUT)) {
+        log::warn!("Failed to set a read timeout: {e}");
+    }
+    let authenticated = step(
+        &mut checks,
+        "Authenticate",
+        "Compare the password with rcon.password in server.properties",
+        || match client.authenticate(target.password) {
+            Ok(()) => Ok(((), "Password accepted".to_string())),
+            Err(RconError::AuthenticationFailed) => Err("Wrong password".to_string()),
+            Err(e) => Err(e.to_string()),
+        },
+    );
+    if authenticated.is_none() {
+        return checks;
+    }
+    let prefix = target.command_char;
+    let sent = step(
+        &mut checks,
+        "Send a command",
+        "The server accepted the login but not the command, check its log and --command-char",
+        || {
+            let body = client
+                .send_command(&format!("{prefix}list"))
+                .map_err(|e| e.to_string())?;
+            Ok((
+                (),
+                format!("{prefix}list answered with {} bytes", body.len()),
+            ))
+        },
+    );
+    if sent.is_none() {
+        return checks;
+    }
+    step(
+        &mut checks,
+        "Fetch and parse /help",
+        "Completion falls back to the bundled command list, check --edition and --command-char",
+        || {
+            let body = client
+                .send_command(&format!("{prefix}help"))
+                .map_err(|e| e.to_string())?;
+            let help = match target.edition {
+                Edition::Java => help_parser::split_help_entries(&body, prefix),
+                Edition::Bedrock => help_parser::normalize_bedrock_help(&body, prefix),
+            };
+            match help_parser::parse_commands(help, prefix).len() {
+                0 => Err(format!("No commands found in {} bytes", body.len())),
+                count => Ok(((), format!("{count} commands"))),
+            }
+        },
+    );
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(result: Result<&str, &str>) -> Check {
+        Check {
+            name: "Authenticate",
+            result: result.map(str::to_string).map_err(str::to_string),
+            elapsed: Duration::from_millis(12),
+            hint: "Check rcon.password in server.properties",
+        }
+    }
+
+    #[test]
+    fn formats_passed_and_failed_checks() {
+        assert_eq!(
+            format_check(&check(Ok("accepted"))),
+            "PASS  Authenticate (12 ms): accepted"
+        );
+        assert_eq!(
+            format_check(&check(Err("wrong password"))),
+            "FAIL  Authenticate (12 ms): wrong password\n      \
+Hint: Check rcon.password in server.properties"
+        );
+    }
+
+    #[test]
+    fn fails_when_any_check_failed() {
+        assert_eq!(exit_code(&[]), 0);
+        assert_eq!(exit_code(&[check(Ok("")), check(Ok(""))]), 0);
+        assert_eq!(exit_code(&[check(Ok("")), check(Err(""))]), 1);
+    }
+
+    #[test]
+    fn stops_at_the_first_failed_step() {
+        // Nothing listens on a port just released
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+        let checks = run(&Target {
+            addr: &addr,
+            password: "pw",
+            proxy: None,
+            command_char: "/",
+            edition: Edition::Java,
+        });
+        let names: Vec<&str> = checks.iter().map(|check| check.name).collect();
+        assert_eq!(names, ["Resolve address", "Connect"]);
+        assert_eq!(exit_code(&checks), 1);
+    }
+}
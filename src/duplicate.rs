@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+/// How soon a repeat of the same command counts as an accidental double send
+pub const DUPLICATE_WINDOW: Duration = Duration::from_secs(2);
+
+/// Remembers the last command sent, to catch the same one submitted again right away
+#[derive(Debug, Default)]
+pub struct DuplicateGuard {
+    last: Option<(String, Instant)>,
+}
+
+impl DuplicateGuard {
+    /// Whether sending `cmd` at `now` repeats the last sent command within the window
+    pub fn is_duplicate(&self, cmd: &str, now: Instant) -> bool {
+        self.last.as_ref().is_some_and(|(last, sent)| {
+            last == cmd && now.saturating_duration_since(*sent) < DUPLICATE_WINDOW
+        })
+    }
+
+    pub fn record(&mut self, cmd: &str, now: Instant) {
+        self.last = Some((cmd.to_string(), now));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_the_same_command_only_within_the_window() {
+        let start = Instant::now();
+        let mut guard = DuplicateGuard::default();
+        assert!(!guard.is_duplicate("/give @a diamond 64", start));
+        guard.record("/give @a diamond 64", start);
+        let soon = start + Duration::from_millis(500);
+        assert!(guard.is_duplicate("/give @a diamond 64", soon));
+        assert!(!guard.is_duplicate("/give @a diamond 63", soon));
+        assert!(!guard.is_duplicate("/give @a diamond 64", start + DUPLICATE_WINDOW));
+    }
+
+    #[test]
+    fn the_window_restarts_with_each_send() {
+        let start = Instant::now();
+        let mut guard = DuplicateGuard::default();
+        guard.record("/list", start);
+        guard.record("/list", start + Duration::from_secs(5));
+        assert!(guard.is_duplicate("/list", start + Duration::from_secs(6)));
+    }
+}
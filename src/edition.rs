@@ -0,0 +1,109 @@
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Edition {
+    #[default]
+    Java,
+    /// Bedrock Dedicated Server, or a bridge like GeyserMC
+    Bedrock,
+}
+
+/// Usage lines for common commands, in the Java /help format, used when the server's
+/// /help can't be fetched or parsed
+const JAVA_FALLBACK_HELP: &str = "\
+/advancement (grant|revoke)
+/attribute <target> <attribute> (get|base|modifier)
+/ban <targets> [<reason>]
+/ban-ip <target> [<reason>]
+/banlist [ips|players]
+/clear [<targets>] [<item>] [<maxCount>]
+/data (get|merge|modify|remove)
+/defaultgamemode <gamemode>
+/deop <targets>
+/difficulty [peaceful|easy|normal|hard]
+/effect (give|clear)
+/enchant <targets> <enchantment> [<level>]
+/execute (run|if|unless|as|at|store|positioned|rotated|facing|align|anchored|in|summon|on)
+/function <name> [<arguments>]
+/gamemode <gamemode> [<target>]
+/gamerule <rule> [<value>]
+/give <targets> <item> [<count>]
+/help [<command>]
+/kick <targets> [<reason>]
+/kill [<targets>]
+/list [uuids]
+/loot (give|insert|spawn|replace)
+/op <targets>
+/pardon <targets>
+/pardon-ip <target>
+/particle <name> [<pos>]
+/say <message>
+/schedule (function|clear)
+/scoreboard (objectives|players)
+/seed
+/setworldspawn [<pos>] [<angle>]
+/spawnpoint [<targets>] [<pos>] [<angle>]
+/stop
+/summon <entity> [<pos>] [<nbt>]
+/teleport <targets> <location>
+/tell <targets> <message>
+/time (add|query|set)
+/title <targets> (clear|reset|title|subtitle|actionbar|times)
+/tp <targets> <location>
+/weather (clear|rain|thunder) [<duration>]
+/whitelist (add|remove|list|on|off|reload)";
+
+/// Bedrock names its allow list differently and has no /banlist, /pardon or /seed
+const BEDROCK_FALLBACK_HELP: &str = "\
+/ability <targets> [<ability>] [<value>]
+/allowlist (add|remove|list|on|off|reload)
+/clear [<targets>] [<item>] [<maxCount>]
+/deop <targets>
+/difficulty <difficulty>
+/effect <targets> <effect> [<seconds>] [<amplifier>] [<hideParticles>]
+/enchant <targets> <enchantment> [<level>]
+/execute (run|if|unless|as|at|positioned|rotated|facing|align|anchored|in)
+/gamemode <gamemode> [<targets>]
+/gamerule [<rule>] [<value>]
+/give <targets> <item> [<amount>]
+/help [<page>]
+/kick <targets> [<reason>]
+/kill [<targets>]
+/list
+/op <targets>
+/say <message>
+/scoreboard (objectives|players)
+/setworldspawn [<pos>]
+/spawnpoint [<targets>] [<pos>]
+/stop
+/summon <entity> [<pos>]
+/teleport <targets> <location>
+/tell <targets> <message>
+/time (add|query|set)
+/tp <targets> <location>
+/weather (clear|rain|thunder|query) [<duration>]";
+
+impl Edition {
+    pub fn fallback_help(self) -> &'static str {
+        match self {
+            Edition::Java => JAVA_FALLBACK_HELP,
+            Edition::Bedrock => BEDROCK_FALLBACK_HELP,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::help_parser::parse_commands;
+
+    #[test]
+    fn fallback_lists_follow_the_edition() {
+        let java = parse_commands(Edition::Java.fallback_help().to_string(), "/");
+        let bedrock = parse_commands(Edition::Bedrock.fallback_help().to_string(), "/");
+        assert!(java.contains_key("/attribute"));
+        assert!(!bedrock.contains_key("/attribute"));
+        assert!(bedrock.contains_key("/give"));
+    }
+}
@@ -0,0 +1,244 @@
+use clap::ValueEnum;
+use std::path::Path;
+
+use crate::cli::{self, Cli};
+use crate::profile::Profile;
+
+/// Shown instead of a password
+const MASKED: &str = "********";
+
+/// One line of `--print-config`: a setting's effective value and, for settings with
+/// several inputs, the one it was taken from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub name: &'static str,
+    pub value: String,
+    pub source: Option<String>,
+}
+
+fn setting(name: &'static str, value: impl Into<String>, source: Option<String>) -> Setting {
+    Setting {
+        name,
+        value: value.into(),
+        source,
+    }
+}
+
+fn value_name(value: impl ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn path_or_none(path: Option<&Path>) -> String {
+    path.map_or("<none>".to_string(), |path| path.display().to_string())
+}
+
+/// The settings a run would use, following the same precedence as connecting: a profile,
+/// then the flag, its file flag and the environment. Passwords are masked.
+/// `env` looks up environment variables.
+pub fn resolve(
+    cli: &Cli,
+    config_path: Option<&Path>,
+    profile: Option<(&str, &Profile)>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Vec<Setting> {
+    let config_source = match cli.config.is_some() {
+        true => "--config",
+        false => "default",
+    };
+    let (address, address_source) = match (profile, &cli.address, &cli.address_file) {
+        (Some((name, profile)), _, _) => (profile.address.clone(), Some(format!("profile {name}"))),
+        (None, Some(address), _) => (address.clone(), Some("--address".to_string())),
+        (None, None, Some(path)) => (
+            cli::read_value_file(path).unwrap_or_else(|e| format!("<{e}>")),
+            Some(format!("--address-file {}", path.display())),
+        ),
+        (None, None, None) => match env(cli::ADDRESS_ENV) {
+            Some(address) => (address, Some(cli::ADDRESS_ENV.to_string())),
+            None => ("<not set>".to_string(), None),
+        },
+    };
+    let profile_password =
+        profile.and_then(
+            |(name, profile)| match (&profile.password, &profile.password_file) {
+                (Some(_), _) => Some(format!("profile {name}")),
+                (None, Some(path)) => {
+                    Some(format!("profile {name} password_file {}", path.display()))
+                }
+                (None, None) => None,
+            },
+        );
+    let (password, password_source) = if cli.no_auth {
+        ("<none>", Some("--no-auth".to_string()))
+    } else if cli.password.is_some() {
+        (MASKED, Some("--password".to_string()))
+    } else if let Some(path) = &cli.password_file {
+        (MASKED, Some(format!("--password-file {}", path.display())))
+    } else if let Some(source) = profile_password {
+        (MASKED, Some(source))
+    } else if env(cli::PASSWORD_ENV).is_some() {
+        (MASKED, Some(cli::PASSWORD_ENV.to_string()))
+    } else {
+        ("<not set, asked for when connecting>", None)
+    };
+    let color = match cli.no_color {
+        true => "off",
+        false => "on",
+    };
+    let format = match cli.raw {
+        true => "raw",
+        false if cli.compact_errors => "formatted, compact errors",
+        false => "formatted",
+    };
+    vec![
+        setting(
+            "config",
+            path_or_none(config_path),
+            Some(config_source.to_string()),
+        ),
+        setting("profile", profile.map_or("<none>", |(name, _)| name), None),
+        setting("address", address, address_source),
+        setting("password", password, password_source),
+        setting(
+            "proxy",
+            cli.proxy
+                .as_ref()
+                .map_or("<none>".to_string(), |proxy| proxy.to_string()),
+            None,
+        ),
+        setting("edition", value_name(cli.edition), None),
+        setting("command-char", cli.command_char.clone(), None),
+        setting(
+            "command-timeout",
+            cli.command_timeout
+                .map_or("<none>".to_string(), |secs| format!("{secs}s")),
+            None,
+        ),
+        setting("color", color, None),
+        setting("format", format, None),
+        setting("locale", value_name(cli.locale), None),
+        setting(
+            "completion-spec",
+            path_or_none(cli.completion_spec.as_deref()),
+            None,
+        ),
+        setting(
+            "history-file",
+            path_or_none(cli::history_path(cli).as_deref()),
+            None,
+        ),
+        setting("log-file", path_or_none(cli.log_file.as_deref()), None),
+        setting("tee", path_or_none(cli.tee.as_deref()), None),
+    ]
+}
+
+/// `name  value  (from source)` lines, with the values aligned
+pub fn format_settings(settings: &[Setting]) -> String {
+    let width = settings.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    settings
+        .iter()
+        .map(|s| match &s.source {
+            Some(source) => format!("{:width$}  {}  (from {source})", s.name, s.value),
+            None => format!("{:width$}  {}", s.name, s.value),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::Parser;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::parse_from(std::iter::once("mc-rcon").chain(args.iter().copied()))
+    }
+
+    fn profile() -> Profile {
+        Profile {
+            address: "survival.example:25575".to_string(),
+            password: Some("hunter2".to_string()),
+            password_file: None,
+            on_connect: Vec::new(),
+        }
+    }
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            cli::ADDRESS_ENV => Some("env.example:25575".to_string()),
+            cli::PASSWORD_ENV => Some("from-env".to_string()),
+            _ => None,
+        }
+    }
+
+    /// The value and source of one setting
+    fn lookup(settings: &[Setting], name: &str) -> (String, Option<String>) {
+        let setting = settings.iter().find(|s| s.name == name).unwrap();
+        (setting.value.clone(), setting.source.clone())
+    }
+
+    #[test]
+    fn profile_then_flag_then_environment() {
+        let profile = profile();
+        let settings = resolve(
+            &cli(&["-a", "flag:1"]),
+            None,
+            Some(("survival", &profile)),
+            env,
+        );
+        assert_eq!(
+            lookup(&settings, "address"),
+            (
+                "survival.example:25575".to_string(),
+                Some("profile survival".to_string())
+            )
+        );
+        let settings = resolve(&cli(&["-a", "flag:1"]), None, None, env);
+        assert_eq!(
+            lookup(&settings, "address"),
+            ("flag:1".to_string(), Some("--address".to_string()))
+        );
+        let settings = resolve(&cli(&[]), None, None, env);
+        assert_eq!(
+            lookup(&settings, "address"),
+            (
+                "env.example:25575".to_string(),
+                Some(cli::ADDRESS_ENV.to_string())
+            )
+        );
+        let settings = resolve(&cli(&[]), None, None, |_| None);
+        assert_eq!(lookup(&settings, "address").0, "<not set>");
+    }
+
+    #[test]
+    fn passwords_are_masked_whatever_their_source() {
+        let profile = profile();
+        let sources = [
+            resolve(
+                &cli(&["-p", "secret"]),
+                None,
+                Some(("survival", &profile)),
+                env,
+            ),
+            resolve(&cli(&[]), None, Some(("survival", &profile)), env),
+            resolve(&cli(&[]), None, None, env),
+        ];
+        let found: Vec<(String, Option<String>)> = sources
+            .iter()
+            .map(|settings| lookup(settings, "password"))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (MASKED.to_string(), Some("--password".to_string())),
+                (MASKED.to_string(), Some("profile survival".to_string())),
+                (MASKED.to_string(), Some(cli::PASSWORD_ENV.to_string())),
+            ]
+        );
+        let printed = format_settings(&sources[0]);
+        assert!(!printed.contains("secret") && !printed.contains("hunter2"));
+    }
+}
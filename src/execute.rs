@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use crate::mc_commands::{choice, optional, required, NamespaceStyle};
+use crate::server_cache::ServerCache;
+use crate::Argument;
+
+/// Keywords that can start an `/execute` clause
+pub const CLAUSE_KEYWORDS: &[&str] = &[
+    "align",
+    "anchored",
+    "as",
+    "at",
+    "facing",
+    "if",
+    "in",
+    "on",
+    "positioned",
+    "rotated",
+    "run",
+    "store",
+    "summon",
+    "unless",
+];
+
+const DATA_TYPES: &[&str] = &["byte", "short", "int", "long", "float", "double"];
+
+/// Clause shapes keyed by their keywords. A clause with longer keys below it, like
+/// `store` and `store result`, continues with one of those keywords.
+fn clauses() -> HashMap<String, Vec<Argument>> {
+    let mut clauses: Vec<(String, Vec<Argument>)> = vec![
+        ("align".into(), vec![required("axes")]),
+        ("anchored".into(), vec![choice(&["eyes", "feet"])]),
+        ("as".into(), vec![required("targets")]),
+        ("at".into(), vec![required("targets")]),
+        ("facing".into(), vec![required("pos")]),
+        (
+            "facing entity".into(),
+            vec![required("targets"), choice(&["eyes", "feet"])],
+        ),
+        ("in".into(), vec![required("dimension")]),
+        (
+            "on".into(),
+            vec![choice(&[
+                "attacker",
+                "controller",
+                "leasher",
+                "origin",
+                "owner",
+                "passengers",
+                "target",
+                "vehicle",
+            ])],
+        ),
+        ("positioned".into(), vec![required("pos")]),
+        ("positioned as".into(), vec![required("targets")]),
+        ("positioned over".into(), vec![required("heightmap")]),
+        ("rotated".into(), vec![required("rotation")]),
+        ("rotated as".into(), vec![required("targets")]),
+        ("summon".into(), vec![required("entity")]),
+        ("store".into(), vec![]),
+    ];
+    for mode in ["result", "success"] {
+        clauses.push((format!("store {mode}"), vec![]));
+        let stored_value = |holder: &str| {
+            vec![
+                required(holder),
+                required("path"),
+                choice(DATA_TYPES),
+                required("scale"),
+            ]
+        };
+        clauses.push((format!("store {mode} block"), stored_value("pos")));
+        clauses.push((format!("store {mode} entity"), stored_value("target")));
+        clauses.push((format!("store {mode} storage"), stored_value("storage")));
+        clauses.push((
+            format!("store {mode} bossbar"),
+            vec![required("id"), choice(&["value", "max"])],
+        ));
+        clauses.push((
+            format!("store {mode} score"),
+            vec![required("targets"), required("objective")],
+        ));
+    }
+    for condition in ["if", "unless"] {
+        let tests = [
+            ("", vec![]),
+            (" block", vec![required("pos"), required("block")]),
+            (
+                " blocks",
+                vec![
+                    required("pos"),
+                    required("pos"),
+                    required("pos"),
+                    choice(&["all", "masked"]),
+                ],
+            ),
+            (" biome", vec![required("pos"), required("biome")]),
+            (" data", vec![]),
+            (" data block", vec![required("pos"), required("path")]),
+            (" data entity", vec![required("target"), required("path")]),
+            (" data storage", vec![required("storage"), required("path")]),
+            (" dimension", vec![required("dimension")]),
+            (" entity", vec![required("targets")]),
+            (" function", vec![required("function")]),
+            (" loaded", vec![required("pos")]),
+            (" predicate", vec![required("predicate")]),
+            (
+                " score",
+                vec![
+                    required("target"),
+                    required("targetObjective"),
+                    choice(&["<", "<=", "=", ">", ">=", "matches"]),
+                    optional("source"),
+                    optional("sourceObjective"),
+                ],
+            ),
+        ];
+        for (test, args) in tests {
+            clauses.push((format!("{condition}{test}"), args));
+        }
+    }
+    clauses.into_iter().collect()
+}
+
+/// What can be typed at the end of an `/execute` command line
+#[derive(Debug, PartialEq)]
+pub enum ExecuteStep {
+    /// Candidates for the last word
+    Candidates(Vec<String>),
+    /// The clauses ended with `run`, the command starts at this word index
+    Run(usize),
+    /// The clauses don't follow the grammar
+    Unknown,
+}
+
+/// Keywords that continue the clause at `path`
+fn sub_keywords(clauses: &HashMap<String, Vec<Argument>>, path: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = clauses
+        .keys()
+        .filter_map(|key| key.strip_prefix(path)?.strip_prefix(' '))
+        .filter(|rest| !rest.contains(' '))
+        .map(str::to_string)
+        .collect();
+    keywords.sort();
+    keywords
+}
+
+/// Walk the clauses typed after `/execute`, the last word being the one to complete
+pub fn complete_clauses(
+    words: &[&str],
+    cache: &ServerCache,
+    namespace_style: NamespaceStyle,
+) -> ExecuteStep {
+    let clauses = clauses();
+    let Some(last) = words.len().checked_sub(1) else {
+        return ExecuteStep::Unknown;
+    };
+    let mut i = 0;
+    while i < last {
+        if words[i] == "run" {
+            return ExecuteStep::Run(i + 1);
+        }
+        let mut path = words[i].to_string();
+        let Some(mut args) = clauses.get(&path) else {
+            return ExecuteStep::Unknown;
+        };
+        i += 1;
+        let (mut arg_idx, mut offset) = (0, 0);
+        loop {
+            let at_start = arg_idx == 0 && offset == 0;
+            let keywords = if at_start {
+                sub_keywords(&clauses, &path)
+            } else {
+                Vec::new()
+            };
+            if arg_idx == args.len() && keywords.is_empty() {
+                break; // The clause is complete
+            }
+            if i == last {
+                let mut candidates = keywords;
+                if let Some(arg) = args.get(arg_idx) {
+                    candidates.extend(arg.candidates(offset, cache, namespace_style));
+                    if matches!(arg, Argument::Optional(_)) {
+                        candidates.extend(CLAUSE_KEYWORDS.iter().map(|s| s.to_string()));
+                    }
+                }
+                return ExecuteStep::Candidates(candidates);
+            }
+            if keywords.iter().any(|keyword| keyword == words[i]) {
+                path = format!("{path} {}", words[i]);
+                args = &clauses[&path];
+                i += 1;
+                continue;
+            }
+            let Some(arg) = args.get(arg_idx) else {
+                return ExecuteStep::Unknown;
+            };
+            // Trailing optional arguments end where the next clause starts
+            if matches!(arg, Argument::Optional(_)) && CLAUSE_KEYWORDS.contains(&words[i]) {
+                break;
+            }
+            offset += 1;
+            i += 1;
+            if offset == arg.width() {
+                arg_idx += 1;
+                offset = 0;
+            }
+        }
+    }
+    ExecuteStep::Candidates(CLAUSE_KEYWORDS.iter().map(|s| s.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(line: &str) -> ExecuteStep {
+        let words: Vec<&str> = line.split(' ').collect();
+        complete_clauses(&words, &ServerCache::default(), NamespaceStyle::default())
+    }
+
+    #[test]
+    fn walks_chained_clauses() {
+        assert_eq!(
+            step("as @p "),
+            ExecuteStep::Candidates(CLAUSE_KEYWORDS.iter().map(|s| s.to_string()).collect())
+        );
+        assert_eq!(
+            step("anchored "),
+            ExecuteStep::Candidates(vec!["eyes".to_string(), "feet".to_string()])
+        );
+        assert_eq!(step("as @p at @s run "), ExecuteStep::Run(5));
+        assert_eq!(step("bogus @p "), ExecuteStep::Unknown);
+    }
+
+    #[test]
+    fn continues_clauses_with_their_sub_keywords() {
+        let ExecuteStep::Candidates(candidates) = step("store result ") else {
+            panic!("expected candidates");
+        };
+        assert_eq!(
+            candidates,
+            ["block", "bossbar", "entity", "score", "storage"]
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use clap::ValueEnum;
+
+use crate::color_scheme::ColorScheme;
+use crate::command::CommandResult;
+use crate::display::{self, DisplayOptions};
+use crate::{banlist, grep, help_parser, server_cache, text_component};
+
+pub const ERROR_PREFIXES: &[&str] = &[
+    "Unknown or incomplete command, see below for error",
+    "Incorrect argument for command",
+];
+
+/// Language the server answers in, for recognizing its translated error messages
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Es,
+    Fr,
+    Pt,
+    Ru,
+    Zh,
+}
+
+impl Locale {
+    /// The vanilla error messages in this language, besides the English ones
+    pub fn error_prefixes(self) -> &'static [&'static str] {
+        match self {
+            Locale::En => &[],
+            Locale::De => &[
+                "Unbekannter oder unvollständiger Befehl, siehe unten für Fehler",
+                "Ungültiges Argument für Befehl",
+            ],
+            Locale::Es => &[
+                "Comando desconocido o incompleto, revisa abajo el error",
+                "Argumento incorrecto para el comando",
+            ],
+            Locale::Fr => &[
+                "Commande inconnue ou incomplète, voir ci-dessous pour l'erreur",
+                "Argument incorrect pour la commande",
+            ],
+            Locale::Pt => &[
+                "Comando desconhecido ou incompleto, veja o erro abaixo",
+                "Argumento incorreto para o comando",
+            ],
+            Locale::Ru => &[
+                "Неизвестная или неполная команда, ошибка ниже",
+                "Неверный аргумент для команды",
+            ],
+            Locale::Zh => &["未知或不完整的命令，错误见下", "错误的命令参数"],
+        }
+    }
+}
+
+/// The error message a response body starts with, in English or the server's language
+pub fn error_prefix(body: &str, locale: Locale) -> Option<&'static str> {
+    ERROR_PREFIXES
+        .iter()
+        .chain(locale.error_prefixes())
+        .find(|prefix| body.starts_with(*prefix))
+        .copied()
+}
+
+/// Whether a response body is one of the server's error messages
+pub fn is_error_response(body: &str, locale: Locale) -> bool {
+    error_prefix(body, locale).is_some()
+}
+
+pub fn format_generic_response(body: &str, locale: Locale) -> String {
+    if let Some(prefix) = error_prefix(body, locale) {
+        let suffix = &body[prefix.len()..];
+        format!("{}\n{}", prefix, suffix.trim_start())
+    } else {
+        body.to_string()
+    }
+}
+
+pub fn format_response(options: &DisplayOptions, cmd: &str, body: &str) -> String {
+    let command_char = options.command_char.as_str();
+    // The command as typed, without its prefix
+    let bare = cmd.strip_prefix(command_char).unwrap_or(cmd);
+    let name = bare.split(' ').next().unwrap_or_default();
+    if options.no_newline_fix && help_parser::is_help_command(cmd, command_char) {
+        body.to_string()
+    } else if help_parser::is_help_command(cmd, command_char) {
+        match help_parser::help_topic(cmd, command_char) {
+            Some(command) => help_parser::format_command_help(body, command, command_char),
+            None => help_parser::format_help_response(body, command_char),
+        }
+    } else if let Some(entries) = (name == "banlist")
+        .then(|| banlist::parse_banlist(body))
+        .flatten()
+    {
+        banlist::format_banlist(entries, options.banlist_sort)
+    } else if let Some(entries) = (bare.trim() == "list uuids")
+        .then(|| server_cache::parse_player_uuids(body))
+        .flatten()
+        .filter(|entries| !entries.is_empty())
+    {
+        server_cache::format_player_uuids(body, &entries)
+    } else if let Some(text) = text_component::format_text_component(body) {
+        text
+    } else if options.compact_errors {
+        // Errors are still recognized by is_error_response, just not split
+        body.to_string()
+    } else {
+        format_generic_response(body, options.locale)
+    }
+}
+
+/// Everything printed for one command: the optional echo, the body and the optional
+/// timing. The body lines cut off by `max_output_lines` are returned second.
+pub fn render_response(
+    options: &DisplayOptions,
+    cmd: &str,
+    result: &CommandResult,
+) -> (String, Option<String>) {
+    let mut rendered = String::new();
+    if options.echo {
+        rendered.push_str(&format!("> {cmd}\n"));
+    }
+    let mut formatted = result.formatted.clone();
+    // The message is the first line, the failing input follows it
+    if result.is_error && !options.raw {
+        let (message, rest) = formatted
+            .split_once('\n')
+            .map_or((formatted.as_str(), None), |(message, rest)| {
+                (message, Some(rest))
+            });
+        let mut painted = ColorScheme::paint(options.colors.error, message);
+        if let Some(rest) = rest {
+            painted.push('\n');
+            painted.push_str(rest);
+        }
+        formatted = painted;
+    }
+    if let Some(re) = &options.grep {
+        let highlight = (!options.no_color).then_some(options.colors.grep_match);
+        formatted = grep::filter_lines(&formatted, re, options.grep_invert, highlight);
+    }
+    let mut rest = None;
+    if let Some(max) = options.max_output_lines {
+        (formatted, rest) = display::truncate_lines(&formatted, max);
+    }
+    rendered.push_str(&formatted);
+    if options.timings {
+        rendered.push_str(&format!("\n({} ms)", result.elapsed.as_millis()));
+    }
+    if options.no_color {
+        rendered = display::strip_ansi(&rendered);
+        rest = rest.map(|rest| display::strip_ansi(&rest));
+    }
+    (rendered, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::Parser;
+
+    fn options(args: &[&str]) -> DisplayOptions {
+        let cli = Cli::parse_from(std::iter::once("mc-rcon").chain(args.iter().copied()));
+        DisplayOptions::from_cli(&cli)
+    }
+
+    #[test]
+    fn recognizes_commands_sent_with_another_command_char() {
+        let options = options(&["--command-char", "!"]);
+        assert_eq!(
+            format_response(&options, "!help", "!list!say <message>"),
+            "!list\n!say <message>"
+        );
+        assert_eq!(
+            format_response(
+                &options,
+                "!help give",
+                "!give <targets> <item>!give <targets>"
+            ),
+            "!give <targets> <item>\n!give <targets>"
+        );
+        let bans = "There are 1 ban(s):Steve was banned by Server: griefing";
+        assert_eq!(
+            format_response(&options, "!banlist", bans),
+            banlist::format_banlist(banlist::parse_banlist(bans).unwrap(), None)
+        );
+        let uuids = "There are 1 of a max of 20 players online: \
+Steve (069a79f4-44e9-4726-a5be-fca90e38aaf5)";
+        assert_eq!(
+            format_response(&options, "!list uuids", uuids),
+            "There are 1 of a max of 20 players online:\n  \
+Steve  069a79f4-44e9-4726-a5be-fca90e38aaf5"
+        );
+    }
+
+    const UNKNOWN: &str = "Unknown or incomplete command, see below for error  /fly<--[HERE]";
+
+    #[test]
+    fn splits_errors_unless_compact() {
+        assert_eq!(
+            format_response(&options(&[]), "/fly", UNKNOWN),
+            "Unknown or incomplete command, see below for error\n/fly<--[HERE]"
+        );
+        assert_eq!(
+            format_response(&options(&["--compact-errors"]), "/fly", UNKNOWN),
+            UNKNOWN
+        );
+        assert!(is_error_response(UNKNOWN, Locale::En));
+    }
+
+    #[test]
+    fn compact_errors_are_still_painted() {
+        let result = CommandResult {
+            body: UNKNOWN.to_string(),
+            formatted: UNKNOWN.to_string(),
+            is_error: true,
+            elapsed: std::time::Duration::ZERO,
+        };
+        let (rendered, _) = render_response(&options(&["--compact-errors"]), "/fly", &result);
+        assert_eq!(
+            rendered,
+            ColorScheme::paint(ColorScheme::default().error, UNKNOWN)
+        );
+    }
+
+    #[test]
+    fn recognizes_errors_in_the_server_locale() {
+        let german =
+            "Unbekannter oder unvollständiger Befehl, siehe unten für Fehler  /fly<--[HIER]";
+        assert!(!is_error_response(german, Locale::En));
+        assert!(is_error_response(german, Locale::De));
+        assert!(!is_error_response(german, Locale::Fr));
+        // English errors are recognized whatever the locale
+        assert!(is_error_response(
+            "Incorrect argument for command  /gamemode x<--[HERE]",
+            Locale::Zh
+        ));
+        assert_eq!(
+            format_generic_response(german, Locale::De),
+            "Unbekannter oder unvollständiger Befehl, siehe unten für Fehler\n/fly<--[HIER]"
+        );
+    }
+
+    #[test]
+    fn no_newline_fix_passes_the_help_body_through() {
+        let body = "/list/say <message> /tp <destination>";
+        assert_eq!(
+            format_response(&options(&["--no-newline-fix"]), "/help", body),
+            body
+        );
+        assert_eq!(
+            format_response(
+                &options(&["--no-newline-fix"]),
+                "/help say",
+                "/say <message>/say"
+            ),
+            "/say <message>/say"
+        );
+        assert_ne!(format_response(&options(&[]), "/help", body), body);
+    }
+
+    #[test]
+    fn set_options_apply_to_the_next_response() {
+        let result = CommandResult {
+            body: UNKNOWN.to_string(),
+            formatted: format_response(&options(&[]), "/fly", UNKNOWN),
+            is_error: true,
+            elapsed: std::time::Duration::from_millis(12),
+        };
+        let mut options = options(&[]);
+        let (before, _) = render_response(&options, "/fly", &result);
+        assert!(before.starts_with("\x1b["));
+        options.set("no-color", true).unwrap();
+        options.set("echo", true).unwrap();
+        options.set("timings", true).unwrap();
+        let (after, _) = render_response(&options, "/fly", &result);
+        assert_eq!(after, format!("> /fly\n{}\n(12 ms)", result.formatted));
+        options.set("timings", false).unwrap();
+        let (after, _) = render_response(&options, "/fly", &result);
+        assert!(!after.ends_with("(12 ms)"));
+    }
+}
@@ -0,0 +1,57 @@
+use regex::Regex;
+
+use crate::color_scheme::ColorScheme;
+use crate::display::strip_ansi;
+
+/// Keep the lines matching `re` (or not matching, with `invert`). Matches are highlighted
+/// in the `highlight` colors when given; lines are matched without their ANSI codes.
+pub fn filter_lines(text: &str, re: &Regex, invert: bool, highlight: Option<&str>) -> String {
+    text.lines()
+        .filter_map(|line| {
+            let plain = strip_ansi(line);
+            if re.is_match(&plain) == invert {
+                return None;
+            }
+            match highlight {
+                Some(code) if !invert => Some(
+                    re.replace_all(&plain, |caps: &regex::Captures| {
+                        ColorScheme::paint(code, &caps[0])
+                    })
+                    .into_owned(),
+                ),
+                _ => Some(line.to_string()),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn parse_pattern(pattern: &str) -> Result<Regex, String> {
+    Regex::new(pattern).map_err(|e| format!("Invalid pattern '{pattern}': {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_matching_lines() {
+        let re = parse_pattern("Steve|Alex").unwrap();
+        let text = "Steve: 12\nZed: 3\n\x1b[31mAlex\x1b[0m: 7";
+        assert_eq!(
+            filter_lines(text, &re, false, None),
+            "Steve: 12\n\x1b[31mAlex\x1b[0m: 7"
+        );
+        assert_eq!(filter_lines(text, &re, true, None), "Zed: 3");
+        assert_eq!(
+            filter_lines("Steve: 12", &re, false, Some("1")),
+            "\x1b[1mSteve\x1b[0m: 12"
+        );
+    }
+
+    #[test]
+    fn reports_invalid_patterns() {
+        let e = parse_pattern("(").unwrap_err();
+        assert!(e.starts_with("Invalid pattern '(': "));
+    }
+}
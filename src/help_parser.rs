@@ -1,29 +1,147 @@
 use regex::Regex;
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
 use crate::Argument;
 
-pub fn format_help_response(body: &str) -> String {
+pub fn format_help_response(body: &str, command_char: &str) -> String {
+    split_help_entries(body, command_char)
+}
+
+/// Whether a command sent is `/help`, with or without the command prefix
+pub fn is_help_command(cmd: &str, command_char: &str) -> bool {
+    let name = cmd.split_whitespace().next().unwrap_or_default();
+    name == "help" || name.strip_prefix(command_char) == Some("help")
+}
+
+/// The command a `/help <command>` asks about, None for the full listing or a page of it
+pub fn help_topic<'a>(cmd: &'a str, command_char: &str) -> Option<&'a str> {
+    cmd.split_whitespace()
+        .nth(1)
+        .filter(|word| word.parse::<u32>().is_err())
+        .map(|word| word.strip_prefix(command_char).unwrap_or(word))
+}
+
+/// Format the response to `/help <command>`, the usages of one command. Only a repeat of
+/// `/<command>` glued to the previous usage starts a new line, so slashes inside a usage
+/// or its description stay where they are.
+pub fn format_command_help(body: &str, command: &str, command_char: &str) -> String {
+    let usage = format!("{command_char}{command}");
     let mut fixed = String::with_capacity(body.len());
-    let chars = body.chars().peekable();
-    let mut prev = None;
-    for c in chars {
-        if c == '/' && prev != Some('\n') && prev.is_some() {
-            fixed.push('\n');
+    for line in body.lines() {
+        let mut last = 0;
+        for (idx, _) in line.match_indices(&usage) {
+            let before = line[..idx].chars().last();
+            let after = line[idx + usage.len()..].chars().next();
+            let glued = before.is_some_and(|c| !c.is_whitespace());
+            let whole_name = after.is_none_or(|c| c.is_whitespace() || command_char.starts_with(c));
+            if glued && whole_name {
+                fixed.push_str(&line[last..idx]);
+                fixed.push('\n');
+                last = idx;
+            }
         }
-        fixed.push(c);
-        prev = Some(c);
+        fixed.push_str(&line[last..]);
+        fixed.push('\n');
     }
     fixed.trim().to_string()
 }
 
-pub fn parse_commands(help: String) -> HashMap<String, Vec<Argument>> {
-    let re_cmd = Regex::new(r"^(?P<cmd>/\w+)(?P<args>.*)").unwrap();
-    let re_required = Regex::new(r"<([^>]+)>").unwrap();
-    let re_optional = Regex::new(r"\[<([^>]+)>\]").unwrap();
-    let re_required_choice = Regex::new(r"\(([^)]+)\)").unwrap();
-    let re_optional_choice = Regex::new(r"\[([^\]]+\|[^\]]+)\]").unwrap();
-    let re_alias = Regex::new(r"^(?P<alias>/\w+)\s*->\s*(?P<target>\w+)").unwrap();
+/// An alias entry like `/tell -> msg`, whose target may be glued to the next command
+fn is_alias_entry(entry: &str) -> bool {
+    static RE_ALIAS: OnceLock<Regex> = OnceLock::new();
+    RE_ALIAS
+        .get_or_init(|| Regex::new(r"^\S+\s*->\s*[\w-]+$").unwrap())
+        .is_match(entry)
+}
+
+/// Put every command of a run-together /help response on its own line. Only a prefix
+/// glued to the end of the previous command's usage, after a closing bracket, a bare
+/// command name or an alias target, starts a new line. Existing line breaks, blank lines,
+/// indentation and slashes in descriptions (`and/or`) are kept. Commands without a prefix
+/// can't be told apart from their arguments, so those are left as they are.
+pub fn split_help_entries(body: &str, command_char: &str) -> String {
+    let Some(marker) = command_char.chars().next() else {
+        return body.trim().to_string();
+    };
+    let mut fixed = String::with_capacity(body.len());
+    for line in body.lines() {
+        let is_command = line.trim_start().starts_with(marker);
+        // Where the current command starts in `fixed`
+        let mut entry_start = fixed.len();
+        for c in line.chars() {
+            let entry = &fixed[entry_start..];
+            let glued = match entry.chars().last() {
+                Some(']' | '>' | ')') => true,
+                Some(prev) => {
+                    !prev.is_whitespace()
+                        && (!entry.trim_start().contains(' ') || is_alias_entry(entry.trim_start()))
+                }
+                None => false,
+            };
+            if is_command && c == marker && glued {
+                fixed.push('\n');
+                entry_start = fixed.len();
+            }
+            fixed.push(c);
+        }
+        fixed.push('\n');
+    }
+    fixed
+        .trim_start_matches(['\r', '\n'])
+        .trim_end()
+        .to_string()
+}
+
+/// Parse the arguments of a usage line, like `<targets> <item> [<count>]`
+pub fn parse_arguments(usage: &str) -> Vec<Argument> {
+    // Alternatives are tried left to right, so bracketed forms must come before bare ones.
+    // Compiled once since this runs for every line of /help.
+    static RE_ARGUMENT: OnceLock<Regex> = OnceLock::new();
+    let re_argument = RE_ARGUMENT.get_or_init(|| {
+        Regex::new(
+            r"\[\((?P<opt_paren_choice>[^)]+)\)\]|\[<(?P<optional>[^>]+)>\]|\[(?P<opt_choice>[^\]]+\|[^\]]+)\]|\((?P<req_choice>[^)]+)\)|<(?P<required>[^>]+)>",
+        )
+        .unwrap()
+    });
+    let split_choices = |choices: &str| choices.split('|').map(|s| s.trim().to_string()).collect();
+    let mut args = Vec::new();
+    // Parse arguments in the order they appear
+    for cap in re_argument.captures_iter(usage) {
+        // `<true|false>` lists its values like a choice
+        if let Some(name) = cap
+            .name("required")
+            .filter(|name| name.as_str().contains('|'))
+        {
+            args.push(Argument::RequiredChoice(split_choices(name.as_str())));
+        } else if let Some(name) = cap
+            .name("optional")
+            .filter(|name| name.as_str().contains('|'))
+        {
+            args.push(Argument::OptionalChoice(split_choices(name.as_str())));
+        } else if let Some(name) = cap.name("required") {
+            args.push(Argument::Required(name.as_str().to_string()));
+        } else if let Some(name) = cap.name("optional") {
+            args.push(Argument::Optional(name.as_str().to_string()));
+        } else if let Some(choices) = cap.name("req_choice") {
+            args.push(Argument::RequiredChoice(split_choices(choices.as_str())));
+        } else if let Some(choices) = cap
+            .name("opt_choice")
+            .or_else(|| cap.name("opt_paren_choice"))
+        {
+            args.push(Argument::OptionalChoice(split_choices(choices.as_str())));
+        }
+    }
+    args
+}
+
+pub fn parse_commands(help: String, command_char: &str) -> HashMap<String, Vec<Argument>> {
+    let prefix = regex::escape(command_char);
+    let re_cmd = Regex::new(&format!(r"^(?P<cmd>{prefix}[\w-]+)(?P<args>.*)")).unwrap();
+    let re_alias = Regex::new(&format!(
+        r"^(?P<alias>{prefix}[\w-]+)\s*->\s*(?P<target>[\w-]+)"
+    ))
+    .unwrap();
 
     let mut commands: HashMap<String, Vec<Argument>> = HashMap::new();
     let mut alias_map: HashMap<String, String> = HashMap::new(); // alias -> target
@@ -32,37 +150,303 @@ pub fn parse_commands(help: String) -> HashMap<String, Vec<Argument>> {
         let line = line.trim();
         if let Some(cap) = re_cmd.captures(line) {
             let name = cap["cmd"].to_string();
-            let mut args = Vec::new();
             let args_str = cap.name("args").map(|m| m.as_str()).unwrap_or("");
-            // Parse required args
-            for cap in re_required.captures_iter(args_str) {
-                args.push(Argument::Required(cap[1].to_string()));
-            }
-            // Parse optional args
-            for cap in re_optional.captures_iter(args_str) {
-                args.push(Argument::Optional(cap[1].to_string()));
-            }
-            // Parse choices (parentheses or brackets)
-            for cap in re_required_choice.captures_iter(args_str) {
-                let opts = cap[1].split('|').map(|s| s.trim().to_string()).collect();
-                args.push(Argument::RequiredChoice(opts));
-            }
-            for cap in re_optional_choice.captures_iter(args_str) {
-                let opts = cap[1].split('|').map(|s| s.trim().to_string()).collect();
-                args.push(Argument::OptionalChoice(opts));
-            }
-            commands.insert(name, args);
+            commands.insert(name, parse_arguments(args_str));
         }
         if let Some(cap) = re_alias.captures(line) {
             let alias = cap["alias"].to_string();
-            let target = format!("/{}", &cap["target"]);
+            let target = format!("{command_char}{}", &cap["target"]);
             alias_map.insert(alias, target);
         }
     }
 
     for (alias, target) in alias_map {
-        // Replace empty alias commands with target commands
-        commands.insert(alias, commands[&target].clone());
+        // Replace empty alias commands with target commands. A partial or paged /help may
+        // not list the target, the alias is skipped then.
+        if let Some(args) = commands.get(&target).cloned() {
+            commands.insert(alias, args);
+        }
     }
     commands
 }
+
+/// Argument name to use for a Bedrock parameter type, so the usual name based
+/// candidates apply
+fn bedrock_argument_name<'a>(name: &'a str, param_type: &'a str) -> &'a str {
+    match param_type {
+        "target" => "targets",
+        "x y z" => "pos",
+        "GameMode" => "gamemode",
+        "Item" => "item",
+        "EntityType" => "entity",
+        "Effect" => "effect",
+        "Boolean" => "bool",
+        _ => name,
+    }
+}
+
+/// Rewrite a Bedrock /help response into the Java format: drop the `§` color codes and
+/// the page headers, turn `<player: target>` into `<targets>` and `<a|b>` enums into
+/// `(a|b)` choices. Only lines starting with `command_char` are commands.
+pub fn normalize_bedrock_help(body: &str, command_char: &str) -> String {
+    // Compiled once since this runs for every /help page
+    static RE_COLOR: OnceLock<Regex> = OnceLock::new();
+    static RE_TYPED: OnceLock<Regex> = OnceLock::new();
+    static RE_ENUM: OnceLock<Regex> = OnceLock::new();
+    let re_color = RE_COLOR.get_or_init(|| Regex::new(r"§.").unwrap());
+    let re_typed = RE_TYPED.get_or_init(|| {
+        Regex::new(r"(?P<open>[<\[])(?P<name>[^:<>\[\]]+):\s*(?P<type>[^<>\[\]]+)[>\]]").unwrap()
+    });
+    let re_enum = RE_ENUM.get_or_init(|| Regex::new(r"<(?P<choices>[^<>:]+\|[^<>:]+)>").unwrap());
+    let body = re_color.replace_all(body, "");
+    body.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with(command_char))
+        .map(|line| {
+            let line = re_typed.replace_all(line, |cap: &regex::Captures| {
+                let name = bedrock_argument_name(cap["name"].trim(), cap["type"].trim());
+                match &cap["open"] {
+                    "<" => format!("<{name}>"),
+                    _ => format!("[<{name}>]"),
+                }
+            });
+            re_enum.replace_all(&line, "(${choices})").into_owned()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Total number of pages from a paginated `--- Showing help page 1 of 9 ---` header
+pub fn help_page_count(body: &str) -> Option<u32> {
+    static RE_PAGE: OnceLock<Regex> = OnceLock::new();
+    let re_page = RE_PAGE.get_or_init(|| Regex::new(r"help page \d+ of (\d+)").unwrap());
+    re_page.captures(body)?[1].parse().ok()
+}
+
+/// Progress message for fetching `page` of a paginated /help with `total` pages
+pub fn page_progress(page: u32, total: u32) -> String {
+    format!("Fetching help page {page}/{total}...")
+}
+
+/// Usage and description of a single command, as returned by `/help <command>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandHelp {
+    pub signature: String,
+    pub description: Option<String>,
+}
+
+/// Parse a `/help <command>` response. Plugin servers answer with `Description:` and
+/// `Usage:` lines, vanilla with the bare usage lines.
+pub fn parse_command_help(body: &str, command: &str, command_char: &str) -> Option<CommandHelp> {
+    let mut signature = None;
+    let mut description = None;
+    for line in body.lines() {
+        let line = line.trim();
+        if let Some(text) = line.strip_prefix("Description:") {
+            description = Some(text.trim().to_string());
+        } else if let Some(text) = line.strip_prefix("Usage:") {
+            signature = Some(text.trim().to_string());
+        }
+    }
+    if signature.is_none() && description.is_none() {
+        signature = Some(format_command_help(body, command, command_char));
+    }
+    let signature = signature.unwrap_or_default();
+    if signature.is_empty() && description.is_none() {
+        return None;
+    }
+    Some(CommandHelp {
+        signature,
+        description,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The vanilla 1.20 /help listing as RCON returns it, with every usage run together
+    const VANILLA_HELP: &str = "/advancement (grant|revoke)/attribute <target> <attribute> \
+(base|get|modifier)/ban <targets> [<reason>]/ban-ip <target> [<reason>]/banlist \
+[ips|players]/bossbar (add|get|list|remove|set)/clear [<targets>]/clone (<begin>|from)\
+/damage <target> <amount> [<damageType>]/data (merge|get|remove|modify)/datapack \
+(enable|disable|list)/debug (start|stop|function)/defaultgamemode <gamemode>/deop \
+<targets>/difficulty [peaceful|easy|normal|hard]/effect (clear|give)/enchant <targets> \
+<enchantment> [<level>]/execute (run|if|unless|as|at|store|positioned|rotated|facing|\
+align|anchored|in|summon|on)/experience (add|set|query)/fill <from> <to> <block> \
+[replace|keep|outline|hollow|destroy]/fillbiome <from> <to> <biome> [replace]/forceload \
+(add|remove|query)/function <name> [<arguments>|with]/gamemode <gamemode> [<target>]\
+/gamerule (announceAdvancements|doDaylightCycle|keepInventory)/give <targets> <item> \
+[<count>]/help [<command>]/item (replace|modify)/jfr (start|stop)/kick <targets> \
+[<reason>]/kill [<targets>]/list [uuids]/locate (structure|biome|poi)/loot \
+(replace|insert|give|spawn)/me <action>/msg <targets> <message>/op <targets>/pardon \
+<targets>/pardon-ip <target>/particle <name> [<pos>]/perf (start|stop)/place \
+(feature|jigsaw|structure|template)/playsound <sound> (master|music|record|weather|block|\
+hostile|neutral|player|ambient|voice)/publish [<allowCommands>]/recipe (give|take)\
+/reload/return <value>/ride <target> (mount|dismount)/save-all [flush]/save-off/save-on\
+/say <message>/schedule (function|clear)/scoreboard (objectives|players)/seed/setblock \
+<pos> <block> [destroy|keep|replace]/setidletimeout <minutes>/setworldspawn [<pos>]\
+/spawnpoint [<targets>]/spectate [<target>]/spreadplayers <center> <spreadDistance> \
+<maxRange> (<respectTeams>|under)/stop/stopsound <targets> [*|master|music|record|\
+weather|block|hostile|neutral|player|ambient|voice]/summon <entity> [<pos>]/tag \
+<targets> (list|add|remove)/team (list|add|remove|empty|join|leave|modify)/teammsg \
+<message>/teleport (<location>|<destination>|<targets>)/tell -> msg/tellraw <targets> \
+<message>/time (set|add|query)/title <targets> (clear|reset|title|subtitle|actionbar|\
+times)/tm -> teammsg/tp -> teleport/trigger <objective> [add|set]/w -> msg/weather \
+(clear|rain|thunder)/whitelist (on|off|list|add|remove|reload)/worldborder \
+(add|center|damage|get|set|warning)/xp -> experience";
+
+    #[test]
+    fn splits_vanilla_help_into_one_entry_per_line() {
+        let split = split_help_entries(VANILLA_HELP, "/");
+        let lines: Vec<&str> = split.lines().collect();
+        assert_eq!(lines.len(), VANILLA_HELP.matches("/").count());
+        assert!(lines.iter().all(|line| line.starts_with('/')));
+        for entry in [
+            "/tell -> msg",
+            "/tellraw <targets> <message>",
+            "/tm -> teammsg",
+            "/tp -> teleport",
+            "/trigger <objective> [add|set]",
+            "/w -> msg",
+            "/weather (clear|rain|thunder)",
+            "/xp -> experience",
+            "/reload",
+            "/save-off",
+        ] {
+            assert!(lines.contains(&entry), "missing {entry}");
+        }
+    }
+
+    #[test]
+    fn parses_commands_after_aliases_in_vanilla_help() {
+        let commands = parse_commands(split_help_entries(VANILLA_HELP, "/"), "/");
+        for name in [
+            "/tellraw", "/trigger", "/weather", "/tell", "/tp", "/w", "/xp",
+        ] {
+            assert!(commands.contains_key(name), "missing {name}");
+        }
+        assert_eq!(commands["/tp"], commands["/teleport"]);
+    }
+
+    #[test]
+    fn keeps_blank_lines_and_slashes_in_descriptions() {
+        let body = "/say <message>\n\n/me <action> and/or text";
+        assert_eq!(split_help_entries(body, "/"), body);
+    }
+
+    #[test]
+    fn splits_help_for_another_command_char() {
+        assert_eq!(
+            format_help_response("!list!say <message>", "!"),
+            "!list\n!say <message>"
+        );
+        assert!(is_help_command("!help give", "!"));
+        assert!(is_help_command("help", "!"));
+        assert!(!is_help_command("/helpme", "/"));
+        assert_eq!(help_topic("!help !give", "!"), Some("give"));
+        assert_eq!(help_topic("!help 2", "!"), None);
+        assert_eq!(
+            format_command_help("!give <targets> <item>!give <targets>", "give", "!"),
+            "!give <targets> <item>\n!give <targets>"
+        );
+    }
+
+    #[test]
+    fn reads_plugin_descriptions_and_vanilla_usages() {
+        let plugin = "--------- Help: /home ---------\nDescription: Teleport to your home\n\
+Usage: /home [name]";
+        assert_eq!(
+            parse_command_help(plugin, "home", "/"),
+            Some(CommandHelp {
+                signature: "/home [name]".to_string(),
+                description: Some("Teleport to your home".to_string()),
+            })
+        );
+        let vanilla = "/time add <time>/time query (daytime|gametime|day)/time set <time>";
+        assert_eq!(
+            parse_command_help(vanilla, "time", "/").unwrap().signature,
+            "/time add <time>\n/time query (daytime|gametime|day)\n/time set <time>"
+        );
+        assert_eq!(parse_command_help("", "time", "/"), None);
+    }
+
+    #[test]
+    fn finds_the_help_topic() {
+        assert_eq!(help_topic("/help give", "/"), Some("give"));
+        assert_eq!(help_topic("/help /give", "/"), Some("give"));
+        assert_eq!(help_topic("/help 2", "/"), None);
+        assert_eq!(help_topic("/help", "/"), None);
+    }
+
+    #[test]
+    fn normalizes_bedrock_help() {
+        let body = "§2--- Showing help page 1 of 9 (/help <page>) ---\n\
+§f/gamemode <mode: GameMode> [player: target]\n\
+/give <player: target> <itemName: Item> [amount: int]\n\
+/weather <clear|rain|thunder> [duration: int]\n\
+§2Tip: Use the <tab> key while typing a command to auto-complete it";
+        assert_eq!(
+            normalize_bedrock_help(body, "/"),
+            "/gamemode <gamemode> [<targets>]\n\
+/give <targets> <item> [<amount>]\n\
+/weather (clear|rain|thunder) [<duration>]"
+        );
+        let commands = parse_commands(normalize_bedrock_help(body, "/"), "/");
+        assert!(commands.contains_key("/weather"));
+        assert_eq!(help_page_count(body), Some(9));
+    }
+
+    #[test]
+    fn normalizes_bedrock_help_with_another_command_char() {
+        let body = "§2--- Showing help page 1 of 2 (!help <page>) ---\n\
+§f!gamemode <mode: GameMode> [player: target]\n\
+/say <message: message>";
+        assert_eq!(
+            normalize_bedrock_help(body, "!"),
+            "!gamemode <gamemode> [<targets>]"
+        );
+    }
+
+    #[test]
+    fn reports_progress_for_each_page() {
+        assert_eq!(help_page_count("--- Showing help page 1 of 3 ---"), Some(3));
+        assert_eq!(help_page_count("/say <message>"), None);
+        let messages: Vec<String> = (2..=3).map(|page| page_progress(page, 3)).collect();
+        assert_eq!(
+            messages,
+            ["Fetching help page 2/3...", "Fetching help page 3/3..."]
+        );
+    }
+
+    #[test]
+    fn single_command_help_keeps_slashes_inside_usages() {
+        let body = "/give <targets> <item> [<count>]/give <targets> and/or <item>";
+        assert_eq!(
+            format_command_help(body, "give", "/"),
+            "/give <targets> <item> [<count>]\n/give <targets> and/or <item>"
+        );
+        // The full listing formatter splits at every command it finds
+        let listing = "/me <action> and/or <text>/say <message>";
+        assert_eq!(
+            format_help_response(listing, "/"),
+            "/me <action> and/or <text>\n/say <message>"
+        );
+        // A usage of a longer name isn't a repeat of the command
+        assert_eq!(
+            format_command_help("/give <item>/giveall <item>", "give", "/"),
+            "/give <item>/giveall <item>"
+        );
+    }
+
+    #[test]
+    fn skips_aliases_of_commands_missing_from_the_help() {
+        // A page of the listing with the alias but not its target
+        let commands = parse_commands(
+            "/tell -> msg\n/tp -> teleport\n/teleport <targets>".to_string(),
+            "/",
+        );
+        assert_eq!(commands["/tell"], []);
+        assert_eq!(commands["/tp"], commands["/teleport"]);
+    }
+}
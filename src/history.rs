@@ -0,0 +1,74 @@
+/// Expand a shell-style history reference at the start of a line: `!!` for the last
+/// entry, `!n` for entry n counting from 1, or `!prefix` for the latest entry starting
+/// with `prefix`, which may leave out the `command_char`. Words after the reference are
+/// appended. Returns None for lines without one, and for every line when commands
+/// themselves start with `!`.
+pub fn expand(line: &str, entries: &[&str], command_char: &str) -> Option<Result<String, String>> {
+    if command_char.starts_with('!') {
+        return None;
+    }
+    let rest = line.strip_prefix('!')?;
+    let (reference, args) = match rest.split_once(char::is_whitespace) {
+        Some((reference, args)) => (reference, Some(args)),
+        None => (rest, None),
+    };
+    let entry = match reference {
+        "" => return None,
+        "!" => entries.last(),
+        _ => match reference.parse::<usize>() {
+            Ok(n) => n.checked_sub(1).and_then(|index| entries.get(index)),
+            Err(_) => entries.iter().rev().find(|entry| {
+                let name = entry.strip_prefix(command_char).unwrap_or(entry);
+                entry.starts_with(reference) || name.starts_with(reference)
+            }),
+        },
+    };
+    Some(match entry {
+        Some(entry) => Ok(match args {
+            Some(args) => format!("{entry} {}", args.trim()),
+            None => entry.to_string(),
+        }),
+        None => Err(format!("!{reference}: No such history entry")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTRIES: &[&str] = &["/say hello", "/give Steve diamond", "/list"];
+
+    #[test]
+    fn expands_last_numbered_and_prefixed_entries() {
+        assert_eq!(expand("!!", ENTRIES, "/"), Some(Ok("/list".to_string())));
+        assert_eq!(
+            expand("!1", ENTRIES, "/"),
+            Some(Ok("/say hello".to_string()))
+        );
+        assert_eq!(
+            expand("!give 5", ENTRIES, "/"),
+            Some(Ok("/give Steve diamond 5".to_string()))
+        );
+        assert_eq!(
+            expand("!/say", ENTRIES, "/"),
+            Some(Ok("/say hello".to_string()))
+        );
+    }
+
+    #[test]
+    fn reports_missing_entries() {
+        assert_eq!(
+            expand("!9", ENTRIES, "/"),
+            Some(Err("!9: No such history entry".to_string()))
+        );
+        assert_eq!(expand("/list", ENTRIES, "/"), None);
+        assert_eq!(expand("! x", ENTRIES, "/"), None);
+    }
+
+    #[test]
+    fn leaves_commands_alone_when_they_start_with_a_bang() {
+        assert_eq!(expand("!say hi", &["!say hello"], "!"), None);
+        assert_eq!(expand("!kick Steve", &[], "!"), None);
+        assert_eq!(expand("!!", &["!list"], "!"), None);
+    }
+}
@@ -0,0 +1,177 @@
+pub mod alias;
+pub mod banlist;
+pub mod canonicalize;
+pub mod cli;
+pub mod color_scheme;
+pub mod command;
+pub mod completer;
+pub mod config;
+pub mod connection;
+#[cfg(unix)]
+pub mod control;
+pub mod display;
+pub mod doctor;
+pub mod duplicate;
+pub mod edition;
+pub mod effective;
+pub mod execute;
+pub mod format;
+pub mod grep;
+pub mod help_parser;
+pub mod history;
+pub mod logging;
+pub mod macros;
+pub mod mc_commands;
+pub mod meta;
+pub mod notify;
+pub mod output;
+pub mod palette;
+pub mod profile;
+pub mod proxy;
+pub mod rcon;
+pub mod script;
+pub mod serve;
+pub mod server_cache;
+pub mod session;
+pub mod shortcuts;
+pub mod spec;
+pub mod tail;
+pub mod tee;
+pub mod template;
+pub mod terminal;
+pub mod text_component;
+pub mod tokenizer;
+pub mod transcript;
+
+pub use cli::Cli;
+pub use completer::MinecraftCompleter;
+
+use mc_commands::NamespaceStyle;
+use server_cache::ServerCache;
+
+// TODO: Add support for complex structures like (<respectTeams>|under)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    Required(String),            // <arg>
+    Optional(String),            // [<arg>]
+    RequiredChoice(Vec<String>), //(a|b|c)
+    OptionalChoice(Vec<String>), // [(a|b|c)] or [a|b|c]
+}
+
+impl Argument {
+    /// Number of input words this argument consumes (e.g. 3 for coordinates)
+    pub fn width(&self) -> usize {
+        match self {
+            Argument::Required(name) | Argument::Optional(name) => {
+                mc_commands::argument_width(name)
+            }
+            _ => 1,
+        }
+    }
+
+    /// Whether `word` can be this argument, telling coordinates and entities apart
+    pub fn accepts(&self, word: &str) -> bool {
+        match self {
+            Argument::Required(name) | Argument::Optional(name) => {
+                if mc_commands::is_coordinate_argument(name) {
+                    mc_commands::is_coordinate(word)
+                } else if mc_commands::is_entity_argument(name) {
+                    !mc_commands::is_coordinate(word)
+                } else {
+                    true
+                }
+            }
+            Argument::RequiredChoice(choices) | Argument::OptionalChoice(choices) => {
+                choices.iter().any(|choice| choice == word)
+            }
+        }
+    }
+
+    /// Name of a named argument, None for choices
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Argument::Required(name) | Argument::Optional(name) => Some(name),
+            Argument::RequiredChoice(_) | Argument::OptionalChoice(_) => None,
+        }
+    }
+
+    /// Candidates for the word at `offset` within this argument
+    pub fn candidates(
+        &self,
+        offset: usize,
+        cache: &ServerCache,
+        namespace_style: NamespaceStyle,
+    ) -> Vec<String> {
+        match self {
+            Argument::Required(name) | Argument::Optional(name) => {
+                let overridden = cache.overrides.get(name);
+                let listed: Vec<&str> = match overridden {
+                    Some(candidates) => candidates.iter().map(String::as_str).collect(),
+                    None => mc_commands::argument_candidates(name).to_vec(),
+                };
+                let mut candidates: Vec<String> = listed
+                    .iter()
+                    .map(|candidate| {
+                        candidate
+                            .split(' ')
+                            .skip(offset)
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .filter(|candidate| !candidate.is_empty())
+                    .collect();
+                if offset == 0 {
+                    if overridden.is_none() {
+                        candidates.extend(
+                            mc_commands::argument_ids(name)
+                                .iter()
+                                .flat_map(|id| namespace_style.id_candidates(id)),
+                        );
+                    }
+                    candidates.extend(cache.candidates(name));
+                }
+                candidates
+            }
+            Argument::RequiredChoice(choices) | Argument::OptionalChoice(choices) => {
+                choices.clone()
+            }
+        }
+    }
+}
+
+/// Find the argument an input word belongs to, along with the word's offset inside it
+pub fn locate_argument(args: &[Argument], mut word_index: usize) -> Option<(&Argument, usize)> {
+    for arg in args {
+        if word_index < arg.width() {
+            return Some((arg, word_index));
+        }
+        word_index -= arg.width();
+    }
+    None
+}
+
+/// Render arguments back into a usage string like `<targets> <item> [<count>]`
+pub fn signature_string(args: &[Argument]) -> String {
+    args.iter()
+        .map(|arg| match arg {
+            Argument::Required(name) => format!("<{name}>"),
+            Argument::Optional(name) => format!("[<{name}>]"),
+            Argument::RequiredChoice(choices) => format!("({})", choices.join("|")),
+            Argument::OptionalChoice(choices) => format!("[{}]", choices.join("|")),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_string_round_trips_parsed_usages() {
+        let usage = "<targets> <item> [<count>] (a|b) [x|y]";
+        let args = help_parser::parse_arguments(usage);
+        assert_eq!(signature_string(&args), usage);
+        assert_eq!(signature_string(&[]), "");
+    }
+}
@@ -0,0 +1,80 @@
+use log::Level;
+use std::io::Write;
+
+/// Logger writing to stderr, so stdout keeps only the server's responses, filtered by
+/// `env` and formatted so warnings and errors keep the `Warning:`/`Error:` look of the
+/// rest of the output
+fn builder(env: env_logger::Env) -> env_logger::Builder {
+    let mut builder = env_logger::Builder::from_env(env);
+    builder
+        .target(env_logger::Target::Stderr)
+        .format(|buf, record| match record.level() {
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            Level::Warn => writeln!(buf, "Warning: {}", record.args()),
+            level => writeln!(buf, "[{level} {}] {}", record.target(), record.args()),
+        });
+    builder
+}
+
+/// Log to stderr, filtered by `RUST_LOG` and showing only warnings and errors by default
+pub fn init() {
+    builder(env_logger::Env::default().default_filter_or("warn")).init();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Log;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn log_all(filter: &str) -> String {
+        let captured = Captured::default();
+        // A variable nobody sets, so RUST_LOG doesn't leak into the test
+        let env = env_logger::Env::new()
+            .filter("MC_RCON_TEST_LOG")
+            .default_filter_or(filter);
+        let logger = builder(env)
+            .target(env_logger::Target::Pipe(Box::new(captured.clone())))
+            .build();
+        for (level, message) in [
+            (Level::Debug, "sending /list"),
+            (Level::Warn, "reconnecting"),
+        ] {
+            logger.log(
+                &log::Record::builder()
+                    .level(level)
+                    .target("rcon")
+                    .args(format_args!("{message}"))
+                    .build(),
+            );
+        }
+        let bytes = captured.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn debug_logging_adds_tagged_lines() {
+        assert_eq!(
+            log_all("debug"),
+            "[DEBUG rcon] sending /list\nWarning: reconnecting\n"
+        );
+    }
+
+    #[test]
+    fn shows_only_warnings_by_default() {
+        assert_eq!(log_all("warn"), "Warning: reconnecting\n");
+    }
+}
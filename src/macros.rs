@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+/// Named command sequences recorded in the shell, and the one being recorded
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Macros {
+    macros: BTreeMap<String, Vec<String>>,
+    recording: Option<(String, Vec<String>)>,
+}
+
+impl Macros {
+    pub fn new(macros: BTreeMap<String, Vec<String>>) -> Macros {
+        Macros {
+            macros,
+            recording: None,
+        }
+    }
+
+    /// Start capturing the commands sent from now on under `name`
+    pub fn start(&mut self, name: &str) -> Result<(), String> {
+        if let Some((current, _)) = &self.recording {
+            return Err(format!(
+                "Already recording '{current}', end it with :macro stop"
+            ));
+        }
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("Invalid macro name '{name}'"));
+        }
+        self.recording = Some((name.to_string(), Vec::new()));
+        Ok(())
+    }
+
+    /// The macro being recorded, if any
+    pub fn recording(&self) -> Option<&str> {
+        self.recording.as_ref().map(|(name, _)| name.as_str())
+    }
+
+    /// Capture a sent command, if a macro is being recorded
+    pub fn record(&mut self, cmd: &str) {
+        if let Some((_, commands)) = &mut self.recording {
+            commands.push(cmd.to_string());
+        }
+    }
+
+    /// Finish recording, replacing any macro of the same name. Returns its name and
+    /// length. Nothing is kept when no command was sent meanwhile.
+    pub fn stop(&mut self) -> Result<(String, usize), String> {
+        let (name, commands) = self
+            .recording
+            .take()
+            .ok_or_else(|| "Not recording a macro".to_string())?;
+        if commands.is_empty() {
+            return Err(format!("No commands were recorded for '{name}'"));
+        }
+        let len = commands.len();
+        self.macros.insert(name.clone(), commands);
+        Ok((name, len))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.macros.get(name).map(Vec::as_slice)
+    }
+
+    pub fn to_map(&self) -> BTreeMap<String, Vec<String>> {
+        self.macros.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_until_stopped_and_runs_after() {
+        let mut macros = Macros::default();
+        macros.record("/list");
+        assert_eq!(macros.recording(), None);
+        macros.start("night").unwrap();
+        assert_eq!(macros.recording(), Some("night"));
+        assert!(macros.start("other").is_err());
+        macros.record("/time set night");
+        macros.record("/weather clear");
+        assert_eq!(macros.stop(), Ok(("night".to_string(), 2)));
+        assert_eq!(macros.recording(), None);
+        assert_eq!(
+            macros.get("night").unwrap(),
+            ["/time set night", "/weather clear"]
+        );
+        assert!(macros.stop().is_err());
+    }
+
+    #[test]
+    fn empty_recordings_are_dropped() {
+        let mut macros = Macros::default();
+        macros.start("nothing").unwrap();
+        assert!(macros.stop().is_err());
+        assert_eq!(macros.get("nothing"), None);
+        assert!(macros.start("two words").is_err());
+    }
+}
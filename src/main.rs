@@ -1,255 +1,1294 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
-use minecraft_client_rs::Client;
 use rpassword::prompt_password;
-use rustyline::completion::{Completer, Pair};
 use rustyline::error::ReadlineError;
-use rustyline::highlight::Highlighter;
-use rustyline::hint::Hinter;
 use rustyline::history::DefaultHistory;
-use rustyline::validate::{ValidationContext, ValidationResult, Validator};
-use rustyline::{CompletionType, Config, Context as RustyContext, Editor, Helper};
-use std::borrow::Cow;
-use std::collections::HashMap;
-
-mod help_parser;
-
-/// Minecraft RCON CLI
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-pub struct Cli {
-    /// Server address (host:port)
-    #[arg(short, long)]
-    pub address: String,
-
-    /// RCON password
-    #[arg(short, long)]
-    pub password: Option<String>,
-}
-
-// TODO: Add support for complex structures like (<respectTeams>|under)
-#[derive(Debug, Clone)]
-enum Argument {
-    #[allow(dead_code)]
-    Required(String), // <arg>
-    #[allow(dead_code)]
-    Optional(String), // [<arg>]
-    RequiredChoice(Vec<String>), //(a|b|c)
-    OptionalChoice(Vec<String>), // [(a|b|c)] or [a|b|c]
-}
-
-struct MinecraftCompleter {
-    commands: HashMap<String, Vec<Argument>>,
-}
-
-const ERROR_PREFIXES: &[&str] = &[
-    "Unknown or incomplete command, see below for error",
-    "Incorrect argument for command",
-];
-
-impl Completer for MinecraftCompleter {
-    type Candidate = Pair;
-
-    fn complete(
-        &self,
-        line: &str,
-        pos: usize,
-        _ctx: &RustyContext<'_>,
-    ) -> Result<(usize, Vec<Pair>), ReadlineError> {
-        let input = &line[..pos];
-        let words: Vec<&str> = input.split(' ').collect();
-        match words.len() {
-            // No suggestions on empty input
-            0 => Ok((0, Vec::new())),
-            // Complete command name
-            1 => {
-                let candidates = self
-                    .commands
-                    .keys()
-                    .filter(|cmd_name| cmd_name.starts_with(line))
-                    .map(|cmd_name| Pair {
-                        display: cmd_name.clone(),
-                        replacement: cmd_name.clone() + " ",
-                    })
-                    .collect();
-                Ok((0, candidates))
+use rustyline::{Editor, EventHandler, KeyEvent};
+use std::collections::{HashMap, VecDeque};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use minecraft_fancy_rcon_cli::alias::Aliases;
+use minecraft_fancy_rcon_cli::canonicalize::canonicalize_ids;
+use minecraft_fancy_rcon_cli::color_scheme::ColorScheme;
+use minecraft_fancy_rcon_cli::command::{self, CommandResult};
+use minecraft_fancy_rcon_cli::completer::{record_sources, CandidateSource};
+use minecraft_fancy_rcon_cli::config::{self, Config};
+use minecraft_fancy_rcon_cli::connection::{self, ConnectionState};
+#[cfg(unix)]
+use minecraft_fancy_rcon_cli::control;
+use minecraft_fancy_rcon_cli::display::{self, DisplayOptions};
+use minecraft_fancy_rcon_cli::duplicate::DuplicateGuard;
+use minecraft_fancy_rcon_cli::edition::Edition;
+use minecraft_fancy_rcon_cli::format::{format_response, render_response};
+use minecraft_fancy_rcon_cli::grep;
+use minecraft_fancy_rcon_cli::macros::Macros;
+use minecraft_fancy_rcon_cli::meta::{self, MetaCommand};
+use minecraft_fancy_rcon_cli::palette::{self, PaletteHandler, PendingCommand};
+use minecraft_fancy_rcon_cli::rcon::{self, RconClient, RconError};
+use minecraft_fancy_rcon_cli::script::{self, BatchSummary};
+use minecraft_fancy_rcon_cli::server_cache::ServerCache;
+use minecraft_fancy_rcon_cli::session::Session;
+use minecraft_fancy_rcon_cli::spec::CompletionSpec;
+use minecraft_fancy_rcon_cli::tail::{self, LineTracker};
+use minecraft_fancy_rcon_cli::template::TemplateHandler;
+use minecraft_fancy_rcon_cli::terminal;
+use minecraft_fancy_rcon_cli::transcript::{self, Transcript};
+use minecraft_fancy_rcon_cli::{
+    cli, doctor, effective, help_parser, history, logging, mc_commands, notify, output, profile,
+    serve, shortcuts, tee, Argument, Cli, MinecraftCompleter,
+};
+
+/// `println!` that also writes the line to the --tee file
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        tee::println(&format!($($arg)*))
+    };
+}
+
+/// Quit keywords only match bare words, so `/quit` is still sent to the server
+fn is_quit_command(cmd: &str, quit_keywords: &[String]) -> bool {
+    quit_keywords
+        .iter()
+        .any(|keyword| cmd.eq_ignore_ascii_case(keyword))
+}
+
+/// Connect and authenticate, trying again as often as --auth-retries allows
+fn connect(cli: &Cli, addr: &str, password: &str) -> Result<RconClient> {
+    let mut attempt = 0;
+    loop {
+        let error = match connect_once(cli, addr, password) {
+            Ok(client) => return Ok(client),
+            Err(e) => e,
+        };
+        let delay = error
+            .downcast_ref::<RconError>()
+            .and_then(|e| rcon::auth_retry_delay(e, attempt, cli.auth_retries));
+        match delay {
+            Some(delay) => {
+                log::warn!("{error}, trying again in {} s", delay.as_secs());
+                std::thread::sleep(delay);
+                attempt += 1;
             }
-            // Try to match command
-            _ => {
-                match self.commands.get(words[0]) {
-                    Some(args) => {
-                        // Complete argument
-                        let mut pairs = Vec::new();
-                        let input_argument_count = words.len() - 1; // -1 for command name
-
-                        // If there are too many input arguments, return no suggestions
-                        if args.len() < input_argument_count {
-                            return Ok((0, Vec::new()));
-                        }
-                        if let Some(
-                            Argument::RequiredChoice(choices) | Argument::OptionalChoice(choices),
-                        ) = args.get(input_argument_count - 1)
-                        {
-                            for choice in choices {
-                                if choice.starts_with(words.last().unwrap()) {
-                                    pairs.push(Pair {
-                                        display: choice.clone(),
-                                        replacement: choice.clone() + " ",
-                                    });
-                                }
-                            }
-                        }
-                        Ok((line.len() - words.last().unwrap().len(), pairs))
+            None => return Err(error),
+        }
+    }
+}
+
+/// Reconnects the same way as at startup, for the services that outlive one connection
+fn reconnector(cli: &Cli, addr: &str, password: &str) -> Box<serve::Reconnect> {
+    let (cli, addr, password) = (cli.clone(), addr.to_string(), password.to_string());
+    Box::new(move || {
+        log::info!("Reconnecting to {addr}");
+        connect(&cli, &addr, &password)
+    })
+}
+
+fn connect_once(cli: &Cli, addr: &str, password: &str) -> Result<RconClient> {
+    let mut client = match &cli.proxy {
+        Some(proxy) => {
+            log::info!("Connecting to {addr} through {proxy}");
+            RconClient::from_stream(proxy.connect(addr)?)
+        }
+        None => {
+            log::info!("Connecting to {addr}");
+            RconClient::connect(addr)?
+        }
+    };
+    match client.authenticate(password) {
+        Err(RconError::AuthenticationFailed) if cli.no_auth => {
+            anyhow::bail!("The server requires an RCON password, run without --no-auth")
+        }
+        result => result?,
+    }
+    log::debug!("Authenticated");
+    client.set_read_timeout(cli.command_timeout.map(Duration::from_secs_f64))?;
+    Ok(client)
+}
+
+/// The /help response in the Java format, with each command on its own line
+fn fetch_help(cli: &Cli, session: &mut Session) -> Result<String, RconError> {
+    let prefix = &cli.command_char;
+    let first_page = session.send_command(&format!("{prefix}help"))?;
+    match cli.edition {
+        Edition::Java => Ok(help_parser::split_help_entries(&first_page, prefix)),
+        // Bedrock only sends one page at a time
+        Edition::Bedrock => {
+            let mut pages = vec![first_page.clone()];
+            let total = help_parser::help_page_count(&first_page).unwrap_or(1);
+            let on_terminal = std::io::stderr().is_terminal();
+            for page in 2..=total {
+                if !cli.quiet {
+                    // Overwrite the previous message in place on a terminal
+                    let message = help_parser::page_progress(page, total);
+                    match on_terminal {
+                        true => eprint!("\r{message}"),
+                        false => eprintln!("{message}"),
                     }
-                    None => Ok((0, Vec::new())),
                 }
+                pages.push(session.send_command(&format!("{prefix}help {page}"))?);
             }
+            if !cli.quiet && on_terminal && total > 1 {
+                eprint!("\r\x1b[K");
+            }
+            Ok(help_parser::normalize_bedrock_help(
+                &pages.join("\n"),
+                prefix,
+            ))
         }
     }
 }
 
-impl Hinter for MinecraftCompleter {
-    type Hint = String;
-    fn hint(&self, line: &str, _pos: usize, _ctx: &RustyContext<'_>) -> Option<String> {
-        if line.is_empty() || line == "/" || !line.starts_with('/') || line.contains(' ') {
-            return None;
-        }
-        if let Some(cmd_name) = self
-            .commands
-            .keys()
-            .find(|cmd_name| cmd_name.starts_with(line))
-        {
-            return Some(cmd_name[line.len()..].to_string());
+/// The bundled command list of the server's edition
+fn fallback_commands(cli: &Cli) -> HashMap<String, Vec<Argument>> {
+    let prefix = &cli.command_char;
+    let fallback: Vec<String> = cli
+        .edition
+        .fallback_help()
+        .lines()
+        .map(|line| line.replacen('/', prefix, 1))
+        .collect();
+    help_parser::parse_commands(fallback.join("\n"), prefix)
+}
+
+/// The bundled usages, for commands sent without fetching /help first
+fn bundled_commands(cli: &Cli) -> HashMap<String, Vec<Argument>> {
+    let mut commands = fallback_commands(cli);
+    if cli.edition == Edition::Java {
+        mc_commands::apply_curated(&mut commands, &cli.command_char);
+        CompletionSpec::bundled().apply_known(&mut commands, &cli.command_char);
+    }
+    commands
+}
+
+/// The commands to complete, with where each one's usage came from
+fn load_commands(
+    cli: &Cli,
+    session: &mut Session,
+) -> (
+    HashMap<String, Vec<Argument>>,
+    HashMap<String, CandidateSource>,
+) {
+    let prefix = &cli.command_char;
+    let mut commands = if cli.no_help_fetch {
+        HashMap::new()
+    } else {
+        let help = fetch_help(cli, session).unwrap_or_else(|e| {
+            log::warn!("Fetching {prefix}help failed: {e}");
+            String::new()
+        });
+        let commands = help_parser::parse_commands(help, prefix);
+        log::debug!("Parsed {} commands from {prefix}help", commands.len());
+        commands
+    };
+    let mut sources = HashMap::new();
+    record_sources(
+        &mut sources,
+        &HashMap::new(),
+        &commands,
+        CandidateSource::Help,
+    );
+    if commands.is_empty() {
+        if cli.no_help_fetch {
+            log::info!("Completing from the bundled command list");
+        } else {
+            log::warn!("Completing from the bundled command list");
         }
-        // TODO: Add support for argument hinting
-        None
+        commands = fallback_commands(cli);
+        record_sources(
+            &mut sources,
+            &HashMap::new(),
+            &commands,
+            CandidateSource::Fallback,
+        );
+    }
+    // The curated shapes follow the Java syntax
+    if cli.edition == Edition::Java {
+        let before = commands.clone();
+        mc_commands::apply_curated(&mut commands, prefix);
+        CompletionSpec::bundled().apply_known(&mut commands, prefix);
+        record_sources(&mut sources, &before, &commands, CandidateSource::Curated);
     }
+    (commands, sources)
 }
 
-impl Highlighter for MinecraftCompleter {
-    fn highlight_candidate<'c>(
-        &self,
-        candidate: &'c str,
-        _completion: rustyline::CompletionType,
-    ) -> Cow<'c, str> {
-        Cow::Owned(highlight_command(self, candidate, true))
+/// Send a profile's `on_connect` commands in order, printing their responses unless
+/// `--quiet` is given
+fn run_on_connect(
+    cli: &Cli,
+    session: &mut Session,
+    options: &DisplayOptions,
+    log: &mut Option<Transcript>,
+    bundled: &HashMap<String, Vec<Argument>>,
+    on_connect: &[String],
+) {
+    for cmd in on_connect {
+        let cmd = wrap_command(cli, &prepare_command(cli, bundled, cmd));
+        if let Some(warning) = length_warning(cli, &cmd) {
+            log::warn!("{warning}");
+        }
+        if !cli.quiet {
+            send_and_print(session, options, log, &mut None, &cmd);
+            continue;
+        }
+        match session.send_command(&cmd) {
+            Ok(body) => log::debug!("{cmd} answered {}", display::redact(&body, &options.redact)),
+            Err(e) => log::warn!("{cmd} failed: {e}"),
+        }
     }
+}
 
-    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
-        Cow::Owned(highlight_command(self, line, false))
+/// Send the commands of a batch in order, stopping at the first failure with
+/// `--fail-fast`. Returns the outcomes and, when it stopped early, why.
+fn run_batch(
+    cli: &Cli,
+    session: &mut Session,
+    options: &DisplayOptions,
+    log: &mut Option<Transcript>,
+    aliases: &Aliases,
+    bundled: &HashMap<String, Vec<Argument>>,
+    commands: Vec<String>,
+) -> (BatchSummary, Option<String>) {
+    let delay = cli.replay_delay.map(Duration::from_millis);
+    let mut summary = BatchSummary::default();
+    let mut failed: Option<String> = None;
+    for (index, mut cmd) in commands.into_iter().enumerate() {
+        if let Some(delay) = transcript::replay_delay(index, delay) {
+            std::thread::sleep(delay);
+        }
+        if cli.strip_ansi_from_input {
+            cmd = display::strip_ansi(&cmd);
+        }
+        let cmd = match aliases.expand(&cmd).and_then(|cmd| expand_env(cli, cmd)) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                summary.record(true);
+                if cli.fail_fast {
+                    failed = Some(e);
+                    break;
+                }
+                continue;
+            }
+        };
+        let cmd = prepare_command(cli, bundled, &cmd);
+        autosave_before(cli, session, options, log, &cmd);
+        let cmd = wrap_command(cli, &cmd);
+        if let Some(warning) = length_warning(cli, &cmd) {
+            log::warn!("{warning}");
+        }
+        let result = send_and_print(session, options, log, &mut None, &cmd);
+        let is_error = result.is_none_or(|result| result.is_error);
+        summary.record(is_error);
+        if cli.fail_fast && is_error {
+            failed = Some(format!("Stopped after {cmd} failed"));
+            break;
+        }
     }
+    (summary, failed)
 }
 
-fn highlight_command(completer: &MinecraftCompleter, s: &str, is_suggestion: bool) -> String {
-    let mut colored = String::new();
+/// Connect again when the last command found the connection dropped, or when it sat
+/// idle for longer than --reconnect-on-idle allows. Returns whether a dropped connection
+/// was re-established.
+fn reconnect_if_needed(
+    cli: &Cli,
+    addr: &str,
+    password: &str,
+    session: &mut Session,
+    state: ConnectionState,
+    idle: bool,
+) -> Result<bool> {
+    let dropped = state == ConnectionState::Dropped;
+    match (dropped, idle) {
+        (true, _) => log::info!("Reconnecting to {addr}"),
+        (false, true) => log::info!("Reconnecting to {addr} after being idle"),
+        (false, false) => return Ok(false),
+    }
+    session.replace_client(connect(cli, addr, password)?);
+    Ok(dropped)
+}
 
-    let words: Vec<&str> = s.split_whitespace().collect();
-    if words.is_empty() {
-        return s.to_string();
+/// Apply the opt-in rewrites to a command right before it is sent, `commands` telling
+/// where its arguments take IDs
+fn prepare_command(cli: &Cli, commands: &HashMap<String, Vec<Argument>>, cmd: &str) -> String {
+    if !cli.canonicalize_ids {
+        return cmd.to_string();
     }
-    let command_found = completer
-        .commands
-        .iter()
-        .any(|(cmd_name, _)| cmd_name == words[0]);
+    let (canonical, changes) = canonicalize_ids(cmd, commands);
+    for (original, replacement) in changes {
+        log::warn!("Sending {original} as {replacement}");
+    }
+    canonical
+}
 
-    if command_found {
-        if is_suggestion {
-            colored.push_str("\x1b[33m"); // yellow
-        } else {
-            colored.push_str("\x1b[32m"); // green
+/// Expand environment variables in a command for --expand-env, after its aliases
+fn expand_env(cli: &Cli, cmd: String) -> Result<String, String> {
+    match cli.expand_env {
+        true => {
+            script::expand_env(&cmd, |name| std::env::var(name).ok()).map_err(|e| e.to_string())
         }
-        colored.push_str(words[0]);
-        colored.push_str("\x1b[0m"); // reset
-    } else {
-        colored.push_str(words[0]);
+        false => Ok(cmd),
     }
-    colored.push_str(&s[words[0].len()..]);
-    colored
 }
 
-impl Validator for MinecraftCompleter {
-    fn validate(
-        &self,
-        _ctx: &mut ValidationContext<'_>,
-    ) -> Result<ValidationResult, ReadlineError> {
-        Ok(ValidationResult::Valid(None))
+/// Apply `--prepend` and `--append` to a command about to be sent. The unwrapped command
+/// is the one completed, recorded and remembered.
+fn wrap_command(cli: &Cli, cmd: &str) -> String {
+    command::wrap(
+        cmd,
+        cli.prepend.as_deref(),
+        cli.append.as_deref(),
+        &cli.command_char,
+    )
+}
+
+/// Why a command about to be sent may not reach the server whole, if it may not
+fn length_warning(cli: &Cli, cmd: &str) -> Option<String> {
+    rcon::over_length_limit(cmd, cli.max_command_length).map(|len| {
+        format!(
+            "The command is {len} bytes, over the {} byte limit",
+            cli.max_command_length
+        )
+    })
+}
+
+/// Send /save-all first when `cmd` matches `--autosave-before`, printing its response
+fn autosave_before(
+    cli: &Cli,
+    session: &mut Session,
+    options: &DisplayOptions,
+    log: &mut Option<Transcript>,
+    cmd: &str,
+) {
+    if command::needs_autosave(cmd, &cli.autosave_before) {
+        let save = wrap_command(cli, &format!("{}save-all", cli.command_char));
+        send_and_print(session, options, log, &mut None, &save);
     }
 }
 
-impl Helper for MinecraftCompleter {}
+/// Run `cmd` every `interval` until Ctrl-C, printing only the response lines no earlier
+/// run printed. Stops at the first failure.
+fn tail(
+    session: &mut Session,
+    options: &DisplayOptions,
+    cmd: &str,
+    interval: Duration,
+) -> Result<(), RconError> {
+    let _interrupts = terminal::catch_interrupts();
+    let mut tracker = LineTracker::default();
+    outln!(
+        "Running {cmd} every {}s, Ctrl-C to stop",
+        interval.as_secs_f64()
+    );
+    loop {
+        let result = command::run_command(session, options, cmd)?;
+        for line in tracker.new_lines(&result.formatted) {
+            outln!("{line}");
+        }
+        if tail::sleep_unless_interrupted(interval) {
+            return Ok(());
+        }
+    }
+}
 
-fn format_generic_response(body: &str) -> String {
-    if let Some(prefix) = ERROR_PREFIXES
-        .iter()
-        .find(|prefix| body.starts_with(*prefix))
-    {
-        let suffix = &body[prefix.len()..];
-        format!("{}\n{}", prefix, suffix.trim_start())
-    } else {
-        body.to_string()
+/// Ask a yes or no question in the shell, anything but yes meaning no
+fn confirm(rl: &mut Editor<MinecraftCompleter, DefaultHistory>, prompt: &str) -> bool {
+    let answer = rl.readline(prompt);
+    matches!(answer.as_deref().map(str::trim), Ok("y" | "Y" | "yes"))
+}
+
+/// Debug log of every command sent, whichever mode or meta-command sent it
+fn log_command(cmd: &str, response: Result<&str, &RconError>) {
+    match response {
+        Ok(body) => log::debug!("Sent {cmd}, received {} bytes", body.len()),
+        Err(e) => log::debug!("Sent {cmd}, failed: {e}"),
+    }
+}
+
+/// Send a command and print its response, returning the result on success
+fn send_and_print(
+    session: &mut Session,
+    options: &DisplayOptions,
+    log: &mut Option<Transcript>,
+    more: &mut Option<String>,
+    cmd: &str,
+) -> Option<CommandResult> {
+    let start = Instant::now();
+    let result = command::run_command(session, options, cmd);
+    let elapsed = match &result {
+        Ok(result) => result.elapsed,
+        Err(_) => start.elapsed(),
+    };
+    if notify::should_notify(elapsed, options.notify_after) {
+        notify::notify(cmd, elapsed, options.notify_desktop);
+    }
+    match result {
+        Ok(result) => {
+            if !options.output_only_file {
+                let (rendered, rest) = render_response(options, cmd, &result);
+                outln!("{rendered}");
+                *more = rest;
+            }
+            if let Some(dir) = &options.output_dir {
+                // With raw, the formatted body is the redacted body
+                let contents = match options.raw {
+                    true => result.formatted.clone(),
+                    false => display::strip_ansi(&result.formatted),
+                };
+                if let Err(e) = output::write_output(dir, cmd, &contents, &options.command_char) {
+                    eprintln!("Error: {e:#}");
+                }
+            }
+            if let Some(log) = log {
+                log.record(cmd, &display::redact(&result.body, &options.redact));
+            }
+            Some(result)
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            if let Some(log) = log {
+                log.record(cmd, &format!("Error: {e}"));
+            }
+            None
+        }
     }
 }
 
 fn main() -> Result<()> {
-    let config = Config::builder()
-        .completion_type(CompletionType::List)
-        .build();
-    let mut rl = Editor::<MinecraftCompleter, DefaultHistory>::with_config(config).unwrap();
+    logging::init();
+    terminal::save();
+    terminal::install_panic_hook();
+    let result = run();
+    terminal::restore();
+    result
+}
 
-    println!("Minecraft RCON CLI");
+fn run() -> Result<()> {
     let cli = Cli::parse();
-    let addr = cli.address;
-    let password = match cli.password {
-        Some(pw) => pw,
-        None => prompt_password("Enter RCON password: ").expect("Failed to read password"),
+    // Resolve the whole command file up front so a missing variable fails before connecting
+    let batch = match &cli.command_file {
+        Some(path) => Some(script::load_command_file(path, |name| {
+            cli.vars
+                .iter()
+                .find(|(var, _)| var == name)
+                .map(|(_, value)| value.clone())
+                .or_else(|| std::env::var(name).ok())
+        })?),
+        None => match &cli.replay {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read log file {}", path.display()))?;
+                Some(transcript::extract_commands(&contents))
+            }
+            None => None,
+        },
+    };
+    let mut log = cli.log_file.as_deref().map(Transcript::open).transpose()?;
+    let spec = cli
+        .completion_spec
+        .as_deref()
+        .map(CompletionSpec::load)
+        .transpose()?
+        .unwrap_or_default();
+    if let Some(path) = &cli.tee {
+        tee::open(path, cli.tee_ansi)?;
+    }
+    if let Some(dir) = &cli.output_dir {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create output directory {}", dir.display()))?;
+    }
+    if batch.is_none()
+        && cli.serve.is_none()
+        && cli.control_socket.is_none()
+        && !cli.doctor
+        && !cli.print_config
+    {
+        outln!("Minecraft RCON CLI");
+    }
+
+    let mut options = DisplayOptions::from_cli(&cli);
+    let config_path = cli.config.clone().or_else(config::default_path);
+    let user_config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let mut aliases = Aliases::new(user_config.aliases.clone());
+    let mut macros = Macros::new(user_config.macros.clone());
+    let configured_addr = cli::resolve_value(
+        cli.address.as_deref(),
+        cli.address_file.as_deref(),
+        std::env::var(cli::ADDRESS_ENV).ok(),
+    )?;
+    let profiles = &user_config.profiles;
+    let profile_name = match (&cli.profile, cli.select_profile) {
+        (Some(name), _) => Some(name.clone()),
+        (None, true) if profiles.is_empty() => anyhow::bail!("The config file has no profiles"),
+        (None, true) => Some(profile::pick(profiles)?),
+        // Only ask when there is nothing else to connect to
+        (None, false) if configured_addr.is_none() && profiles.len() == 1 => {
+            profiles.keys().next().cloned()
+        }
+        (None, false) if configured_addr.is_none() && profiles.len() > 1 => {
+            Some(profile::pick(profiles)?)
+        }
+        (None, false) => None,
+    };
+    let profile = match &profile_name {
+        Some(name) => Some(profiles.get(name).cloned().ok_or_else(|| {
+            anyhow::anyhow!(
+                "No profile '{name}' in the config file, the profiles are:\n{}",
+                profile::format_menu(profiles)
+            )
+        })?),
+        None => None,
+    };
+    if cli.print_config {
+        let settings = effective::resolve(
+            &cli,
+            config_path.as_deref(),
+            profile_name.as_deref().zip(profile.as_ref()),
+            |name| std::env::var(name).ok(),
+        );
+        outln!("{}", effective::format_settings(&settings));
+        return Ok(());
+    }
+    let addr = match &profile {
+        Some(profile) => profile.address.clone(),
+        None => configured_addr.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No server address given, use --address, --address-file, --profile or {}",
+                cli::ADDRESS_ENV
+            )
+        })?,
+    };
+    let profile_password = match profile.as_ref().map(|p| (&p.password, &p.password_file)) {
+        Some((Some(password), _)) => Some(password.clone()),
+        Some((None, Some(path))) => Some(cli::read_value_file(path)?),
+        _ => None,
+    };
+    let password = if cli.no_auth {
+        String::new()
+    } else {
+        match cli::resolve_value(
+            cli.password.as_deref(),
+            cli.password_file.as_deref(),
+            profile_password.or_else(|| std::env::var(cli::PASSWORD_ENV).ok()),
+        )? {
+            Some(pw) => pw,
+            None => prompt_password("Enter RCON password: ").expect("Failed to read password"),
+        }
+    };
+
+    if cli.doctor {
+        let checks = doctor::run(&doctor::Target {
+            addr: &addr,
+            password: &password,
+            proxy: cli.proxy.as_ref(),
+            command_char: &cli.command_char,
+            edition: cli.edition,
+        });
+        for check in &checks {
+            outln!("{}", doctor::format_check(check));
+        }
+        match doctor::exit_code(&checks) {
+            0 => return Ok(()),
+            code => std::process::exit(code),
+        }
+    }
+
+    let client = connect(&cli, &addr, &password)?;
+
+    if let Some(serve_addr) = &cli.serve {
+        let reconnect = reconnector(&cli, &addr, &password);
+        return serve::run(serve_addr, cli.serve_allow_remote, client, reconnect);
+    }
+    if let Some(path) = &cli.control_socket {
+        #[cfg(unix)]
+        return control::run(path, client, reconnector(&cli, &addr, &password));
+        #[cfg(not(unix))]
+        anyhow::bail!(
+            "--control-socket {} needs Unix domain sockets",
+            path.display()
+        );
+    }
+    let mut session = Session::new(client).on_command(log_command);
+    // Only looked up by --canonicalize-ids, the shell uses the usages from /help instead
+    let bundled = match cli.canonicalize_ids {
+        true => bundled_commands(&cli),
+        false => HashMap::new(),
     };
+    if let Some(profile) = &profile {
+        run_on_connect(
+            &cli,
+            &mut session,
+            &options,
+            &mut log,
+            &bundled,
+            &profile.on_connect,
+        );
+    }
 
-    let mut client = Client::new(addr.clone()).map_err(|e| anyhow::anyhow!(e.to_string()))?;
-    client
-        .authenticate(password.clone())
-        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    if let Some(commands) = batch {
+        // There is no :more to show the rest later
+        options.max_output_lines = None;
+        let (summary, failed) = run_batch(
+            &cli,
+            &mut session,
+            &options,
+            &mut log,
+            &aliases,
+            &bundled,
+            commands,
+        );
+        if !cli.quiet {
+            eprintln!("{summary}");
+        }
+        if let Some(e) = failed {
+            anyhow::bail!("{e}");
+        }
+        return Ok(());
+    }
 
-    // Fetch and parse /help for dynamic completion
-    let help_response = client
-        .send_command("/help".to_string())
-        .map_err(|e| anyhow::anyhow!(e.to_string()))?
-        .body;
-    let commands = help_parser::parse_commands(help_parser::format_help_response(&help_response));
-    rl.set_helper(Some(MinecraftCompleter { commands }));
-    println!("Connected. Type Minecraft commands or 'exit' to quit.");
+    let config = cli::editor_config(&cli, user_config.max_history)?;
+    let mut rl = Editor::<MinecraftCompleter, DefaultHistory>::with_config(config).unwrap();
+    let history_path = cli::history_path(&cli);
+    if let Some(path) = &history_path {
+        // A missing history file just means a first run
+        let _ = rl.load_history(path);
+    }
 
+    let (mut commands, mut sources) = load_commands(&cli, &mut session);
+    let before = commands.clone();
+    spec.apply(&mut commands, &cli.command_char);
+    record_sources(&mut sources, &before, &commands, CandidateSource::Spec);
+    // Seed player name completion, it is refreshed whenever /list is run
+    let mut cache = ServerCache {
+        overrides: spec.arguments,
+        ..Default::default()
+    };
+    let list = format!("{}list", cli.command_char);
+    let seeded = match cli.retry_empty {
+        true => rcon::retry_empty(|| session.send_command(&list)),
+        false => session.send_command(&list),
+    };
+    if let Ok(body) = seeded {
+        cache.observe(&list, &body, &cli.command_char);
+    }
+    rl.bind_sequence(
+        KeyEvent::alt('a'),
+        EventHandler::Conditional(Box::new(TemplateHandler::new(commands.clone()))),
+    );
+    // Filled in by a `run` key binding, which then accepts the empty line
+    let palette_pending = PendingCommand::default();
+    for (name, binding) in &user_config.keys {
+        let key = palette::parse_key(name).map_err(anyhow::Error::msg)?;
+        let handler = PaletteHandler::new(binding.clone(), palette_pending.clone());
+        rl.bind_sequence(key, EventHandler::Conditional(Box::new(handler)));
+    }
+    rl.set_helper(Some(MinecraftCompleter {
+        commands,
+        cache,
+        no_color: options.no_color,
+        completion_type: config.completion_type(),
+        command_char: cli.command_char.clone(),
+        typed_word: Default::default(),
+        aliases: aliases.clone(),
+        namespace_style: cli.namespace_style,
+        edition: cli.edition,
+        colors: options.colors,
+        preview: cli.preview,
+        sources,
+        debug_completion: cli.debug_completion,
+    }));
+    match cli.quit_keywords.first() {
+        Some(keyword) => outln!("Connected. Type Minecraft commands or '{keyword}' to quit."),
+        None => outln!("Connected. Type Minecraft commands or press Ctrl-D to quit."),
+    }
+
+    let mut state = ConnectionState::Connected;
+    // The previous command and its response body, for :grep
+    let mut last_response: Option<(String, String)> = None;
+    let mut duplicates = DuplicateGuard::default();
+    // The lines of the last response cut off by --max-output-lines
+    let mut more: Option<String> = None;
+    // Commands of a running macro, sent before reading more input
+    let mut queued: VecDeque<String> = VecDeque::new();
+    // Set with :dim, commands are sent wrapped in /execute in
+    let mut dimension: Option<String> = None;
+    // Only piped commands stop at a failure, a person can decide for themselves
+    let piped = !std::io::stdin().is_terminal();
+    let fail_fast = cli.fail_fast && piped;
+    let mut failed: Option<String> = None;
+    let mut summary = BatchSummary::default();
+    // When the last command went out, for --reconnect-on-idle
+    let mut last_used = Instant::now();
+    let idle_threshold = cli.reconnect_on_idle.map(Duration::from_secs_f64);
     loop {
-        let readline = rl.readline("> ");
+        let prompt = connection::render_prompt(&cli.prompt, state, !options.no_color);
+        let from_macro = !queued.is_empty();
+        let readline = match queued.pop_front() {
+            Some(line) => {
+                outln!("{prompt}{line}");
+                Ok(line)
+            }
+            None => rl.readline(&prompt),
+        };
         match readline {
-            Ok(line) => {
+            Ok(mut line) => {
+                if cli.strip_ansi_from_input {
+                    line = display::strip_ansi(&line);
+                }
                 let cmd = line.trim();
-                if cmd.eq_ignore_ascii_case("exit") || cmd.eq_ignore_ascii_case("quit") {
+                if is_quit_command(cmd, &cli.quit_keywords) {
                     break;
                 }
                 if cmd.is_empty() {
+                    if let Some(picked) = palette_pending.lock().unwrap().take() {
+                        queued.push_front(picked);
+                    }
                     continue;
                 }
-                // Ignore failures in history addition
-                let _ = rl.add_history_entry(cmd);
-                match client.send_command(cmd.to_string()) {
-                    Ok(response) => {
-                        if cmd.starts_with("help") || cmd.starts_with("/help") {
-                            println!("{}", help_parser::format_help_response(&response.body));
+                // Shown like a shell does, and remembered in its expanded form
+                let recalled = match from_macro {
+                    true => None,
+                    false => {
+                        let entries: Vec<&str> = rl.history().iter().map(String::as_str).collect();
+                        history::expand(cmd, &entries, &cli.command_char)
+                    }
+                };
+                let cmd = match &recalled {
+                    Some(Ok(recalled)) => {
+                        outln!("{recalled}");
+                        recalled.as_str()
+                    }
+                    Some(Err(e)) => {
+                        eprintln!("Error: {e}");
+                        continue;
+                    }
+                    None => cmd,
+                };
+                if !from_macro {
+                    // Ignore failures in history addition
+                    let _ = rl.add_history_entry(cmd);
+                }
+                let expanded = match aliases.expand(cmd).and_then(|cmd| expand_env(&cli, cmd)) {
+                    Ok(expanded) => expanded,
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        continue;
+                    }
+                };
+                let cmd = expanded.as_str();
+                let parsed = meta::parse(cmd);
+                let sends = match &parsed {
+                    None => true,
+                    Some(Ok(meta)) => meta.sends_command(),
+                    Some(Err(_)) => false,
+                };
+                // Re-establish a dropped or idle connection before sending anything else
+                let mut reconnected = false;
+                if sends {
+                    let idle = connection::is_idle(last_used, Instant::now(), idle_threshold);
+                    match reconnect_if_needed(&cli, &addr, &password, &mut session, state, idle) {
+                        Ok(dropped) => reconnected = dropped,
+                        Err(e) => {
+                            eprintln!("Error: Reconnecting failed: {e}");
+                            continue;
+                        }
+                    }
+                    last_used = Instant::now();
+                }
+                match parsed {
+                    Some(Ok(MetaCommand::Set(None))) => outln!("{}", options.describe()),
+                    Some(Ok(MetaCommand::Set(Some((option, value))))) => {
+                        match options.set(&option, value) {
+                            Ok(()) => {
+                                if let Some(helper) = rl.helper_mut() {
+                                    helper.no_color = options.no_color;
+                                }
+                            }
+                            Err(e) => eprintln!("Error: {e}"),
+                        }
+                    }
+                    Some(Ok(MetaCommand::Info(command))) => {
+                        let command = command
+                            .strip_prefix(cli.command_char.as_str())
+                            .unwrap_or(&command);
+                        match session.send_command(&format!("{}help {command}", cli.command_char)) {
+                            Ok(body) => match help_parser::parse_command_help(
+                                &body,
+                                command,
+                                &cli.command_char,
+                            ) {
+                                Some(help) => {
+                                    outln!("{}", help.signature);
+                                    if let Some(description) = help.description {
+                                        outln!("{description}");
+                                    }
+                                }
+                                None => eprintln!(
+                                    "Error: No help available for {}{command}",
+                                    cli.command_char
+                                ),
+                            },
+                            Err(e) => {
+                                eprintln!("Error: {e}");
+                                state = ConnectionState::after_command(None, false);
+                            }
+                        }
+                    }
+                    Some(Ok(MetaCommand::Grep { pattern, invert })) => {
+                        match (grep::parse_pattern(&pattern), &last_response) {
+                            (Err(e), _) => eprintln!("Error: {e}"),
+                            (Ok(_), None) => eprintln!("Error: No response to filter yet"),
+                            (Ok(re), Some((cmd, body))) => {
+                                let formatted = display::redact(
+                                    &format_response(&options, cmd, body),
+                                    &options.redact,
+                                );
+                                let highlight =
+                                    (!options.no_color).then_some(options.colors.grep_match);
+                                outln!(
+                                    "{}",
+                                    grep::filter_lines(&formatted, &re, invert, highlight)
+                                );
+                            }
+                        }
+                    }
+                    Some(Ok(MetaCommand::Shortcut(shortcut)))
+                        if !shortcut.supports(cli.edition) =>
+                    {
+                        eprintln!("Error: :{} needs a Bedrock server", shortcut.name);
+                    }
+                    Some(Ok(MetaCommand::Shortcut(shortcut))) => {
+                        match command::run_command(
+                            &mut session,
+                            &options,
+                            &shortcut.command(&cli.command_char),
+                        ) {
+                            Ok(result) => match shortcut.summarize(&result.body) {
+                                Some(summary) => {
+                                    outln!("{}", display::redact(&summary, &options.redact))
+                                }
+                                None => outln!("{}", result.formatted),
+                            },
+                            Err(e) => {
+                                eprintln!("Error: {e}");
+                                state = ConnectionState::after_command(None, false);
+                            }
+                        }
+                    }
+                    Some(Ok(MetaCommand::Dim(new_dimension))) => {
+                        match &new_dimension {
+                            Some(name) => outln!("Running commands in {name}"),
+                            None => outln!("Running commands in their default dimension"),
+                        }
+                        dimension = new_dimension;
+                    }
+                    Some(Ok(MetaCommand::Where(selector))) => {
+                        let query = shortcuts::where_command(&selector, &cli.command_char);
+                        match command::run_command(&mut session, &options, &query) {
+                            Ok(result) => match shortcuts::parse_position(&result.body) {
+                                Some(position) => {
+                                    outln!("{}", shortcuts::format_position(position))
+                                }
+                                None => outln!("{}", result.formatted),
+                            },
+                            Err(e) => {
+                                eprintln!("Error: {e}");
+                                state = ConnectionState::after_command(None, false);
+                            }
+                        }
+                    }
+                    Some(Ok(MetaCommand::Uuid(name))) => {
+                        match rl.helper().and_then(|helper| helper.cache.uuid(&name)) {
+                            Some(uuid) => outln!("{uuid}"),
+                            None => eprintln!(
+                                "Error: No UUID known for {name}, run {}list uuids first",
+                                cli.command_char
+                            ),
+                        }
+                    }
+                    Some(Ok(MetaCommand::Alias { name, expansion })) => {
+                        match aliases.define(&name, &expansion) {
+                            Ok(()) => {
+                                if let Some(helper) = rl.helper_mut() {
+                                    helper.aliases = aliases.clone();
+                                }
+                            }
+                            Err(e) => eprintln!("Error: {e}"),
+                        }
+                    }
+                    Some(Ok(MetaCommand::Unalias(name))) => {
+                        if aliases.remove(&name) {
+                            if let Some(helper) = rl.helper_mut() {
+                                helper.aliases = aliases.clone();
+                            }
                         } else {
-                            println!("{}", format_generic_response(&response.body));
+                            eprintln!("Error: No alias named '{name}'");
+                        }
+                    }
+                    Some(Ok(MetaCommand::Aliases)) => match aliases.describe() {
+                        list if list.is_empty() => outln!("No aliases defined"),
+                        list => outln!("{list}"),
+                    },
+                    Some(Ok(MetaCommand::AliasSave)) => match &config_path {
+                        Some(path) => {
+                            // Re-read the file so settings changed since startup are kept
+                            let saved = Config::load(path).and_then(|mut saved| {
+                                saved.aliases = aliases.to_map();
+                                saved.save(path)
+                            });
+                            match saved {
+                                Ok(()) => outln!("Saved aliases to {}", path.display()),
+                                Err(e) => eprintln!("Error: {e:#}"),
+                            }
+                        }
+                        None => eprintln!("Error: No config file location, pass --config"),
+                    },
+                    Some(Ok(MetaCommand::Commands)) => {
+                        let mut names: Vec<String> = rl
+                            .helper()
+                            .map(|helper| {
+                                helper
+                                    .commands
+                                    .keys()
+                                    .filter(|name| !name.contains(' '))
+                                    .cloned()
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        names.sort();
+                        if !options.no_color {
+                            for name in &mut names {
+                                *name = ColorScheme::paint(options.colors.suggestion, name);
+                            }
+                        }
+                        outln!("{}", display::columns(&names, terminal::width()));
+                    }
+                    Some(Ok(MetaCommand::More)) => match more.take() {
+                        Some(rest) => outln!("{rest}"),
+                        None => eprintln!("Error: Nothing more to show"),
+                    },
+                    Some(Ok(MetaCommand::MacroRecord(name))) => match macros.start(&name) {
+                        Ok(()) => outln!("Recording '{name}', end it with :macro stop"),
+                        Err(e) => eprintln!("Error: {e}"),
+                    },
+                    Some(Ok(MetaCommand::MacroStop)) => match macros.stop() {
+                        Ok((name, len)) => {
+                            outln!("Recorded {len} commands as '{name}'");
+                            match &config_path {
+                                Some(path) => {
+                                    let saved = Config::load(path).and_then(|mut saved| {
+                                        saved.macros = macros.to_map();
+                                        saved.save(path)
+                                    });
+                                    if let Err(e) = saved {
+                                        eprintln!("Error: {e:#}");
+                                    }
+                                }
+                                None => {
+                                    log::warn!("No config file location, '{name}' won't be kept")
+                                }
+                            }
+                        }
+                        Err(e) => eprintln!("Error: {e}"),
+                    },
+                    Some(Ok(MetaCommand::MacroRun(name))) => match macros.get(&name) {
+                        Some(commands) => queued.extend(commands.iter().cloned()),
+                        None => eprintln!("Error: No macro named '{name}'"),
+                    },
+                    // Sent like a macro's commands, so it goes through the same checks
+                    Some(Ok(MetaCommand::SendFile(path))) => {
+                        match script::load_single_command(Path::new(&path)) {
+                            Ok(cmd) => queued.push_front(cmd),
+                            Err(e) => eprintln!("Error: {e:#}"),
+                        }
+                    }
+                    Some(Ok(MetaCommand::Tail { command, interval })) => {
+                        let commands = &rl.helper().expect("helper is set").commands;
+                        let cmd = prepare_command(&cli, commands, &command);
+                        let cmd =
+                            command::in_dimension(&cmd, dimension.as_deref(), &cli.command_char);
+                        let sent = wrap_command(&cli, &cmd);
+                        if let Err(e) = tail(&mut session, &options, &sent, interval) {
+                            eprintln!("Error: {e}");
+                            state = ConnectionState::after_command(None, false);
+                        }
+                        last_used = Instant::now();
+                    }
+                    Some(Err(e)) => eprintln!("Error: {e}"),
+                    None => {
+                        let commands = &rl.helper().expect("helper is set").commands;
+                        let cmd = prepare_command(&cli, commands, cmd);
+                        if cli.guard_duplicates
+                            && !from_macro
+                            && duplicates.is_duplicate(&cmd, Instant::now())
+                            && !confirm(&mut rl, "Same command as just now, send again? [y/N] ")
+                        {
+                            outln!("Not sent");
+                            continue;
+                        }
+                        let sent = wrap_command(
+                            &cli,
+                            &command::in_dimension(&cmd, dimension.as_deref(), &cli.command_char),
+                        );
+                        if let Some(warning) = length_warning(&cli, &sent) {
+                            if !confirm(&mut rl, &format!("{warning}, send anyway? [y/N] ")) {
+                                outln!("Not sent");
+                                continue;
+                            }
+                        }
+                        let invalid =
+                            rl.helper()
+                                .filter(|_| cli.validate_args)
+                                .and_then(|helper| {
+                                    command::invalid_choice(
+                                        &helper.commands,
+                                        &cmd,
+                                        &cli.command_char,
+                                    )
+                                });
+                        if let Some(invalid) = invalid {
+                            if !confirm(&mut rl, &format!("{invalid}, send anyway? [y/N] ")) {
+                                outln!("Not sent");
+                                continue;
+                            }
+                        }
+                        autosave_before(&cli, &mut session, &options, &mut log, &cmd);
+                        let result =
+                            send_and_print(&mut session, &options, &mut log, &mut more, &sent);
+                        last_used = Instant::now();
+                        duplicates.record(&cmd, Instant::now());
+                        macros.record(&cmd);
+                        state = ConnectionState::after_command(
+                            result.as_ref().map(|result| result.is_error),
+                            reconnected,
+                        );
+                        if let (Some(result), Some(helper)) = (&result, rl.helper_mut()) {
+                            helper.cache.observe(&cmd, &result.body, &cli.command_char);
+                        }
+                        let is_error = result.as_ref().is_none_or(|result| result.is_error);
+                        summary.record(is_error);
+                        if fail_fast && is_error {
+                            failed = Some(cmd);
+                            break;
+                        }
+                        if let Some(result) = result {
+                            last_response = Some((cmd, result.body));
                         }
                     }
-                    Err(e) => eprintln!("Error: {e}"),
                 }
             }
             Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {
                 break;
             }
             Err(err) => {
-                println!("Error: {err:?}");
+                outln!("Error: {err:?}");
                 break;
             }
         }
     }
+    if let Some(path) = &history_path {
+        if let Err(e) = rl.save_history(path) {
+            log::warn!("Failed to save history to {}: {e}", path.display());
+        }
+    }
+    if piped && !cli.quiet {
+        eprintln!("{summary}");
+    }
+    if let Some(cmd) = failed {
+        anyhow::bail!("Stopped after {cmd} failed");
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minecraft_fancy_rcon_cli::rcon::test_server::{client_for, packet};
+
+    #[test]
+    fn quit_keywords_only_match_bare_words() {
+        let keywords = ["exit".to_string(), "quit".to_string()];
+        assert!(is_quit_command("QUIT", &keywords));
+        assert!(!is_quit_command("/quit", &keywords));
+        assert!(!is_quit_command("exit now", &keywords));
+        assert!(!is_quit_command("exit", &[]));
+    }
+
+    #[test]
+    fn no_auth_explains_a_rejected_empty_password() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        std::thread::spawn(move || {
+            use std::io::{Read, Write};
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut request = [0u8; 14];
+            conn.read_exact(&mut request).unwrap();
+            // An empty response with request id -1, which rejects the password
+            let mut response = 10i32.to_le_bytes().to_vec();
+            response.extend((-1i32).to_le_bytes());
+            response.extend([2, 0, 0, 0, 0, 0]);
+            conn.write_all(&response).unwrap();
+        });
+        let cli = Cli::parse_from(["minecraft-fancy-rcon-cli", "--no-auth"]);
+        let e = connect_once(&cli, &addr, "").err().unwrap();
+        assert_eq!(
+            e.to_string(),
+            "The server requires an RCON password, run without --no-auth"
+        );
+    }
+
+    #[test]
+    fn no_help_fetch_completes_without_sending_anything() {
+        // Connecting needs only the listen backlog, nothing ever answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let sent = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut session = Session::new(RconClient::from_stream(stream)).on_command({
+            let sent = std::rc::Rc::clone(&sent);
+            move |cmd, _| sent.borrow_mut().push(cmd.to_string())
+        });
+        let cli = Cli::parse_from(["minecraft-fancy-rcon-cli", "--no-help-fetch"]);
+        let (commands, sources) = load_commands(&cli, &mut session);
+        assert!(sent.borrow().is_empty());
+        assert!(commands.contains_key("/give"));
+        assert_eq!(sources["/tp"], CandidateSource::Fallback);
+    }
+
+    #[test]
+    fn fallback_commands_follow_the_command_char() {
+        let cli = Cli::parse_from(["minecraft-fancy-rcon-cli", "--command-char", "!"]);
+        let commands = fallback_commands(&cli);
+        assert!(commands.contains_key("!give"));
+        assert!(!commands.contains_key("/give"));
+    }
+
+    #[test]
+    fn fetches_every_bedrock_help_page() {
+        let mut session = Session::new(client_for(|id, cmd| match cmd {
+            "/help" => packet(id, b"--- Showing help page 1 of 3 ---\n/list"),
+            "/help 2" => packet(id, b"/say <message: message>"),
+            "/help 3" => packet(id, b"/weather <clear|rain|thunder>"),
+            _ => packet(id, b""),
+        }));
+        let cli = Cli::parse_from([
+            "minecraft-fancy-rcon-cli",
+            "--edition",
+            "bedrock",
+            "--quiet",
+        ]);
+        assert_eq!(
+            fetch_help(&cli, &mut session).unwrap(),
+            "/list\n/say <message>\n/weather (clear|rain|thunder)"
+        );
+    }
+
+    #[test]
+    fn runs_on_connect_commands_in_order() {
+        let config: Config = toml::from_str(
+            "[profiles.survival]\naddress = \"localhost:25575\"\n\
+on_connect = [\"/save-on\", \"/scoreboard objectives setdisplay sidebar kills\"]",
+        )
+        .unwrap();
+        let sent = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut session = Session::new(client_for(|id, _| packet(id, b""))).on_command({
+            let sent = std::rc::Rc::clone(&sent);
+            move |cmd, response| sent.borrow_mut().push((cmd.to_string(), response.is_ok()))
+        });
+        let cli = Cli::parse_from(["minecraft-fancy-rcon-cli", "--quiet"]);
+        run_on_connect(
+            &cli,
+            &mut session,
+            &DisplayOptions::from_cli(&cli),
+            &mut None,
+            &HashMap::new(),
+            &config.profiles["survival"].on_connect,
+        );
+        assert_eq!(
+            *sent.borrow(),
+            [
+                ("/save-on".to_string(), true),
+                (
+                    "/scoreboard objectives setdisplay sidebar kills".to_string(),
+                    true
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn warns_about_commands_over_the_configured_length() {
+        let cli = Cli::parse_from(["minecraft-fancy-rcon-cli", "--max-command-length", "10"]);
+        assert_eq!(length_warning(&cli, "/say hello"), None);
+        assert_eq!(
+            length_warning(&cli, "/say hello!"),
+            Some("The command is 11 bytes, over the 10 byte limit".to_string())
+        );
+    }
+
+    #[test]
+    fn saves_the_world_before_a_matching_command() {
+        let sent = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut session = Session::new(client_for(|id, _| packet(id, b""))).on_command({
+            let sent = std::rc::Rc::clone(&sent);
+            move |cmd, _| sent.borrow_mut().push(cmd.to_string())
+        });
+        let cli = Cli::parse_from(["minecraft-fancy-rcon-cli", "--autosave-before"]);
+        let options = DisplayOptions::from_cli(&cli);
+        autosave_before(&cli, &mut session, &options, &mut None, "/say restarting");
+        assert!(sent.borrow().is_empty());
+        autosave_before(&cli, &mut session, &options, &mut None, "/stop");
+        assert_eq!(*sent.borrow(), ["/save-all"]);
+    }
+
+    /// The commands a batch sends, its outcomes and why it stopped early
+    fn batch(args: &[&str]) -> (Vec<String>, BatchSummary, Option<String>) {
+        let sent = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut session = Session::new(client_for(|id, cmd| match cmd {
+            "/fly" => packet(
+                id,
+                b"Unknown or incomplete command, see below for error  /fly<--[HERE]",
+            ),
+            _ => packet(id, b"ok"),
+        }))
+        .on_command({
+            let sent = std::rc::Rc::clone(&sent);
+            move |cmd, _| sent.borrow_mut().push(cmd.to_string())
+        });
+        let cli = Cli::parse_from(
+            std::iter::once("minecraft-fancy-rcon-cli").chain(args.iter().copied()),
+        );
+        let commands = ["/list", "/fly", "/seed"].map(String::from).to_vec();
+        let (summary, failed) = run_batch(
+            &cli,
+            &mut session,
+            &DisplayOptions::from_cli(&cli),
+            &mut None,
+            &Aliases::default(),
+            &HashMap::new(),
+            commands,
+        );
+        let sent = sent.borrow().clone();
+        (sent, summary, failed)
+    }
+
+    #[test]
+    fn batches_run_every_command_by_default() {
+        let (sent, summary, failed) = batch(&["--quiet"]);
+        assert_eq!(sent, ["/list", "/fly", "/seed"]);
+        assert_eq!((summary.ok, summary.errors), (2, 1));
+        assert_eq!(failed, None);
+    }
+
+    #[test]
+    fn fail_fast_stops_at_the_first_error() {
+        let (sent, summary, failed) = batch(&["--quiet", "--fail-fast"]);
+        assert_eq!(sent, ["/list", "/fly"]);
+        assert_eq!((summary.ok, summary.errors), (1, 1));
+        assert_eq!(failed, Some("Stopped after /fly failed".to_string()));
+    }
+}
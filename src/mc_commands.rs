@@ -0,0 +1,1529 @@
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+use crate::edition::Edition;
+use crate::Argument;
+
+pub const TARGET_SELECTORS: &[&str] = &["@p", "@a", "@r", "@s", "@e"];
+
+// Offered as a whole on the first coordinate word, then the remaining part
+pub const COORDINATE_TEMPLATES: &[&str] = &["~ ~ ~", "^ ^ ^"];
+
+/// Spreads for `/particle`, the size of the box particles appear in
+pub const DELTA_TEMPLATES: &[&str] = &["0 0 0", "0.5 0.5 0.5", "1 1 1"];
+
+pub const BOOLEANS: &[&str] = &["true", "false"];
+
+/// Stack sizes for `/give` and `/clear`. `/clear` with 0 only counts the items.
+pub const ITEM_COUNTS: &[&str] = &["1", "16", "32", "64"];
+pub const MAX_COUNTS: &[&str] = &["0", "1", "16", "32", "64"];
+
+pub const GAME_MODES: &[&str] = &["survival", "creative", "adventure", "spectator"];
+
+// Names first, then the legacy numeric IDs in the same order
+const GAME_MODE_CANDIDATES: &[&str] = &[
+    "survival",
+    "creative",
+    "adventure",
+    "spectator",
+    "0",
+    "1",
+    "2",
+    "3",
+];
+
+/// Game mode name for a named or legacy numeric (`0` to `3`) game mode
+pub fn game_mode_name(value: &str) -> Option<&'static str> {
+    match value.parse::<usize>() {
+        Ok(id) => GAME_MODES.get(id).copied(),
+        Err(_) => GAME_MODES.iter().find(|name| **name == value).copied(),
+    }
+}
+
+/// Explanation shown next to a candidate, like the name of a numeric game mode
+pub fn candidate_label(arg_name: &str, candidate: &str) -> Option<&'static str> {
+    match arg_name {
+        "gamemode" if candidate.bytes().all(|b| b.is_ascii_digit()) => game_mode_name(candidate),
+        _ => None,
+    }
+}
+
+// What the number means for arguments that only take one, by the command words before
+// it without the prefix. `*` stands for any word.
+const NUMERIC_HINTS: &[(&str, &str)] = &[
+    ("setidletimeout", "<minutes (0 disables)>"),
+    ("time add", "<time (ticks, or with a unit like 1d, 30s)>"),
+    (
+        "schedule function *",
+        "<time (ticks, or with a unit like 1d, 30s)>",
+    ),
+    (
+        "weather clear",
+        "<duration (ticks, or with a unit like 1d, 30s)>",
+    ),
+    (
+        "weather rain",
+        "<duration (ticks, or with a unit like 1d, 30s)>",
+    ),
+    (
+        "weather thunder",
+        "<duration (ticks, or with a unit like 1d, 30s)>",
+    ),
+    (
+        "xp add *",
+        "<amount (points, or levels with levels after it)>",
+    ),
+    (
+        "xp set *",
+        "<amount (points, or levels with levels after it)>",
+    ),
+    (
+        "experience add *",
+        "<amount (points, or levels with levels after it)>",
+    ),
+    (
+        "experience set *",
+        "<amount (points, or levels with levels after it)>",
+    ),
+    ("worldborder set", "<distance (width in blocks)>"),
+    (
+        "worldborder add",
+        "<distance (blocks to widen by, negative shrinks)>",
+    ),
+    ("tick rate", "<rate (ticks per second, 20 is normal)>"),
+    ("title * times", "<fadeIn (ticks, or with a unit like 1s)>"),
+    ("title * times *", "<stay (ticks, or with a unit like 1s)>"),
+    (
+        "title * times * *",
+        "<fadeOut (ticks, or with a unit like 1s)>",
+    ),
+];
+
+/// Hint for the numeric argument following the completed words of a command, given
+/// without the command prefix
+pub fn numeric_hint(words: &[&str]) -> Option<&'static str> {
+    NUMERIC_HINTS.iter().find_map(|(path, hint)| {
+        let path: Vec<&str> = path.split(' ').collect();
+        let matches = path.len() == words.len()
+            && path
+                .iter()
+                .zip(words)
+                .all(|(expected, word)| *expected == "*" || expected == word);
+        matches.then_some(*hint)
+    })
+}
+
+pub const SCOREBOARD_CRITERIA: &[&str] = &[
+    "dummy",
+    "trigger",
+    "deathCount",
+    "playerKillCount",
+    "totalKillCount",
+    "health",
+    "xp",
+    "level",
+    "food",
+    "air",
+    "armor",
+];
+
+pub const SCOREBOARD_SLOTS: &[&str] = &["list", "sidebar", "below_name"];
+
+pub const SCOREBOARD_OPERATIONS: &[&str] = &["+=", "-=", "*=", "/=", "%=", "=", "<", ">", "><"];
+
+pub const AXES: &[&str] = &["xyz", "xz", "y", "x", "z"];
+
+pub const DIMENSIONS: &[&str] = &[
+    "minecraft:overworld",
+    "minecraft:the_nether",
+    "minecraft:the_end",
+];
+
+pub const HEIGHTMAPS: &[&str] = &[
+    "world_surface",
+    "motion_blocking",
+    "motion_blocking_no_leaves",
+    "ocean_floor",
+];
+
+// Yaw and pitch, relative to the executor
+pub const ROTATION_TEMPLATES: &[&str] = &["~ ~"];
+
+// A block column, x and z, as taken by /forceload
+pub const COLUMN_TEMPLATES: &[&str] = &["~ ~"];
+
+// `/forceload remove` also takes `all` in place of the first column
+const COLUMN_OR_ALL: &[&str] = &["all", "~ ~"];
+
+// Yaw facing south, west, north and east
+pub const ANGLES: &[&str] = &["0", "90", "180", "-90"];
+
+// An empty compound first, then single common keys to edit in place
+pub const NBT_TEMPLATES: &[&str] = &[
+    "{}",
+    "{NoAI:1b}",
+    "{Silent:1b}",
+    "{Invulnerable:1b}",
+    "{PersistenceRequired:1b}",
+    "{Glowing:1b}",
+    "{CustomName:'\"Name\"'}",
+];
+
+/// A text component to fill in, for arguments taking JSON text
+pub const TEXT_COMPONENT_TEMPLATES: &[&str] = &["{\"text\":\"\"}"];
+
+/// Candidate values for an argument, derived from its name in the help text
+pub fn argument_candidates(name: &str) -> &'static [&'static str] {
+    match name {
+        "target" | "targets" | "player" | "source" | "destination" => TARGET_SELECTORS,
+        "pos" | "location" => COORDINATE_TEMPLATES,
+        "delta" => DELTA_TEMPLATES,
+        "viewers" => TARGET_SELECTORS,
+        "angle" => ANGLES,
+        "gamemode" => GAME_MODE_CANDIDATES,
+        // Gamerule values and plugin toggles are mostly booleans
+        "value" | "bool" | "boolean" | "enabled" => BOOLEANS,
+        "nbt" => NBT_TEMPLATES,
+        "count" => ITEM_COUNTS,
+        "maxCount" => MAX_COUNTS,
+        "title" => TEXT_COMPONENT_TEMPLATES,
+        "criteria" => SCOREBOARD_CRITERIA,
+        "slot" => SCOREBOARD_SLOTS,
+        "operation" => SCOREBOARD_OPERATIONS,
+        "axes" => AXES,
+        "dimension" => DIMENSIONS,
+        "heightmap" => HEIGHTMAPS,
+        "rotation" => ROTATION_TEMPLATES,
+        "column" => COLUMN_TEMPLATES,
+        "column_or_all" => COLUMN_OR_ALL,
+        _ => &[],
+    }
+}
+
+/// The only values an argument takes by its name, checked by `--validate-args`. Empty
+/// when it takes others too.
+pub fn fixed_values(name: &str) -> &'static [&'static str] {
+    match name {
+        "gamemode" => GAME_MODE_CANDIDATES,
+        _ => &[],
+    }
+}
+
+/// Bundled ID list for an argument whose values are namespaced IDs
+pub fn argument_ids(name: &str) -> &'static [&'static str] {
+    match name {
+        "item" => ITEM_IDS,
+        "entity" => ENTITY_IDS,
+        "effect" => EFFECT_IDS,
+        "enchantment" => ENCHANTMENT_IDS,
+        "advancement" => ADVANCEMENT_IDS,
+        "particle" => PARTICLE_IDS,
+        "attribute" => ATTRIBUTE_IDS,
+        _ => &[],
+    }
+}
+
+/// How bundled IDs are offered for completion
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NamespaceStyle {
+    /// With the namespace, `minecraft:diamond`
+    #[default]
+    Explicit,
+    /// Without it, `diamond`
+    Bare,
+    /// Both forms
+    Both,
+}
+
+impl NamespaceStyle {
+    /// The candidates for a bundled ID, given without namespace
+    pub fn id_candidates(self, id: &str) -> Vec<String> {
+        match self {
+            NamespaceStyle::Explicit => vec![format!("minecraft:{id}")],
+            NamespaceStyle::Bare => vec![id.to_string()],
+            NamespaceStyle::Both => vec![format!("minecraft:{id}"), id.to_string()],
+        }
+    }
+}
+
+/// Number of space separated words an argument spans
+pub fn argument_width(name: &str) -> usize {
+    match name {
+        "pos" | "location" | "delta" => 3,
+        "rotation" | "column" | "column_or_all" => 2,
+        _ => 1,
+    }
+}
+
+/// Argument shapes for commands whose /help usage is truncated or misparsed
+fn curated_commands() -> Vec<(&'static str, Vec<Argument>)> {
+    vec![
+        (
+            "/spawnpoint",
+            vec![optional("targets"), optional("pos"), optional("angle")],
+        ),
+        ("/ban-ip", vec![required("name_or_ip"), optional("reason")]),
+        // Completed from the addresses seen in `/banlist ips`
+        ("/pardon-ip", vec![required("ip")]),
+        (
+            "/summon",
+            vec![required("entity"), optional("pos"), optional("nbt")],
+        ),
+        ("/setworldspawn", vec![optional("pos"), optional("angle")]),
+        // /help only shows the particle and position
+        (
+            "/particle",
+            vec![
+                required("particle"),
+                optional("pos"),
+                optional("delta"),
+                optional("speed"),
+                optional("count"),
+                optional_choice(&["force", "normal"]),
+                optional("viewers"),
+            ],
+        ),
+        // Java lists the rules as choices without their values
+        ("/gamerule", vec![required("rule"), optional("value")]),
+    ]
+}
+
+pub(crate) fn required(name: &str) -> Argument {
+    Argument::Required(name.to_string())
+}
+
+pub(crate) fn optional(name: &str) -> Argument {
+    Argument::Optional(name.to_string())
+}
+
+pub(crate) fn choice(choices: &[&str]) -> Argument {
+    Argument::RequiredChoice(choices.iter().map(|s| s.to_string()).collect())
+}
+
+pub(crate) fn optional_choice(choices: &[&str]) -> Argument {
+    Argument::OptionalChoice(choices.iter().map(|s| s.to_string()).collect())
+}
+
+/// Subcommand trees, keyed by the command and the subcommand words leading to each level
+fn curated_subcommands() -> Vec<(&'static str, Vec<Argument>)> {
+    let target_objective_score = || {
+        vec![
+            required("targets"),
+            required("objective"),
+            required("score"),
+        ]
+    };
+    vec![
+        ("/scoreboard", vec![choice(&["objectives", "players"])]),
+        (
+            "/scoreboard objectives",
+            vec![choice(&["add", "remove", "list", "setdisplay", "modify"])],
+        ),
+        (
+            "/scoreboard objectives add",
+            vec![
+                required("objective"),
+                required("criteria"),
+                optional("displayName"),
+            ],
+        ),
+        ("/scoreboard objectives remove", vec![required("objective")]),
+        ("/scoreboard objectives list", vec![]),
+        (
+            "/scoreboard objectives setdisplay",
+            vec![required("slot"), optional("objective")],
+        ),
+        (
+            "/scoreboard objectives modify",
+            vec![
+                required("objective"),
+                choice(&[
+                    "displayname",
+                    "rendertype",
+                    "displayautoupdate",
+                    "numberformat",
+                ]),
+            ],
+        ),
+        (
+            "/scoreboard players",
+            vec![choice(&[
+                "add",
+                "remove",
+                "set",
+                "reset",
+                "get",
+                "list",
+                "enable",
+                "operation",
+            ])],
+        ),
+        ("/scoreboard players add", target_objective_score()),
+        ("/scoreboard players remove", target_objective_score()),
+        ("/scoreboard players set", target_objective_score()),
+        (
+            "/scoreboard players reset",
+            vec![required("targets"), optional("objective")],
+        ),
+        (
+            "/scoreboard players get",
+            vec![required("target"), required("objective")],
+        ),
+        ("/scoreboard players list", vec![optional("target")]),
+        (
+            "/scoreboard players enable",
+            vec![required("targets"), required("objective")],
+        ),
+        (
+            "/scoreboard players operation",
+            vec![
+                required("targets"),
+                required("targetObjective"),
+                required("operation"),
+                required("source"),
+                required("sourceObjective"),
+            ],
+        ),
+        ("/effect", vec![choice(&["give", "clear"])]),
+        (
+            "/effect give",
+            vec![
+                required("targets"),
+                required("effect"),
+                optional("seconds"),
+                optional("amplifier"),
+                optional_choice(&["true", "false"]),
+            ],
+        ),
+        (
+            "/effect clear",
+            vec![optional("targets"), optional("effect")],
+        ),
+        ("/forceload", vec![choice(&["add", "remove", "query"])]),
+        (
+            "/forceload add",
+            vec![required("column"), optional("column")],
+        ),
+        (
+            "/forceload remove",
+            vec![required("column_or_all"), optional("column")],
+        ),
+        ("/forceload remove all", vec![]),
+        ("/forceload query", vec![optional("column")]),
+        ("/datapack", vec![choice(&["list", "enable", "disable"])]),
+        (
+            "/datapack list",
+            vec![optional_choice(&["available", "enabled"])],
+        ),
+        // The pack to place it next to with `before` or `after`
+        (
+            "/datapack enable",
+            vec![
+                required("available_pack"),
+                optional_choice(&["first", "last", "before", "after"]),
+                optional("enabled_pack"),
+            ],
+        ),
+        ("/datapack disable", vec![required("enabled_pack")]),
+    ]
+}
+
+/// Alternative argument lists for commands with several forms, by name without the prefix
+pub fn overloads(name: &str) -> Option<Vec<Vec<Argument>>> {
+    match name {
+        "tp" | "teleport" => Some(vec![
+            vec![required("destination")],
+            vec![required("location")],
+            vec![required("targets"), required("destination")],
+            vec![required("targets"), required("location")],
+            vec![
+                required("targets"),
+                required("location"),
+                required("rotation"),
+            ],
+            vec![
+                required("targets"),
+                required("location"),
+                choice(&["facing"]),
+                required("pos"),
+            ],
+            vec![
+                required("targets"),
+                required("location"),
+                choice(&["facing"]),
+                choice(&["entity"]),
+                required("targets"),
+                optional_choice(&["eyes", "feet"]),
+            ],
+        ]),
+        "title" => Some(vec![
+            vec![required("targets"), choice(&["clear", "reset"])],
+            vec![
+                required("targets"),
+                choice(&["title", "subtitle", "actionbar"]),
+                required("title"),
+            ],
+            vec![
+                required("targets"),
+                choice(&["times"]),
+                required("fadeIn"),
+                required("stay"),
+                required("fadeOut"),
+            ],
+        ]),
+        _ => None,
+    }
+}
+
+/// Whether a word is a world coordinate, absolute (`12.5`), relative (`~2`) or local (`^`)
+pub fn is_coordinate(word: &str) -> bool {
+    let number = word.trim_start_matches(['~', '^']);
+    number.len() < word.len() || number.parse::<f64>().is_ok()
+}
+
+/// Arguments that take coordinates rather than an entity
+pub fn is_coordinate_argument(name: &str) -> bool {
+    matches!(name, "pos" | "location" | "rotation" | "column")
+}
+
+/// Arguments that take an entity selector or a player name
+pub fn is_entity_argument(name: &str) -> bool {
+    matches!(
+        name,
+        "target" | "targets" | "player" | "source" | "destination"
+    )
+}
+
+/// Replace parsed arguments with curated ones for commands the server knows
+pub fn apply_curated(commands: &mut HashMap<String, Vec<Argument>>, command_char: &str) {
+    for (name, args) in curated_commands() {
+        let name = name.replacen('/', command_char, 1);
+        if let Some(entry) = commands.get_mut(&name) {
+            *entry = args;
+        }
+    }
+    for (path, args) in curated_subcommands() {
+        let path = path.replacen('/', command_char, 1);
+        let root = path.split(' ').next().unwrap_or_default();
+        if commands.contains_key(root) {
+            commands.insert(path, args);
+        }
+    }
+}
+
+/// Common vanilla item IDs, without the `minecraft:` namespace
+pub const ITEM_IDS: &[&str] = &[
+    "acacia_log",
+    "acacia_planks",
+    "amethyst_shard",
+    "ancient_debris",
+    "andesite",
+    "anvil",
+    "apple",
+    "armor_stand",
+    "arrow",
+    "baked_potato",
+    "bamboo",
+    "barrel",
+    "barrier",
+    "beacon",
+    "bed",
+    "beef",
+    "beetroot",
+    "bell",
+    "birch_log",
+    "birch_planks",
+    "black_wool",
+    "blaze_powder",
+    "blaze_rod",
+    "blue_ice",
+    "blue_wool",
+    "bone",
+    "bone_meal",
+    "book",
+    "bookshelf",
+    "bow",
+    "bowl",
+    "bread",
+    "brewing_stand",
+    "brick",
+    "bricks",
+    "bucket",
+    "cactus",
+    "cake",
+    "campfire",
+    "carrot",
+    "cauldron",
+    "chain",
+    "chainmail_boots",
+    "chainmail_chestplate",
+    "chainmail_helmet",
+    "chainmail_leggings",
+    "charcoal",
+    "cherry_log",
+    "cherry_planks",
+    "chest",
+    "chicken",
+    "clay",
+    "clay_ball",
+    "clock",
+    "coal",
+    "coal_block",
+    "coal_ore",
+    "cobblestone",
+    "cobweb",
+    "cod",
+    "command_block",
+    "compass",
+    "composter",
+    "cooked_beef",
+    "cooked_chicken",
+    "cooked_cod",
+    "cooked_mutton",
+    "cooked_porkchop",
+    "cooked_salmon",
+    "cookie",
+    "copper_block",
+    "copper_ingot",
+    "copper_ore",
+    "crafting_table",
+    "crossbow",
+    "dark_oak_log",
+    "dark_oak_planks",
+    "deepslate",
+    "diamond",
+    "diamond_axe",
+    "diamond_block",
+    "diamond_boots",
+    "diamond_chestplate",
+    "diamond_helmet",
+    "diamond_hoe",
+    "diamond_leggings",
+    "diamond_ore",
+    "diamond_pickaxe",
+    "diamond_shovel",
+    "diamond_sword",
+    "diorite",
+    "dirt",
+    "dispenser",
+    "dragon_egg",
+    "dropper",
+    "egg",
+    "elytra",
+    "emerald",
+    "emerald_block",
+    "emerald_ore",
+    "enchanted_book",
+    "enchanted_golden_apple",
+    "enchanting_table",
+    "end_crystal",
+    "end_rod",
+    "end_stone",
+    "ender_chest",
+    "ender_eye",
+    "ender_pearl",
+    "experience_bottle",
+    "feather",
+    "fire_charge",
+    "firework_rocket",
+    "fishing_rod",
+    "flint",
+    "flint_and_steel",
+    "furnace",
+    "ghast_tear",
+    "glass",
+    "glass_bottle",
+    "glass_pane",
+    "glowstone",
+    "glowstone_dust",
+    "gold_block",
+    "gold_ingot",
+    "gold_nugget",
+    "gold_ore",
+    "golden_apple",
+    "golden_axe",
+    "golden_boots",
+    "golden_carrot",
+    "golden_chestplate",
+    "golden_helmet",
+    "golden_hoe",
+    "golden_leggings",
+    "golden_pickaxe",
+    "golden_shovel",
+    "golden_sword",
+    "granite",
+    "grass_block",
+    "gravel",
+    "gunpowder",
+    "hay_block",
+    "honey_bottle",
+    "honeycomb",
+    "hopper",
+    "ice",
+    "iron_axe",
+    "iron_bars",
+    "iron_block",
+    "iron_boots",
+    "iron_chestplate",
+    "iron_door",
+    "iron_helmet",
+    "iron_hoe",
+    "iron_ingot",
+    "iron_leggings",
+    "iron_nugget",
+    "iron_ore",
+    "iron_pickaxe",
+    "iron_shovel",
+    "iron_sword",
+    "item_frame",
+    "jukebox",
+    "jungle_log",
+    "jungle_planks",
+    "ladder",
+    "lantern",
+    "lapis_block",
+    "lapis_lazuli",
+    "lapis_ore",
+    "lava_bucket",
+    "lead",
+    "leather",
+    "leather_boots",
+    "leather_chestplate",
+    "leather_helmet",
+    "leather_leggings",
+    "lectern",
+    "lever",
+    "lodestone",
+    "magma_block",
+    "mangrove_log",
+    "mangrove_planks",
+    "map",
+    "melon",
+    "melon_slice",
+    "milk_bucket",
+    "minecart",
+    "mossy_cobblestone",
+    "mutton",
+    "name_tag",
+    "nether_brick",
+    "nether_star",
+    "nether_wart",
+    "netherite_axe",
+    "netherite_block",
+    "netherite_boots",
+    "netherite_chestplate",
+    "netherite_helmet",
+    "netherite_hoe",
+    "netherite_ingot",
+    "netherite_leggings",
+    "netherite_pickaxe",
+    "netherite_scrap",
+    "netherite_shovel",
+    "netherite_sword",
+    "netherrack",
+    "note_block",
+    "oak_boat",
+    "oak_door",
+    "oak_fence",
+    "oak_log",
+    "oak_planks",
+    "oak_sapling",
+    "oak_slab",
+    "oak_stairs",
+    "observer",
+    "obsidian",
+    "painting",
+    "paper",
+    "piston",
+    "poisonous_potato",
+    "porkchop",
+    "potato",
+    "potion",
+    "pumpkin",
+    "pumpkin_pie",
+    "quartz",
+    "quartz_block",
+    "rabbit",
+    "rail",
+    "raw_copper",
+    "raw_gold",
+    "raw_iron",
+    "red_wool",
+    "redstone",
+    "redstone_block",
+    "redstone_lamp",
+    "redstone_ore",
+    "redstone_torch",
+    "repeater",
+    "respawn_anchor",
+    "rotten_flesh",
+    "saddle",
+    "salmon",
+    "sand",
+    "sandstone",
+    "scaffolding",
+    "sea_lantern",
+    "shears",
+    "shield",
+    "shulker_box",
+    "shulker_shell",
+    "slime_ball",
+    "slime_block",
+    "smithing_table",
+    "smoker",
+    "snowball",
+    "soul_sand",
+    "spider_eye",
+    "splash_potion",
+    "spruce_log",
+    "spruce_planks",
+    "spyglass",
+    "stick",
+    "sticky_piston",
+    "stone",
+    "stone_axe",
+    "stone_bricks",
+    "stone_hoe",
+    "stone_pickaxe",
+    "stone_shovel",
+    "stone_sword",
+    "string",
+    "sugar",
+    "sugar_cane",
+    "sweet_berries",
+    "tnt",
+    "torch",
+    "totem_of_undying",
+    "trident",
+    "tripwire_hook",
+    "turtle_helmet",
+    "water_bucket",
+    "wheat",
+    "wheat_seeds",
+    "white_wool",
+    "wooden_axe",
+    "wooden_hoe",
+    "wooden_pickaxe",
+    "wooden_shovel",
+    "wooden_sword",
+    "writable_book",
+    "written_book",
+    "yellow_wool",
+];
+
+/// Summonable vanilla entity IDs, without the `minecraft:` namespace
+pub const ENTITY_IDS: &[&str] = &[
+    "allay",
+    "area_effect_cloud",
+    "armadillo",
+    "armor_stand",
+    "arrow",
+    "axolotl",
+    "bat",
+    "bee",
+    "blaze",
+    "block_display",
+    "boat",
+    "bogged",
+    "breeze",
+    "camel",
+    "cat",
+    "cave_spider",
+    "chest_minecart",
+    "chicken",
+    "cod",
+    "cow",
+    "creeper",
+    "dolphin",
+    "donkey",
+    "dragon_fireball",
+    "drowned",
+    "egg",
+    "elder_guardian",
+    "end_crystal",
+    "ender_dragon",
+    "ender_pearl",
+    "enderman",
+    "endermite",
+    "evoker",
+    "experience_orb",
+    "eye_of_ender",
+    "falling_block",
+    "firework_rocket",
+    "fox",
+    "frog",
+    "ghast",
+    "giant",
+    "glow_item_frame",
+    "glow_squid",
+    "goat",
+    "guardian",
+    "hoglin",
+    "hopper_minecart",
+    "horse",
+    "husk",
+    "illusioner",
+    "interaction",
+    "iron_golem",
+    "item",
+    "item_display",
+    "item_frame",
+    "lightning_bolt",
+    "llama",
+    "magma_cube",
+    "marker",
+    "minecart",
+    "mooshroom",
+    "mule",
+    "ocelot",
+    "painting",
+    "panda",
+    "parrot",
+    "phantom",
+    "pig",
+    "piglin",
+    "piglin_brute",
+    "pillager",
+    "polar_bear",
+    "pufferfish",
+    "rabbit",
+    "ravager",
+    "salmon",
+    "sheep",
+    "shulker",
+    "silverfish",
+    "skeleton",
+    "skeleton_horse",
+    "slime",
+    "sniffer",
+    "snow_golem",
+    "snowball",
+    "spider",
+    "squid",
+    "stray",
+    "strider",
+    "tadpole",
+    "text_display",
+    "tnt",
+    "tnt_minecart",
+    "trader_llama",
+    "trident",
+    "tropical_fish",
+    "turtle",
+    "vex",
+    "villager",
+    "vindicator",
+    "wandering_trader",
+    "warden",
+    "witch",
+    "wither",
+    "wither_skeleton",
+    "wolf",
+    "zoglin",
+    "zombie",
+    "zombie_horse",
+    "zombie_villager",
+    "zombified_piglin",
+];
+
+/// Status effect IDs, without the `minecraft:` namespace
+pub const EFFECT_IDS: &[&str] = &[
+    "absorption",
+    "bad_omen",
+    "blindness",
+    "conduit_power",
+    "darkness",
+    "dolphins_grace",
+    "fire_resistance",
+    "glowing",
+    "haste",
+    "health_boost",
+    "hero_of_the_village",
+    "hunger",
+    "infested",
+    "instant_damage",
+    "instant_health",
+    "invisibility",
+    "jump_boost",
+    "levitation",
+    "luck",
+    "mining_fatigue",
+    "nausea",
+    "night_vision",
+    "oozing",
+    "poison",
+    "raid_omen",
+    "regeneration",
+    "resistance",
+    "saturation",
+    "slow_falling",
+    "slowness",
+    "speed",
+    "strength",
+    "trial_omen",
+    "unluck",
+    "water_breathing",
+    "weakness",
+    "weaving",
+    "wind_charged",
+    "wither",
+];
+
+/// Vanilla enchantment IDs, without the `minecraft:` namespace
+pub const ENCHANTMENT_IDS: &[&str] = &[
+    "aqua_affinity",
+    "bane_of_arthropods",
+    "binding_curse",
+    "blast_protection",
+    "breach",
+    "channeling",
+    "density",
+    "depth_strider",
+    "efficiency",
+    "feather_falling",
+    "fire_aspect",
+    "fire_protection",
+    "flame",
+    "fortune",
+    "frost_walker",
+    "impaling",
+    "infinity",
+    "knockback",
+    "looting",
+    "loyalty",
+    "luck_of_the_sea",
+    "lure",
+    "mending",
+    "multishot",
+    "piercing",
+    "power",
+    "projectile_protection",
+    "protection",
+    "punch",
+    "quick_charge",
+    "respiration",
+    "riptide",
+    "sharpness",
+    "silk_touch",
+    "smite",
+    "soul_speed",
+    "sweeping_edge",
+    "swift_sneak",
+    "thorns",
+    "unbreaking",
+    "vanishing_curse",
+    "wind_burst",
+];
+
+/// Vanilla particle IDs, without the `minecraft:` namespace
+pub const PARTICLE_IDS: &[&str] = &[
+    "angry_villager",
+    "ash",
+    "bubble",
+    "bubble_column_up",
+    "bubble_pop",
+    "campfire_cosy_smoke",
+    "campfire_signal_smoke",
+    "cherry_leaves",
+    "cloud",
+    "composter",
+    "crimson_spore",
+    "crit",
+    "current_down",
+    "damage_indicator",
+    "dolphin",
+    "dragon_breath",
+    "dripping_lava",
+    "dripping_water",
+    "dust",
+    "dust_color_transition",
+    "effect",
+    "elder_guardian",
+    "electric_spark",
+    "enchant",
+    "enchanted_hit",
+    "end_rod",
+    "entity_effect",
+    "explosion",
+    "explosion_emitter",
+    "falling_dust",
+    "falling_lava",
+    "falling_water",
+    "firework",
+    "fishing",
+    "flame",
+    "flash",
+    "glow",
+    "glow_squid_ink",
+    "happy_villager",
+    "heart",
+    "instant_effect",
+    "item",
+    "item_slime",
+    "item_snowball",
+    "landing_lava",
+    "large_smoke",
+    "lava",
+    "mycelium",
+    "nautilus",
+    "note",
+    "poof",
+    "portal",
+    "rain",
+    "reverse_portal",
+    "scrape",
+    "sculk_charge",
+    "sculk_charge_pop",
+    "sculk_soul",
+    "smoke",
+    "sneeze",
+    "snowflake",
+    "sonic_boom",
+    "soul",
+    "soul_fire_flame",
+    "spit",
+    "splash",
+    "spore_blossom_air",
+    "squid_ink",
+    "sweep_attack",
+    "totem_of_undying",
+    "trial_spawner_detection",
+    "underwater",
+    "wax_off",
+    "wax_on",
+    "white_ash",
+    "witch",
+];
+
+/// Vanilla advancement IDs, without the `minecraft:` namespace
+pub const ADVANCEMENT_IDS: &[&str] = &[
+    "story/root",
+    "story/mine_stone",
+    "story/upgrade_tools",
+    "story/smelt_iron",
+    "story/obtain_armor",
+    "story/lava_bucket",
+    "story/iron_tools",
+    "story/deflect_arrow",
+    "story/form_obsidian",
+    "story/mine_diamond",
+    "story/enter_the_nether",
+    "story/shiny_gear",
+    "story/enchant_item",
+    "story/cure_zombie_villager",
+    "story/follow_ender_eye",
+    "story/enter_the_end",
+    "nether/root",
+    "nether/return_to_sender",
+    "nether/find_bastion",
+    "nether/obtain_ancient_debris",
+    "nether/fast_travel",
+    "nether/find_fortress",
+    "nether/obtain_crying_obsidian",
+    "nether/distract_piglin",
+    "nether/ride_strider",
+    "nether/uneasy_alliance",
+    "nether/loot_bastion",
+    "nether/use_lodestone",
+    "nether/netherite_armor",
+    "nether/get_wither_skull",
+    "nether/obtain_blaze_rod",
+    "nether/charge_respawn_anchor",
+    "nether/explore_nether",
+    "nether/summon_wither",
+    "nether/brew_potion",
+    "nether/create_beacon",
+    "nether/all_potions",
+    "nether/create_full_beacon",
+    "nether/all_effects",
+    "end/root",
+    "end/kill_dragon",
+    "end/dragon_egg",
+    "end/enter_end_gateway",
+    "end/respawn_dragon",
+    "end/dragon_breath",
+    "end/find_end_city",
+    "end/elytra",
+    "end/levitate",
+    "adventure/root",
+    "adventure/voluntary_exile",
+    "adventure/spyglass_at_parrot",
+    "adventure/kill_a_mob",
+    "adventure/trade",
+    "adventure/honey_block_slide",
+    "adventure/ol_betsy",
+    "adventure/lightning_rod_with_villager_no_fire",
+    "adventure/fall_from_world_height",
+    "adventure/avoid_vibration",
+    "adventure/sleep_in_bed",
+    "adventure/hero_of_the_village",
+    "adventure/spyglass_at_ghast",
+    "adventure/throw_trident",
+    "adventure/kill_all_mobs",
+    "adventure/totem_of_undying",
+    "adventure/summon_iron_golem",
+    "adventure/two_birds_one_arrow",
+    "adventure/whos_the_pillager_now",
+    "adventure/arbalistic",
+    "adventure/adventuring_time",
+    "adventure/play_jukebox_in_meadows",
+    "adventure/walk_on_powder_snow_with_leather_boots",
+    "adventure/spyglass_at_dragon",
+    "adventure/very_very_frightening",
+    "adventure/sniper_duel",
+    "adventure/bullseye",
+    "husbandry/root",
+    "husbandry/safely_harvest_honey",
+    "husbandry/breed_an_animal",
+    "husbandry/allay_deliver_item_to_player",
+    "husbandry/ride_a_boat_with_a_goat",
+    "husbandry/tame_an_animal",
+    "husbandry/make_a_sign_glow",
+    "husbandry/fishy_business",
+    "husbandry/silk_touch_nest",
+    "husbandry/tadpole_in_a_bucket",
+    "husbandry/plant_seed",
+    "husbandry/wax_on",
+    "husbandry/bred_all_animals",
+    "husbandry/allay_deliver_cake_to_note_block",
+    "husbandry/complete_catalogue",
+    "husbandry/tactical_fishing",
+    "husbandry/leash_all_frog_variants",
+    "husbandry/balanced_diet",
+    "husbandry/obtain_netherite_hoe",
+    "husbandry/wax_off",
+    "husbandry/axolotl_in_a_bucket",
+    "husbandry/froglights",
+    "husbandry/kill_axolotl_target",
+];
+
+/// Vanilla attribute IDs, without the `minecraft:` namespace
+pub const ATTRIBUTE_IDS: &[&str] = &[
+    "generic.armor",
+    "generic.armor_toughness",
+    "generic.attack_damage",
+    "generic.attack_knockback",
+    "generic.attack_speed",
+    "generic.burning_time",
+    "generic.explosion_knockback_resistance",
+    "generic.fall_damage_multiplier",
+    "generic.flying_speed",
+    "generic.follow_range",
+    "generic.gravity",
+    "generic.jump_strength",
+    "generic.knockback_resistance",
+    "generic.luck",
+    "generic.max_absorption",
+    "generic.max_health",
+    "generic.movement_efficiency",
+    "generic.movement_speed",
+    "generic.oxygen_bonus",
+    "generic.safe_fall_distance",
+    "generic.scale",
+    "generic.step_height",
+    "generic.water_movement_efficiency",
+    "player.block_break_speed",
+    "player.block_interaction_range",
+    "player.entity_interaction_range",
+    "player.mining_efficiency",
+    "player.sneaking_speed",
+    "player.submerged_mining_speed",
+    "player.sweeping_damage_ratio",
+    "zombie.spawn_reinforcements",
+];
+
+/// Highest level of a vanilla enchantment, with or without the namespace
+pub fn enchantment_max_level(id: &str) -> Option<u32> {
+    let id = id.strip_prefix("minecraft:").unwrap_or(id);
+    if !ENCHANTMENT_IDS.contains(&id) {
+        return None;
+    }
+    Some(match id {
+        "sharpness" | "smite" | "bane_of_arthropods" | "efficiency" | "power" | "impaling"
+        | "density" => 5,
+        "protection"
+        | "fire_protection"
+        | "blast_protection"
+        | "projectile_protection"
+        | "feather_falling"
+        | "piercing"
+        | "breach" => 4,
+        "unbreaking" | "fortune" | "looting" | "luck_of_the_sea" | "lure" | "depth_strider"
+        | "respiration" | "thorns" | "soul_speed" | "swift_sneak" | "loyalty" | "riptide"
+        | "quick_charge" | "sweeping_edge" | "wind_burst" => 3,
+        "frost_walker" | "knockback" | "fire_aspect" | "punch" => 2,
+        _ => 1,
+    })
+}
+
+/// Candidates that depend on the word typed for the previous argument, like the levels
+/// of the enchantment before them
+pub fn dependent_candidates(previous_arg: &str, previous_word: &str, arg: &str) -> Vec<String> {
+    match (previous_arg, arg) {
+        ("enchantment", "level") => enchantment_max_level(previous_word)
+            .map(|max| (1..=max).map(|level| level.to_string()).collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// What a gamerule is set to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameruleValue {
+    Bool,
+    Int,
+}
+
+impl GameruleValue {
+    pub fn candidates(self) -> &'static [&'static str] {
+        match self {
+            GameruleValue::Bool => BOOLEANS,
+            GameruleValue::Int => &[],
+        }
+    }
+}
+
+const JAVA_GAMERULES: &[(&str, GameruleValue)] = &[
+    ("announceAdvancements", GameruleValue::Bool),
+    ("blockExplosionDropDecay", GameruleValue::Bool),
+    ("commandBlockOutput", GameruleValue::Bool),
+    ("commandModificationBlockLimit", GameruleValue::Int),
+    ("disableElytraMovementCheck", GameruleValue::Bool),
+    ("disablePlayerMovementCheck", GameruleValue::Bool),
+    ("disableRaids", GameruleValue::Bool),
+    ("doDaylightCycle", GameruleValue::Bool),
+    ("doEntityDrops", GameruleValue::Bool),
+    ("doFireTick", GameruleValue::Bool),
+    ("doImmediateRespawn", GameruleValue::Bool),
+    ("doInsomnia", GameruleValue::Bool),
+    ("doLimitedCrafting", GameruleValue::Bool),
+    ("doMobLoot", GameruleValue::Bool),
+    ("doMobSpawning", GameruleValue::Bool),
+    ("doPatrolSpawning", GameruleValue::Bool),
+    ("doTileDrops", GameruleValue::Bool),
+    ("doTraderSpawning", GameruleValue::Bool),
+    ("doVinesSpread", GameruleValue::Bool),
+    ("doWardenSpawning", GameruleValue::Bool),
+    ("doWeatherCycle", GameruleValue::Bool),
+    ("drowningDamage", GameruleValue::Bool),
+    ("enderPearlsVanishOnDeath", GameruleValue::Bool),
+    ("fallDamage", GameruleValue::Bool),
+    ("fireDamage", GameruleValue::Bool),
+    ("forgiveDeadPlayers", GameruleValue::Bool),
+    ("freezeDamage", GameruleValue::Bool),
+    ("globalSoundEvents", GameruleValue::Bool),
+    ("keepInventory", GameruleValue::Bool),
+    ("lavaSourceConversion", GameruleValue::Bool),
+    ("logAdminCommands", GameruleValue::Bool),
+    ("maxCommandChainLength", GameruleValue::Int),
+    ("maxCommandForkCount", GameruleValue::Int),
+    ("maxEntityCramming", GameruleValue::Int),
+    ("mobExplosionDropDecay", GameruleValue::Bool),
+    ("mobGriefing", GameruleValue::Bool),
+    ("naturalRegeneration", GameruleValue::Bool),
+    ("playersNetherPortalCreativeDelay", GameruleValue::Int),
+    ("playersNetherPortalDefaultDelay", GameruleValue::Int),
+    ("playersSleepingPercentage", GameruleValue::Int),
+    ("projectilesCanBreakBlocks", GameruleValue::Bool),
+    ("randomTickSpeed", GameruleValue::Int),
+    ("reducedDebugInfo", GameruleValue::Bool),
+    ("sendCommandFeedback", GameruleValue::Bool),
+    ("showDeathMessages", GameruleValue::Bool),
+    ("snowAccumulationHeight", GameruleValue::Int),
+    ("spawnChunkRadius", GameruleValue::Int),
+    ("spawnRadius", GameruleValue::Int),
+    ("spectatorsGenerateChunks", GameruleValue::Bool),
+    ("tntExplosionDropDecay", GameruleValue::Bool),
+    ("universalAnger", GameruleValue::Bool),
+    ("waterSourceConversion", GameruleValue::Bool),
+];
+
+// Bedrock spells the rules in lowercase and has some of its own, like pvp
+const BEDROCK_GAMERULES: &[(&str, GameruleValue)] = &[
+    ("commandblockoutput", GameruleValue::Bool),
+    ("commandblocksenabled", GameruleValue::Bool),
+    ("dodaylightcycle", GameruleValue::Bool),
+    ("doentitydrops", GameruleValue::Bool),
+    ("dofiretick", GameruleValue::Bool),
+    ("doimmediaterespawn", GameruleValue::Bool),
+    ("doinsomnia", GameruleValue::Bool),
+    ("dolimitedcrafting", GameruleValue::Bool),
+    ("domobloot", GameruleValue::Bool),
+    ("domobspawning", GameruleValue::Bool),
+    ("dotiledrops", GameruleValue::Bool),
+    ("doweathercycle", GameruleValue::Bool),
+    ("drowningdamage", GameruleValue::Bool),
+    ("falldamage", GameruleValue::Bool),
+    ("firedamage", GameruleValue::Bool),
+    ("freezedamage", GameruleValue::Bool),
+    ("functioncommandlimit", GameruleValue::Int),
+    ("keepinventory", GameruleValue::Bool),
+    ("maxcommandchainlength", GameruleValue::Int),
+    ("mobgriefing", GameruleValue::Bool),
+    ("naturalregeneration", GameruleValue::Bool),
+    ("playerssleepingpercentage", GameruleValue::Int),
+    ("projectilescanbreakblocks", GameruleValue::Bool),
+    ("pvp", GameruleValue::Bool),
+    ("randomtickspeed", GameruleValue::Int),
+    ("recipesunlock", GameruleValue::Bool),
+    ("respawnblocksexplode", GameruleValue::Bool),
+    ("sendcommandfeedback", GameruleValue::Bool),
+    ("showbordereffect", GameruleValue::Bool),
+    ("showcoordinates", GameruleValue::Bool),
+    ("showdaysplayed", GameruleValue::Bool),
+    ("showdeathmessages", GameruleValue::Bool),
+    ("showrecipemessages", GameruleValue::Bool),
+    ("showtags", GameruleValue::Bool),
+    ("spawnradius", GameruleValue::Int),
+    ("tntexplodes", GameruleValue::Bool),
+    ("tntexplosiondropdecay", GameruleValue::Bool),
+];
+
+/// The gamerules of an edition and what each is set to
+pub fn gamerules(edition: Edition) -> &'static [(&'static str, GameruleValue)] {
+    match edition {
+        Edition::Java => JAVA_GAMERULES,
+        Edition::Bedrock => BEDROCK_GAMERULES,
+    }
+}
+
+/// What `rule` is set to, Bedrock matching rules regardless of case like the server does
+pub fn gamerule_value(edition: Edition, rule: &str) -> Option<GameruleValue> {
+    gamerules(edition)
+        .iter()
+        .find(|(name, _)| match edition {
+            Edition::Java => *name == rule,
+            Edition::Bedrock => name.eq_ignore_ascii_case(rule),
+        })
+        .map(|(_, value)| *value)
+}
+
+// Commands that only read server state, by their first words without the prefix
+const QUERY_COMMANDS: &[&str] = &[
+    "list",
+    "seed",
+    "banlist",
+    "whitelist list",
+    "time query",
+    "weather query",
+    "data get",
+    "scoreboard players get",
+    "scoreboard players list",
+    "scoreboard objectives list",
+    "team list",
+    "forceload query",
+    "datapack list",
+];
+
+/// Whether a command only reads server state, so sending it twice does no harm
+pub fn is_query_command(cmd: &str) -> bool {
+    let words: Vec<&str> = cmd
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .split_whitespace()
+        .collect();
+    QUERY_COMMANDS.iter().any(|query| {
+        let query: Vec<&str> = query.split(' ').collect();
+        words.starts_with(&query)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curated_subcommands_follow_the_command_char() {
+        let mut commands = HashMap::from([
+            ("!scoreboard".to_string(), vec![]),
+            ("!spawnpoint".to_string(), vec![]),
+        ]);
+        apply_curated(&mut commands, "!");
+        assert_eq!(
+            commands["!scoreboard players enable"],
+            [required("targets"), required("objective")]
+        );
+        assert_eq!(commands["!scoreboard players operation"].len(), 5);
+        assert_eq!(commands["!spawnpoint"][0], optional("targets"));
+        assert!(!commands.contains_key("!effect give"));
+    }
+
+    #[test]
+    fn numeric_game_modes_have_names() {
+        assert_eq!(game_mode_name("1"), Some("creative"));
+        assert_eq!(game_mode_name("spectator"), Some("spectator"));
+        assert_eq!(game_mode_name("4"), None);
+        assert_eq!(candidate_label("gamemode", "3"), Some("spectator"));
+        assert_eq!(candidate_label("gamemode", "survival"), None);
+        assert_eq!(candidate_label("count", "1"), None);
+    }
+
+    #[test]
+    fn hints_numeric_arguments() {
+        assert_eq!(
+            numeric_hint(&["setidletimeout"]),
+            Some("<minutes (0 disables)>")
+        );
+        assert_eq!(
+            numeric_hint(&["xp", "add", "@p"]),
+            Some("<amount (points, or levels with levels after it)>")
+        );
+        assert_eq!(numeric_hint(&["xp", "add"]), None);
+        assert_eq!(numeric_hint(&["say"]), None);
+    }
+
+    #[test]
+    fn offers_ids_in_the_chosen_namespace_style() {
+        assert_eq!(
+            NamespaceStyle::Explicit.id_candidates("stone"),
+            ["minecraft:stone"]
+        );
+        assert_eq!(NamespaceStyle::Bare.id_candidates("stone"), ["stone"]);
+        assert_eq!(
+            NamespaceStyle::Both.id_candidates("stone"),
+            ["minecraft:stone", "stone"]
+        );
+    }
+
+    #[test]
+    fn boolean_argument_names_offer_true_and_false() {
+        for name in ["value", "bool", "boolean", "enabled"] {
+            assert_eq!(argument_candidates(name), BOOLEANS);
+        }
+        assert!(argument_candidates("message").is_empty());
+    }
+
+    #[test]
+    fn enchantment_levels_stop_at_the_max() {
+        assert_eq!(enchantment_max_level("minecraft:sharpness"), Some(5));
+        assert_eq!(enchantment_max_level("unbreaking"), Some(3));
+        assert_eq!(enchantment_max_level("mending"), Some(1));
+        assert_eq!(enchantment_max_level("minecraft:speed"), None);
+        assert_eq!(
+            dependent_candidates("enchantment", "minecraft:fire_aspect", "level"),
+            ["1", "2"]
+        );
+        assert!(dependent_candidates("effect", "minecraft:speed", "level").is_empty());
+    }
+
+    #[test]
+    fn only_reads_count_as_queries() {
+        assert!(is_query_command("/list uuids"));
+        assert!(is_query_command("!time query daytime"));
+        assert!(!is_query_command("/time set day"));
+        assert!(!is_query_command("/give @p minecraft:diamond"));
+    }
+
+    #[test]
+    fn gamerule_values_follow_the_edition() {
+        assert_eq!(
+            gamerule_value(Edition::Java, "randomTickSpeed"),
+            Some(GameruleValue::Int)
+        );
+        assert_eq!(gamerule_value(Edition::Java, "randomtickspeed"), None);
+        assert_eq!(
+            gamerule_value(Edition::Bedrock, "RandomTickSpeed"),
+            Some(GameruleValue::Int)
+        );
+        assert_eq!(gamerule_value(Edition::Java, "showcoordinates"), None);
+    }
+}
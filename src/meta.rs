@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use crate::shortcuts::{self, Shortcut};
+use crate::tail;
+
+/// Commands handled by the CLI itself instead of being sent to the server
+#[derive(Debug, PartialEq)]
+pub enum MetaCommand {
+    /// `:set` lists options, `:set <option> <on|off>` changes one
+    Set(Option<(String, bool)>),
+    /// `:info <command>` shows a command's usage and description
+    Info(String),
+    /// `:grep [-v] <pattern>` filters the previous response
+    Grep {
+        pattern: String,
+        invert: bool,
+    },
+    /// `:difficulty`, `:weather` and the other read shortcuts
+    Shortcut(&'static Shortcut),
+    /// `:alias <name> <expansion>` defines an alias
+    Alias {
+        name: String,
+        expansion: String,
+    },
+    /// `:unalias <name>` removes one
+    Unalias(String),
+    /// `:aliases` lists them
+    Aliases,
+    /// `:alias-save` writes them to the config file
+    AliasSave,
+    /// `:macro record <name>` captures the commands sent until `:macro stop`
+    MacroRecord(String),
+    MacroStop,
+    /// `:macro run <name>` sends the recorded commands again
+    MacroRun(String),
+    /// `:more` prints the rest of a response cut off by --max-output-lines
+    More,
+    /// `:commands` lists the command names known for completion
+    Commands,
+    /// `:send-file <path>` sends a file's contents as one command
+    SendFile(String),
+    /// `:dim <dimension>` runs the commands after it in that dimension, `:dim` alone
+    /// goes back to the default one
+    Dim(Option<String>),
+    /// `:where <selector>` prints an entity's coordinates
+    Where(String),
+    /// `:uuid <name>` prints a player's UUID seen in `/list uuids`
+    Uuid(String),
+    /// `:tail <command> <seconds>` runs a command repeatedly, printing only new lines
+    Tail {
+        command: String,
+        interval: Duration,
+    },
+}
+
+impl MetaCommand {
+    /// Whether it sends something to the server, and so needs a working connection
+    pub fn sends_command(&self) -> bool {
+        matches!(
+            self,
+            MetaCommand::Info(_)
+                | MetaCommand::Shortcut(_)
+                | MetaCommand::Where(_)
+                | MetaCommand::Tail { .. }
+        )
+    }
+}
+
+fn parse_switch(value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" => Ok(true),
+        "off" | "false" => Ok(false),
+        _ => Err(format!("Expected on or off, got '{value}'")),
+    }
+}
+
+/// Parse a `:`-prefixed line. Returns None for lines meant for the server.
+pub fn parse(line: &str) -> Option<Result<MetaCommand, String>> {
+    let line = line.strip_prefix(':')?;
+    // The pattern is taken verbatim so it may contain spaces
+    if let Some(args) = line.strip_prefix("grep ") {
+        let args = args.trim_start();
+        return Some(match args.strip_prefix("-v ") {
+            Some(pattern) => Ok(MetaCommand::Grep {
+                pattern: pattern.trim_start().to_string(),
+                invert: true,
+            }),
+            None => Ok(MetaCommand::Grep {
+                pattern: args.to_string(),
+                invert: false,
+            }),
+        });
+    }
+    // Like patterns, expansions are kept as typed
+    if let Some((name, expansion)) = line
+        .strip_prefix("alias ")
+        .and_then(|args| args.trim_start().split_once(char::is_whitespace))
+    {
+        return Some(Ok(MetaCommand::Alias {
+            name: name.to_string(),
+            expansion: expansion.trim().to_string(),
+        }));
+    }
+    // Paths may contain spaces too
+    if let Some(path) = line.strip_prefix("send-file ") {
+        return Some(match path.trim() {
+            "" => Err("Usage: :send-file <path>".to_string()),
+            path => Ok(MetaCommand::SendFile(path.to_string())),
+        });
+    }
+    // Selectors like @e[type=cow, limit=1] may contain spaces
+    if let Some(selector) = line.strip_prefix("where ") {
+        return Some(match selector.trim() {
+            "" => Err("Usage: :where <selector>".to_string()),
+            selector => Ok(MetaCommand::Where(selector.to_string())),
+        });
+    }
+    // The command may have spaces, the interval is the last word
+    if let Some(args) = line.strip_prefix("tail ") {
+        return Some(match args.trim().rsplit_once(char::is_whitespace) {
+            Some((command, interval)) => {
+                tail::parse_interval(interval).map(|interval| MetaCommand::Tail {
+                    command: command.trim().to_string(),
+                    interval,
+                })
+            }
+            None => Err("Usage: :tail <command> <seconds>".to_string()),
+        });
+    }
+    let words: Vec<&str> = line.split_whitespace().collect();
+    Some(match words.as_slice() {
+        ["set"] => Ok(MetaCommand::Set(None)),
+        ["set", option, value] => {
+            parse_switch(value).map(|value| MetaCommand::Set(Some((option.to_string(), value))))
+        }
+        ["set", ..] => Err("Usage: :set [<option> <on|off>]".to_string()),
+        ["info", command] => Ok(MetaCommand::Info(
+            command.trim_start_matches('/').to_string(),
+        )),
+        ["info", ..] => Err("Usage: :info <command>".to_string()),
+        ["grep"] => Err("Usage: :grep [-v] <pattern>".to_string()),
+        ["alias", ..] => Err("Usage: :alias <name> <expansion>".to_string()),
+        ["unalias", name] => Ok(MetaCommand::Unalias(name.to_string())),
+        ["unalias", ..] => Err("Usage: :unalias <name>".to_string()),
+        ["aliases"] => Ok(MetaCommand::Aliases),
+        ["alias-save"] => Ok(MetaCommand::AliasSave),
+        ["macro", "record", name] => Ok(MetaCommand::MacroRecord(name.to_string())),
+        ["macro", "stop"] => Ok(MetaCommand::MacroStop),
+        ["macro", "run", name] => Ok(MetaCommand::MacroRun(name.to_string())),
+        ["more"] => Ok(MetaCommand::More),
+        ["commands"] => Ok(MetaCommand::Commands),
+        ["send-file"] => Err("Usage: :send-file <path>".to_string()),
+        ["dim"] => Ok(MetaCommand::Dim(None)),
+        ["dim", dimension] => Ok(MetaCommand::Dim(Some(dimension.to_string()))),
+        ["dim", ..] => Err("Usage: :dim [<dimension>]".to_string()),
+        ["where"] => Err("Usage: :where <selector>".to_string()),
+        ["uuid", name] => Ok(MetaCommand::Uuid(name.to_string())),
+        ["uuid", ..] => Err("Usage: :uuid <name>".to_string()),
+        ["tail"] => Err("Usage: :tail <command> <seconds>".to_string()),
+        ["macro", ..] => Err("Usage: :macro record <name> | stop | run <name>".to_string()),
+        [name, rest @ ..] => match shortcuts::find(name) {
+            Some(shortcut) if rest.is_empty() => Ok(MetaCommand::Shortcut(shortcut)),
+            Some(_) => Err(format!("Usage: :{name}")),
+            None => Err(format!("Unknown meta-command ':{name}'")),
+        },
+        [] => Err("Missing meta-command name".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knows_which_meta_commands_send() {
+        let sends = |line: &str| parse(line).unwrap().unwrap().sends_command();
+        assert!(sends(":info give"));
+        assert!(sends(":where @p"));
+        assert!(sends(":tail /list 5"));
+        assert!(sends(":seed"));
+        assert!(!sends(":aliases"));
+        assert!(!sends(":uuid Steve"));
+    }
+
+    #[test]
+    fn parses_set() {
+        assert_eq!(parse(":set"), Some(Ok(MetaCommand::Set(None))));
+        assert_eq!(
+            parse(":set raw off"),
+            Some(Ok(MetaCommand::Set(Some(("raw".to_string(), false)))))
+        );
+        assert_eq!(
+            parse(":set raw maybe"),
+            Some(Err("Expected on or off, got 'maybe'".to_string()))
+        );
+        assert_eq!(parse("/set"), None);
+    }
+
+    #[test]
+    fn grep_patterns_are_kept_verbatim() {
+        assert_eq!(
+            parse(":grep -v  was banned by"),
+            Some(Ok(MetaCommand::Grep {
+                pattern: "was banned by".to_string(),
+                invert: true,
+            }))
+        );
+        assert_eq!(
+            parse(":grep"),
+            Some(Err("Usage: :grep [-v] <pattern>".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_shortcuts() {
+        assert_eq!(
+            parse(":difficulty"),
+            Some(Ok(MetaCommand::Shortcut(
+                shortcuts::find("difficulty").unwrap()
+            )))
+        );
+        assert_eq!(parse(":seed now"), Some(Err("Usage: :seed".to_string())));
+        assert_eq!(
+            parse(":nope"),
+            Some(Err("Unknown meta-command ':nope'".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_alias_management() {
+        assert_eq!(
+            parse(":alias day  /time set day "),
+            Some(Ok(MetaCommand::Alias {
+                name: "day".to_string(),
+                expansion: "/time set day".to_string(),
+            }))
+        );
+        assert_eq!(
+            parse(":unalias day"),
+            Some(Ok(MetaCommand::Unalias("day".to_string())))
+        );
+        assert_eq!(parse(":aliases"), Some(Ok(MetaCommand::Aliases)));
+        assert_eq!(parse(":alias-save"), Some(Ok(MetaCommand::AliasSave)));
+    }
+
+    #[test]
+    fn parses_more() {
+        assert_eq!(parse(":more"), Some(Ok(MetaCommand::More)));
+        assert!(!parse(":more").unwrap().unwrap().sends_command());
+    }
+
+    #[test]
+    fn send_file_paths_may_contain_spaces() {
+        assert_eq!(
+            parse(":send-file  big nbt.txt "),
+            Some(Ok(MetaCommand::SendFile("big nbt.txt".to_string())))
+        );
+        assert_eq!(
+            parse(":send-file "),
+            Some(Err("Usage: :send-file <path>".to_string()))
+        );
+    }
+
+    #[test]
+    fn tail_takes_the_interval_from_the_last_word() {
+        assert_eq!(
+            parse(":tail /scoreboard players list  2"),
+            Some(Ok(MetaCommand::Tail {
+                command: "/scoreboard players list".to_string(),
+                interval: Duration::from_secs(2),
+            }))
+        );
+        assert_eq!(
+            parse(":tail /list"),
+            Some(Err("Usage: :tail <command> <seconds>".to_string()))
+        );
+        assert_eq!(
+            parse(":tail /list now"),
+            Some(Err(
+                "Expected a positive number of seconds, got 'now'".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn dim_sets_or_clears_the_dimension() {
+        assert_eq!(
+            parse(":dim minecraft:the_nether"),
+            Some(Ok(MetaCommand::Dim(Some(
+                "minecraft:the_nether".to_string()
+            ))))
+        );
+        assert_eq!(parse(":dim"), Some(Ok(MetaCommand::Dim(None))));
+    }
+
+    #[test]
+    fn parses_uuid_lookups() {
+        assert_eq!(
+            parse(":uuid Steve"),
+            Some(Ok(MetaCommand::Uuid("Steve".to_string())))
+        );
+    }
+}
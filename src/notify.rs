@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+/// Whether a command took long enough to notify about
+pub fn should_notify(elapsed: Duration, threshold: Option<Duration>) -> bool {
+    threshold.is_some_and(|threshold| elapsed >= threshold)
+}
+
+/// Ring the terminal bell, and optionally show a desktop notification
+pub fn notify(cmd: &str, elapsed: Duration, desktop: bool) {
+    print!("\x07");
+    if desktop {
+        show_desktop_notification(cmd, elapsed);
+    }
+}
+
+#[cfg(feature = "desktop-notifications")]
+fn show_desktop_notification(cmd: &str, elapsed: Duration) {
+    let result = notify_rust::Notification::new()
+        .summary("Minecraft RCON command finished")
+        .body(&format!("{cmd} took {} ms", elapsed.as_millis()))
+        .show();
+    if let Err(e) = result {
+        log::warn!("Failed to show desktop notification: {e}");
+    }
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+fn show_desktop_notification(_cmd: &str, _elapsed: Duration) {
+    log::warn!("Built without the desktop-notifications feature");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifies_at_the_threshold() {
+        let threshold = Some(Duration::from_millis(500));
+        assert!(!should_notify(Duration::from_millis(499), threshold));
+        assert!(should_notify(Duration::from_millis(500), threshold));
+        assert!(!should_notify(Duration::from_secs(60), None));
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Keeps generated names well below common file name limits
+const MAX_NAME_LEN: usize = 64;
+
+/// File name friendly form of a command: anything but ASCII letters, digits, `-` and `.`
+/// becomes `_`, with runs collapsed and the leading command prefix dropped
+pub fn sanitize(cmd: &str, command_char: &str) -> String {
+    let mut sanitized = String::with_capacity(cmd.len());
+    for c in cmd.strip_prefix(command_char).unwrap_or(cmd).chars() {
+        let c = if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+            c
+        } else {
+            '_'
+        };
+        if !(c == '_' && sanitized.ends_with('_')) {
+            sanitized.push(c);
+        }
+    }
+    let sanitized = sanitized.trim_matches(|c| c == '_' || c == '.');
+    let sanitized: String = sanitized.chars().take(MAX_NAME_LEN).collect();
+    if sanitized.is_empty() {
+        "command".to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// `<milliseconds since the epoch>-<sanitized command>.txt`, so files sort by time
+pub fn output_file_name(cmd: &str, timestamp_ms: u128, command_char: &str) -> String {
+    format!("{timestamp_ms}-{}.txt", sanitize(cmd, command_char))
+}
+
+/// Write one command's response to a new file in `dir`, returning its path
+pub fn write_output(dir: &Path, cmd: &str, contents: &str, command_char: &str) -> Result<PathBuf> {
+    let timestamp_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let mut path = dir.join(output_file_name(cmd, timestamp_ms, command_char));
+    // Commands sent within the same millisecond get a counter
+    let mut counter = 1;
+    while path.exists() {
+        counter += 1;
+        path = dir.join(format!(
+            "{timestamp_ms}-{}-{counter}.txt",
+            sanitize(cmd, command_char)
+        ));
+    }
+    std::fs::write(&path, format!("{contents}\n"))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_the_command_char_from_file_names() {
+        assert_eq!(sanitize("/give @p diamond", "/"), "give_p_diamond");
+        assert_eq!(sanitize("!give @p diamond", "!"), "give_p_diamond");
+        assert_eq!(output_file_name("!list", 42, "!"), "42-list.txt");
+    }
+
+    #[test]
+    fn sanitizes_odd_commands() {
+        assert_eq!(
+            sanitize("/data get entity @e[type=cow]", "/"),
+            "data_get_entity_e_type_cow"
+        );
+        assert_eq!(sanitize("/", "/"), "command");
+        assert_eq!(
+            sanitize(&format!("/say {}", "a".repeat(100)), "/").len(),
+            MAX_NAME_LEN
+        );
+    }
+
+    #[test]
+    fn never_overwrites_an_earlier_response() {
+        let dir = std::env::temp_dir().join(format!("mc-rcon-{}-output", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let first = write_output(&dir, "/list", "one", "/").unwrap();
+        let second = write_output(&dir, "/list", "two", "/").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read_to_string(first).unwrap(), "one\n");
+        assert_eq!(std::fs::read_to_string(second).unwrap(), "two\n");
+    }
+}
@@ -0,0 +1,130 @@
+use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, KeyCode, KeyEvent, Modifiers};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// A command bound to a key in the config file's `[keys]` table, either just the command
+/// to insert or a table that can also run it:
+///
+/// ```toml
+/// [keys]
+/// F2 = "/list"
+/// F3 = { command = "/save-all", run = true }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyBinding {
+    Insert(String),
+    Command {
+        command: String,
+        #[serde(default)]
+        run: bool,
+    },
+}
+
+impl KeyBinding {
+    pub fn command(&self) -> &str {
+        match self {
+            KeyBinding::Insert(command) | KeyBinding::Command { command, .. } => command,
+        }
+    }
+
+    pub fn runs(&self) -> bool {
+        matches!(self, KeyBinding::Command { run: true, .. })
+    }
+}
+
+/// Parse a key name from `[keys]`: `F1` to `F12`, `Alt-<char>` or `Ctrl-<letter>`,
+/// regardless of case
+pub fn parse_key(name: &str) -> Result<KeyEvent, String> {
+    let invalid =
+        || format!("Unknown key '{name}', expected F1 to F12, Alt-<char> or Ctrl-<letter>");
+    let lower = name.to_ascii_lowercase();
+    if let Some(number) = lower.strip_prefix('f') {
+        return match number.parse::<u8>() {
+            Ok(number @ 1..=12) => Ok(KeyEvent(KeyCode::F(number), Modifiers::NONE)),
+            _ => Err(invalid()),
+        };
+    }
+    let (modifier, key) = lower.split_once('-').ok_or_else(invalid)?;
+    let mut chars = key.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return Err(invalid());
+    };
+    match modifier {
+        "alt" => Ok(KeyEvent::alt(c)),
+        "ctrl" if c.is_ascii_lowercase() => Ok(KeyEvent::ctrl(c)),
+        _ => Err(invalid()),
+    }
+}
+
+/// A command picked with a `run` key, for the shell to send after the empty line it
+/// accepts
+pub type PendingCommand = Arc<Mutex<Option<String>>>;
+
+/// Key handler for one binding. At an empty prompt it inserts the command, or for a
+/// `run` binding leaves it in `pending` and accepts the line. Elsewhere the key does
+/// what it did before.
+pub struct PaletteHandler {
+    binding: KeyBinding,
+    pending: PendingCommand,
+}
+
+impl PaletteHandler {
+    pub fn new(binding: KeyBinding, pending: PendingCommand) -> PaletteHandler {
+        PaletteHandler { binding, pending }
+    }
+}
+
+impl ConditionalEventHandler for PaletteHandler {
+    fn handle(
+        &self,
+        _evt: &Event,
+        _n: rustyline::RepeatCount,
+        _positive: bool,
+        ctx: &EventContext,
+    ) -> Option<Cmd> {
+        if !ctx.line().is_empty() {
+            return None;
+        }
+        if self.binding.runs() {
+            *self.pending.lock().unwrap() = Some(self.binding.command().to_string());
+            return Some(Cmd::AcceptLine);
+        }
+        Some(Cmd::Insert(1, self.binding.command().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_names() {
+        assert_eq!(
+            parse_key("F2"),
+            Ok(KeyEvent(KeyCode::F(2), Modifiers::NONE))
+        );
+        assert_eq!(parse_key("alt-S"), Ok(KeyEvent::alt('s')));
+        assert_eq!(parse_key("Ctrl-g"), Ok(KeyEvent::ctrl('g')));
+        for name in ["F13", "Ctrl-1", "Shift-a", "Alt-ab", "Enter"] {
+            assert!(parse_key(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn bindings_insert_or_run_their_command() {
+        let keys: std::collections::BTreeMap<String, KeyBinding> = toml::from_str(
+            "F2 = \"/list\"\nF3 = { command = \"/save-all\", run = true }\n\
+F4 = { command = \"/seed\" }",
+        )
+        .unwrap();
+        let bound: Vec<(&str, bool)> = keys
+            .values()
+            .map(|binding| (binding.command(), binding.runs()))
+            .collect();
+        assert_eq!(
+            bound,
+            [("/list", false), ("/save-all", true), ("/seed", false)]
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::PathBuf;
+
+/// A named server in the config file's `[profiles.<name>]` tables
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_file: Option<PathBuf>,
+    /// Commands sent right after connecting, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_connect: Vec<String>,
+}
+
+/// Numbered `1) name  address` lines, in name order
+pub fn format_menu(profiles: &BTreeMap<String, Profile>) -> String {
+    let width = profiles.keys().map(String::len).max().unwrap_or(0);
+    profiles
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, profile))| format!("{}) {name:width$}  {}", idx + 1, profile.address))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Resolve a menu answer, either the number shown or the profile name
+pub fn parse_selection<'a>(
+    answer: &str,
+    profiles: &'a BTreeMap<String, Profile>,
+) -> Result<&'a str, String> {
+    let answer = answer.trim();
+    let by_number = answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_sub(1))
+        .and_then(|idx| profiles.keys().nth(idx));
+    by_number
+        .or_else(|| profiles.keys().find(|name| *name == answer))
+        .map(String::as_str)
+        .ok_or_else(|| format!("No profile '{answer}', enter 1 to {}", profiles.len()))
+}
+
+/// Ask which profile to use on stdin. Without a terminal to ask on, fail with the list.
+pub fn pick(profiles: &BTreeMap<String, Profile>) -> Result<String> {
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "Can't ask for a profile without a terminal, use --profile with one of:\n{}",
+            format_menu(profiles)
+        );
+    }
+    println!("{}", format_menu(profiles));
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        print!("Profile: ");
+        std::io::stdout().flush()?;
+        let Some(answer) = lines.next().transpose()? else {
+            bail!("No profile selected");
+        };
+        match parse_selection(&answer, profiles) {
+            Ok(name) => return Ok(name.to_string()),
+            Err(e) => eprintln!("Error: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles() -> BTreeMap<String, Profile> {
+        ["survival", "creative"]
+            .into_iter()
+            .enumerate()
+            .map(|(idx, name)| {
+                let profile = Profile {
+                    address: format!("10.0.0.{}:25575", idx + 1),
+                    password: None,
+                    password_file: None,
+                    on_connect: Vec::new(),
+                };
+                (name.to_string(), profile)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lists_profiles_in_name_order() {
+        assert_eq!(
+            format_menu(&profiles()),
+            "1) creative  10.0.0.2:25575\n2) survival  10.0.0.1:25575"
+        );
+    }
+
+    #[test]
+    fn selects_by_number_or_name() {
+        let profiles = profiles();
+        assert_eq!(parse_selection(" 2\n", &profiles), Ok("survival"));
+        assert_eq!(parse_selection("creative", &profiles), Ok("creative"));
+        assert_eq!(
+            parse_selection("0", &profiles),
+            Err("No profile '0', enter 1 to 2".to_string())
+        );
+        assert!(parse_selection("3", &profiles).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, TcpStream};
+
+const SOCKS_VERSION: u8 = 5;
+const DEFAULT_PORT: u16 = 1080;
+
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_USER_PASS: u8 = 0x02;
+const METHOD_REJECTED: u8 = 0xff;
+
+const ADDRESS_IPV4: u8 = 0x01;
+const ADDRESS_DOMAIN: u8 = 0x03;
+const ADDRESS_IPV6: u8 = 0x04;
+
+/// A SOCKS5 proxy to open the RCON connection through, from `socks5://[user:pass@]host[:port]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Proxy {
+    pub host: String,
+    pub port: u16,
+    pub credentials: Option<(String, String)>,
+}
+
+// Leaves the password out, so the proxy can be logged
+impl fmt::Display for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "socks5://")?;
+        if let Some((user, _)) = &self.credentials {
+            write!(f, "{user}@")?;
+        }
+        write!(f, "{}", join_host_port(&self.host, self.port))
+    }
+}
+
+fn join_host_port(host: &str, port: u16) -> String {
+    match host.contains(':') {
+        true => format!("[{host}]:{port}"),
+        false => format!("{host}:{port}"),
+    }
+}
+
+/// Split `host:port`, with IPv6 hosts in brackets. The port is optional when a default
+/// is given.
+fn split_host_port(s: &str, default_port: Option<u16>) -> Result<(String, u16), String> {
+    let (host, port) = match s.strip_prefix('[') {
+        Some(rest) => {
+            let (host, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| format!("missing `]` in `{s}`"))?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match s.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (s, None),
+        },
+    };
+    if host.is_empty() {
+        return Err(format!("missing host in `{s}`"));
+    }
+    let port = match (port, default_port) {
+        (Some(port), _) => port
+            .parse()
+            .map_err(|_| format!("invalid port `{port}` in `{s}`"))?,
+        (None, Some(port)) => port,
+        (None, None) => return Err(format!("missing port in `{s}`")),
+    };
+    Ok((host.to_string(), port))
+}
+
+/// Parse a `socks5://[user:pass@]host[:port]` proxy URL, the port defaults to 1080
+pub fn parse_proxy(s: &str) -> Result<Proxy, String> {
+    let rest = s
+        .strip_prefix("socks5://")
+        .or_else(|| s.strip_prefix("socks5h://"))
+        .ok_or_else(|| format!("expected a socks5:// URL, got `{s}`"))?;
+    let rest = rest.strip_suffix('/').unwrap_or(rest);
+    let (credentials, host_port) = match rest.rsplit_once('@') {
+        Some((userinfo, host_port)) => {
+            let (user, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+            // The username/password method can't carry more than 255 bytes each
+            if user.is_empty() || user.len() > 255 || password.len() > 255 {
+                return Err(format!("invalid proxy credentials in `{s}`"));
+            }
+            (Some((user.to_string(), password.to_string())), host_port)
+        }
+        None => (None, rest),
+    };
+    let (host, port) = split_host_port(host_port, Some(DEFAULT_PORT))?;
+    Ok(Proxy {
+        host,
+        port,
+        credentials,
+    })
+}
+
+fn proxy_error(msg: impl Into<String>) -> io::Error {
+    io::Error::other(format!("SOCKS5 proxy: {}", msg.into()))
+}
+
+fn reply_error(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "connection not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+impl Proxy {
+    /// Open a TCP stream to `target` (`host:port`) through the proxy
+    pub fn connect(&self, target: &str) -> io::Result<TcpStream> {
+        let (host, port) = split_host_port(target, None).map_err(proxy_error)?;
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port))?;
+        self.negotiate(&mut stream)?;
+
+        let mut request = vec![SOCKS_VERSION, 0x01, 0x00];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(ADDRESS_IPV4);
+                request.extend(ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(ADDRESS_IPV6);
+                request.extend(ip.octets());
+            }
+            // Hostnames are resolved by the proxy, they may only exist behind it
+            Err(_) => {
+                let len = u8::try_from(host.len()).map_err(|_| proxy_error("hostname too long"))?;
+                request.extend([ADDRESS_DOMAIN, len]);
+                request.extend(host.as_bytes());
+            }
+        }
+        request.extend(port.to_be_bytes());
+        stream.write_all(&request)?;
+
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply)?;
+        if reply[0] != SOCKS_VERSION {
+            return Err(proxy_error("not a SOCKS5 server"));
+        }
+        if reply[1] != 0x00 {
+            return Err(proxy_error(format!(
+                "connecting to {target} failed: {}",
+                reply_error(reply[1])
+            )));
+        }
+        // Skip the bound address, it isn't needed
+        let address_len = match reply[3] {
+            ADDRESS_IPV4 => 4,
+            ADDRESS_IPV6 => 16,
+            ADDRESS_DOMAIN => {
+                let mut len = [0; 1];
+                stream.read_exact(&mut len)?;
+                len[0] as usize
+            }
+            other => return Err(proxy_error(format!("unknown address type {other}"))),
+        };
+        let mut bound = vec![0; address_len + 2];
+        stream.read_exact(&mut bound)?;
+        Ok(stream)
+    }
+
+    /// Agree on an authentication method and authenticate
+    fn negotiate(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let methods: &[u8] = match self.credentials {
+            Some(_) => &[METHOD_NO_AUTH, METHOD_USER_PASS],
+            None => &[METHOD_NO_AUTH],
+        };
+        let mut greeting = vec![SOCKS_VERSION, methods.len() as u8];
+        greeting.extend(methods);
+        stream.write_all(&greeting)?;
+
+        let mut choice = [0; 2];
+        stream.read_exact(&mut choice)?;
+        if choice[0] != SOCKS_VERSION {
+            return Err(proxy_error("not a SOCKS5 server"));
+        }
+        match (choice[1], &self.credentials) {
+            (METHOD_NO_AUTH, _) => Ok(()),
+            (METHOD_USER_PASS, Some((user, password))) => {
+                // RFC 1929 username/password subnegotiation
+                let mut request = vec![0x01, user.len() as u8];
+                request.extend(user.as_bytes());
+                request.push(password.len() as u8);
+                request.extend(password.as_bytes());
+                stream.write_all(&request)?;
+                let mut status = [0; 2];
+                stream.read_exact(&mut status)?;
+                match status[1] {
+                    0x00 => Ok(()),
+                    _ => Err(proxy_error("authentication failed")),
+                }
+            }
+            (METHOD_REJECTED | METHOD_USER_PASS, None) => Err(proxy_error(
+                "the proxy requires authentication, add user:password@ to the URL",
+            )),
+            _ => Err(proxy_error("no supported authentication method")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proxy_urls() {
+        assert_eq!(
+            parse_proxy("socks5://bastion"),
+            Ok(Proxy {
+                host: "bastion".to_string(),
+                port: DEFAULT_PORT,
+                credentials: None,
+            })
+        );
+        assert_eq!(
+            parse_proxy("socks5h://admin:p@ss@[::1]:9050/"),
+            Ok(Proxy {
+                host: "::1".to_string(),
+                port: 9050,
+                credentials: Some(("admin".to_string(), "p@ss".to_string())),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_proxy_urls() {
+        for (url, error) in [
+            (
+                "http://bastion:8080",
+                "expected a socks5:// URL, got `http://bastion:8080`",
+            ),
+            ("socks5://bastion:x", "invalid port `x` in `bastion:x`"),
+            ("socks5://:1080", "missing host in `:1080`"),
+            ("socks5://[::1:1080", "missing `]` in `[::1:1080`"),
+            (
+                "socks5://:pw@bastion",
+                "invalid proxy credentials in `socks5://:pw@bastion`",
+            ),
+        ] {
+            assert_eq!(parse_proxy(url), Err(error.to_string()));
+        }
+    }
+
+    #[test]
+    fn display_leaves_the_password_out() {
+        let proxy = parse_proxy("socks5://admin:secret@[::1]").unwrap();
+        assert_eq!(proxy.to_string(), "socks5://admin@[::1]:1080");
+    }
+
+    #[test]
+    fn connects_through_a_proxy_without_authentication() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut greeting = [0; 3];
+            conn.read_exact(&mut greeting).unwrap();
+            conn.write_all(&[SOCKS_VERSION, METHOD_NO_AUTH]).unwrap();
+            let mut request = [0; 10];
+            conn.read_exact(&mut request).unwrap();
+            conn.write_all(&[SOCKS_VERSION, 0, 0, ADDRESS_IPV4, 0, 0, 0, 0, 0, 0])
+                .unwrap();
+            request
+        });
+        let proxy = parse_proxy(&format!("socks5://127.0.0.1:{port}")).unwrap();
+        proxy.connect("10.0.0.5:25575").unwrap();
+        let request = server.join().unwrap();
+        assert_eq!(
+            request,
+            [
+                SOCKS_VERSION,
+                0x01,
+                0x00,
+                ADDRESS_IPV4,
+                10,
+                0,
+                0,
+                5,
+                0x63,
+                0xe7
+            ]
+        );
+    }
+}
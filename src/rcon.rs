@@ -0,0 +1,364 @@
+use minecraft_client_rs::message::{encode_message, Message, MessageType, HEADER_SIZE};
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+// https://wiki.vg/Rcon#Fragmentation
+const MAX_RESPONSE_SIZE: usize = 4110;
+
+/// Longest command payload the vanilla server accepts, longer ones fail or are cut off
+pub const MAX_REQUEST_PAYLOAD: usize = 1446;
+
+/// Whether a command is longer than `limit` bytes, returning its length when it is
+pub fn over_length_limit(cmd: &str, limit: usize) -> Option<usize> {
+    (cmd.len() > limit).then_some(cmd.len())
+}
+
+#[derive(Debug)]
+pub enum RconError {
+    Io(io::Error),
+    /// The server didn't answer within the read timeout
+    Timeout(Duration),
+    AuthenticationFailed,
+    Protocol(String),
+    /// The server closed or reset the connection, e.g. because it shut down
+    Closed,
+}
+
+impl std::error::Error for RconError {}
+
+impl RconError {
+    /// Whether the connection can't be used for further commands after this error. After a
+    /// timeout or a mismatched request id, a late response would be read as the answer to
+    /// the next command.
+    pub fn breaks_connection(&self) -> bool {
+        !matches!(self, RconError::AuthenticationFailed)
+    }
+}
+
+impl fmt::Display for RconError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RconError::Io(e) => write!(f, "{e}"),
+            RconError::Timeout(timeout) => {
+                write!(f, "No response within {} s", timeout.as_secs_f64())
+            }
+            RconError::AuthenticationFailed => write!(f, "Authentication failed"),
+            RconError::Protocol(msg) => write!(f, "Invalid response from server: {msg}"),
+            RconError::Closed => write!(f, "Connection closed by the server"),
+        }
+    }
+}
+
+impl From<io::Error> for RconError {
+    fn from(e: io::Error) -> RconError {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => RconError::Closed,
+            _ => RconError::Io(e),
+        }
+    }
+}
+
+/// Minimal RCON client that owns its socket, so timeouts can be applied to it
+pub struct RconClient {
+    conn: TcpStream,
+    last_id: i32,
+    read_timeout: Option<Duration>,
+}
+
+impl RconClient {
+    pub fn connect(addr: &str) -> Result<RconClient, RconError> {
+        Ok(RconClient::from_stream(TcpStream::connect(addr)?))
+    }
+
+    /// Speak RCON over an already open connection, e.g. one through a proxy
+    pub fn from_stream(conn: TcpStream) -> RconClient {
+        RconClient {
+            conn,
+            last_id: 0,
+            read_timeout: None,
+        }
+    }
+
+    /// Give up on a response after `timeout`, None waits forever
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<(), RconError> {
+        self.conn.set_read_timeout(timeout)?;
+        self.read_timeout = timeout;
+        Ok(())
+    }
+
+    pub fn authenticate(&mut self, password: &str) -> Result<(), RconError> {
+        match self.send_message(MessageType::Authenticate, password) {
+            Ok(_) => Ok(()),
+            // The server answers a wrong password with request id -1
+            Err(RconError::Protocol(_)) => Err(RconError::AuthenticationFailed),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Send a command and return the response body
+    pub fn send_command(&mut self, command: &str) -> Result<String, RconError> {
+        self.send_message(MessageType::Command, command)
+    }
+
+    fn send_message(&mut self, msg_type: MessageType, body: &str) -> Result<String, RconError> {
+        self.last_id += 1;
+        let request = Message {
+            size: body.len() as i32 + HEADER_SIZE,
+            id: self.last_id,
+            msg_type: msg_type as i32,
+            body: body.to_string(),
+        };
+        self.conn.write_all(&encode_message(request))?;
+        log::trace!("Sent request {}", self.last_id);
+        let (id, body) = self.read_message()?;
+        log::trace!("Received response {id} ({} bytes)", body.len());
+        if id != self.last_id {
+            return Err(RconError::Protocol(format!(
+                "expected request id {}, got {id}",
+                self.last_id
+            )));
+        }
+        Ok(body)
+    }
+
+    fn read_message(&mut self) -> Result<(i32, String), RconError> {
+        let mut size_bytes = [0u8; 4];
+        self.read_exact(&mut size_bytes)?;
+        let size = i32::from_le_bytes(size_bytes);
+        let size = usize::try_from(size)
+            .ok()
+            .filter(|size| (HEADER_SIZE as usize..=MAX_RESPONSE_SIZE).contains(size))
+            .ok_or_else(|| RconError::Protocol(format!("bad packet size {size}")))?;
+        let mut packet = vec![0u8; size];
+        self.read_exact(&mut packet)?;
+        let id = i32::from_le_bytes(packet[0..4].try_into().unwrap());
+        // Skip the type and drop the two trailing null bytes
+        let body = &packet[8..size - 2];
+        // Some plugins send bytes that aren't UTF-8, they are shown as U+FFFD
+        let body = String::from_utf8_lossy(body);
+        if let Cow::Owned(_) = body {
+            log::debug!("Response {id} isn't valid UTF-8");
+        }
+        Ok((id, body.into_owned()))
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), RconError> {
+        self.conn.read_exact(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                RconError::Timeout(self.read_timeout.unwrap_or_default())
+            }
+            _ => RconError::from(e),
+        })
+    }
+}
+
+/// How long to wait before connecting and authenticating again after `error`, or None
+/// to give up. A server that is still starting may drop the connection or reject the
+/// right password, which looks the same as a wrong one, so these are retried up to
+/// `retries` times. `attempt` counts from 0, and the wait doubles from 1 s up to 8 s.
+pub fn auth_retry_delay(error: &RconError, attempt: u32, retries: u32) -> Option<Duration> {
+    let transient = matches!(
+        error,
+        RconError::AuthenticationFailed | RconError::Closed | RconError::Timeout(_)
+    );
+    (transient && attempt < retries).then(|| Duration::from_secs(1 << attempt.min(3)))
+}
+
+/// Send with `send`, and once more if the first response is empty. Some servers answer the
+/// first commands after connecting with an empty body and only then with the real one.
+pub fn retry_empty<E>(mut send: impl FnMut() -> Result<String, E>) -> Result<String, E> {
+    match send()? {
+        body if body.is_empty() => {
+            log::debug!("Empty response, sending again");
+            send()
+        }
+        body => Ok(body),
+    }
+}
+
+/// Local servers to run clients against in tests, also built with the `test-server`
+/// feature for the binary's tests
+#[cfg(any(test, feature = "test-server"))]
+#[doc(hidden)]
+pub mod test_server {
+    use super::*;
+
+    use std::net::TcpListener;
+
+    /// A raw response packet, with a body that may not be UTF-8
+    pub fn packet(id: i32, body: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::new();
+        packet.extend((body.len() as i32 + HEADER_SIZE).to_le_bytes());
+        packet.extend(id.to_le_bytes());
+        packet.extend(0i32.to_le_bytes());
+        packet.extend(body);
+        packet.extend([0, 0]);
+        packet
+    }
+
+    /// A client connected to a server that writes what `answer` returns for the id and
+    /// body of each request, nothing at all for an empty answer
+    pub fn client_for(answer: impl Fn(i32, &str) -> Vec<u8> + Send + 'static) -> RconClient {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut size = [0u8; 4];
+            while conn.read_exact(&mut size).is_ok() {
+                let mut request = vec![0u8; i32::from_le_bytes(size) as usize];
+                conn.read_exact(&mut request).unwrap();
+                let id = i32::from_le_bytes(request[0..4].try_into().unwrap());
+                let body = String::from_utf8_lossy(&request[8..request.len() - 2]);
+                conn.write_all(&answer(id, &body)).unwrap();
+            }
+        });
+        RconClient::connect(&addr.to_string()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_server::{client_for, packet};
+    use super::*;
+
+    #[test]
+    fn gives_up_after_the_read_timeout() {
+        let mut client = client_for(|id, body| match body {
+            "/slow" => Vec::new(),
+            _ => packet(id, b"ok"),
+        });
+        client
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        assert_eq!(client.send_command("/list").unwrap(), "ok");
+        let e = client.send_command("/slow").unwrap_err();
+        assert!(matches!(e, RconError::Timeout(timeout) if timeout == Duration::from_millis(50)));
+        assert!(e.breaks_connection());
+    }
+
+    #[test]
+    fn authentication_fails_on_request_id_minus_one() {
+        let mut client = client_for(|id, body| match body {
+            "" => packet(id, b""),
+            _ => packet(-1, b""),
+        });
+        assert!(client.authenticate("").is_ok());
+        assert!(matches!(
+            client.authenticate("wrong"),
+            Err(RconError::AuthenticationFailed)
+        ));
+    }
+
+    #[test]
+    fn retries_an_empty_response_once() {
+        let mut responses = vec!["", "", "There are 0 players online"].into_iter();
+        let mut sent = 0;
+        let mut send = || -> Result<String, RconError> {
+            sent += 1;
+            Ok(responses.next().unwrap().to_string())
+        };
+        assert_eq!(retry_empty(&mut send).unwrap(), "");
+        assert_eq!(
+            retry_empty(&mut send).unwrap(),
+            "There are 0 players online"
+        );
+        assert_eq!(sent, 3);
+    }
+
+    #[test]
+    fn does_not_retry_a_response_or_an_error() {
+        let mut sent = 0;
+        let body = retry_empty(|| -> Result<String, RconError> {
+            sent += 1;
+            Ok("Seed: [1]".to_string())
+        });
+        assert_eq!((body.unwrap().as_str(), sent), ("Seed: [1]", 1));
+        let mut sent = 0;
+        let failed = retry_empty(|| {
+            sent += 1;
+            Err(RconError::Closed)
+        });
+        assert!(failed.is_err());
+        assert_eq!(sent, 1);
+    }
+
+    #[test]
+    fn length_limit_allows_exactly_the_limit() {
+        let at_limit = "a".repeat(MAX_REQUEST_PAYLOAD);
+        assert_eq!(over_length_limit(&at_limit, MAX_REQUEST_PAYLOAD), None);
+        let over = "a".repeat(MAX_REQUEST_PAYLOAD + 1);
+        assert_eq!(
+            over_length_limit(&over, MAX_REQUEST_PAYLOAD),
+            Some(MAX_REQUEST_PAYLOAD + 1)
+        );
+        // Bytes count, not characters
+        assert_eq!(over_length_limit("§§", 3), Some(4));
+    }
+
+    #[test]
+    fn a_closed_connection_is_reported_as_closed() {
+        for kind in [io::ErrorKind::UnexpectedEof, io::ErrorKind::ConnectionReset] {
+            assert!(matches!(
+                RconError::from(io::Error::from(kind)),
+                RconError::Closed
+            ));
+        }
+        assert!(matches!(
+            RconError::from(io::Error::from(io::ErrorKind::PermissionDenied)),
+            RconError::Io(_)
+        ));
+    }
+
+    #[test]
+    fn a_server_shutting_down_closes_the_connection() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            // Answer one request, then hang up like a stopped server
+            let (mut conn, _) = listener.accept().unwrap();
+            let mut request = [0u8; 19];
+            conn.read_exact(&mut request).unwrap();
+            let id = i32::from_le_bytes(request[4..8].try_into().unwrap());
+            conn.write_all(&packet(id, b"Stopping the server")).unwrap();
+        });
+        let mut client = RconClient::connect(&addr.to_string()).unwrap();
+        assert_eq!(client.send_command("/stop").unwrap(), "Stopping the server");
+        let e = client.send_command("/list").unwrap_err();
+        assert!(matches!(e, RconError::Closed), "{e:?}");
+        assert_eq!(e.to_string(), "Connection closed by the server");
+    }
+
+    #[test]
+    fn decodes_invalid_utf8_lossily() {
+        let mut client = client_for(|id, _| packet(id, b"caf\xe9 \xff ok"));
+        assert_eq!(
+            client.send_command("/list").unwrap(),
+            "caf\u{fffd} \u{fffd} ok"
+        );
+    }
+
+    #[test]
+    fn auth_retries_back_off_up_to_the_limit() {
+        let delays: Vec<Option<Duration>> = (0..6)
+            .map(|attempt| auth_retry_delay(&RconError::AuthenticationFailed, attempt, 5))
+            .collect();
+        let secs = |secs| Some(Duration::from_secs(secs));
+        assert_eq!(delays, [secs(1), secs(2), secs(4), secs(8), secs(8), None]);
+        assert_eq!(auth_retry_delay(&RconError::Closed, 0, 0), None);
+    }
+
+    #[test]
+    fn only_transient_failures_are_retried() {
+        let protocol = RconError::Protocol("bad packet".to_string());
+        assert_eq!(auth_retry_delay(&protocol, 0, 3), None);
+        let refused = RconError::from(io::Error::from(io::ErrorKind::ConnectionRefused));
+        assert_eq!(auth_retry_delay(&refused, 0, 3), None);
+        assert!(auth_retry_delay(&RconError::Timeout(Duration::from_secs(5)), 0, 3).is_some());
+    }
+}
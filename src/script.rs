@@ -0,0 +1,311 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Substitute `${NAME}` placeholders using `lookup`, with `$$` producing a literal `$`
+pub fn substitute(line: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut substituted = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(idx) = rest.find('$') {
+        substituted.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            substituted.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| anyhow!("unterminated variable placeholder in `{line}`"))?;
+            let name = &after[..end];
+            let value = lookup(name).ok_or_else(|| anyhow!("unresolved variable ${{{name}}}"))?;
+            substituted.push_str(&value);
+            rest = &after[end + 1..];
+        } else {
+            substituted.push('$');
+            rest = &rest[1..];
+        }
+    }
+    substituted.push_str(rest);
+    Ok(substituted)
+}
+
+/// Expand `$NAME` and `${NAME}` using `lookup` for --expand-env, with `$$` producing a
+/// literal `$`. A `$` not followed by a name is kept as it is.
+pub fn expand_env(line: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(idx) = rest.find('$') {
+        expanded.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+        let (name, after) = if let Some(after) = rest.strip_prefix('$') {
+            expanded.push('$');
+            rest = after;
+            continue;
+        } else if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| anyhow!("unterminated variable in `{line}`"))?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+            rest.split_at(end)
+        };
+        if name.is_empty() {
+            expanded.push('$');
+            continue;
+        }
+        let value = lookup(name).ok_or_else(|| anyhow!("unresolved variable ${name}"))?;
+        expanded.push_str(&value);
+        rest = after;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+// Guards against runaway nesting that isn't a cycle, e.g. generated include chains
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Read a command file, skipping blank lines and `#` comments, and substitute variables.
+/// `@include <path>` lines inline another file, relative to the including file's directory.
+/// Every line is resolved before returning so nothing is sent when a variable is missing.
+pub fn load_command_file(
+    path: &Path,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<String>> {
+    let mut commands = Vec::new();
+    load_into(path, &lookup, &mut Vec::new(), &mut commands)?;
+    Ok(commands)
+}
+
+fn load_into(
+    path: &Path,
+    lookup: &impl Fn(&str) -> Option<String>,
+    stack: &mut Vec<PathBuf>,
+    commands: &mut Vec<String>,
+) -> Result<()> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("Failed to read command file {}", path.display()))?;
+    if stack.contains(&canonical) {
+        let chain: Vec<String> = stack
+            .iter()
+            .chain([&canonical])
+            .map(|p| p.display().to_string())
+            .collect();
+        bail!("Include cycle detected: {}", chain.join(" -> "));
+    }
+    if stack.len() >= MAX_INCLUDE_DEPTH {
+        bail!(
+            "{}: includes nested deeper than {MAX_INCLUDE_DEPTH} levels",
+            path.display()
+        );
+    }
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read command file {}", path.display()))?;
+    stack.push(canonical);
+    for (idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = match substitute(line, lookup) {
+            Ok(line) => line,
+            Err(e) => bail!("{}:{}: {e}", path.display(), idx + 1),
+        };
+        if let Some(include) = line.strip_prefix("@include ") {
+            let base = path.parent().unwrap_or(Path::new("."));
+            load_into(&base.join(include.trim()), lookup, stack, commands)?;
+        } else {
+            commands.push(line);
+        }
+    }
+    stack.pop();
+    Ok(())
+}
+
+/// Read a whole file as a single command, for commands too long to paste. Only the
+/// trailing newline is dropped.
+pub fn load_single_command(path: &Path) -> Result<String> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let cmd = contents
+        .strip_suffix('\n')
+        .map(|cmd| cmd.strip_suffix('\r').unwrap_or(cmd))
+        .unwrap_or(&contents);
+    if cmd.trim().is_empty() {
+        bail!("{} is empty", path.display());
+    }
+    Ok(cmd.to_string())
+}
+
+/// Parse a `NAME=VALUE` command line variable
+pub fn parse_var(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got `{s}`")),
+    }
+}
+
+/// Counts the outcomes of a batch of commands for the summary printed after it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub ok: usize,
+    pub errors: usize,
+}
+
+impl BatchSummary {
+    /// Count a command, which failed when it couldn't be sent or got an error response
+    pub fn record(&mut self, failed: bool) {
+        match failed {
+            true => self.errors += 1,
+            false => self.ok += 1,
+        }
+    }
+}
+
+impl std::fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let total = self.ok + self.errors;
+        let commands = match total {
+            1 => "command",
+            _ => "commands",
+        };
+        let errors = match self.errors {
+            1 => "error",
+            _ => "errors",
+        };
+        write!(
+            f,
+            "Executed {total} {commands}: {} ok, {} {errors}",
+            self.ok, self.errors
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for a test's files
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mc-rcon-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn vars(name: &str) -> Option<String> {
+        match name {
+            "PLAYER" => Some("Steve".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        assert_eq!(
+            substitute("/give ${PLAYER} diamond", vars).unwrap(),
+            "/give Steve diamond"
+        );
+        assert_eq!(
+            substitute("/say $$5 for $PLAYER", vars).unwrap(),
+            "/say $5 for $PLAYER"
+        );
+        let e = substitute("/kick ${NOBODY}", vars).unwrap_err();
+        assert_eq!(e.to_string(), "unresolved variable ${NOBODY}");
+        assert!(substitute("/kick ${PLAYER", vars).is_err());
+    }
+
+    #[test]
+    fn loads_command_files_without_comments() {
+        let dir = test_dir("command-file");
+        let path = dir.join("commands.txt");
+        std::fs::write(
+            &path,
+            "# setup\n/time set day\n\n  /give ${PLAYER} stone  \n",
+        )
+        .unwrap();
+        assert_eq!(
+            load_command_file(&path, vars).unwrap(),
+            ["/time set day", "/give Steve stone"]
+        );
+        std::fs::write(&path, "/time set day\n/kick ${NOBODY}\n").unwrap();
+        let e = load_command_file(&path, vars).unwrap_err();
+        assert!(e
+            .to_string()
+            .ends_with("commands.txt:2: unresolved variable ${NOBODY}"));
+    }
+
+    #[test]
+    fn parses_variables() {
+        assert_eq!(parse_var("A=b=c"), Ok(("A".to_string(), "b=c".to_string())));
+        assert!(parse_var("=b").is_err());
+        assert!(parse_var("A").is_err());
+    }
+
+    #[test]
+    fn inlines_includes_relative_to_the_including_file() {
+        let dir = test_dir("include");
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(
+            dir.join("main.txt"),
+            "/say start\n@include lib/setup.txt\n/say end\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("lib/setup.txt"), "/time set day\n").unwrap();
+        assert_eq!(
+            load_command_file(&dir.join("main.txt"), vars).unwrap(),
+            ["/say start", "/time set day", "/say end"]
+        );
+    }
+
+    #[test]
+    fn rejects_include_cycles() {
+        let dir = test_dir("include-cycle");
+        std::fs::write(dir.join("a.txt"), "@include b.txt\n").unwrap();
+        std::fs::write(dir.join("b.txt"), "@include a.txt\n").unwrap();
+        let e = load_command_file(&dir.join("a.txt"), vars).unwrap_err();
+        assert!(e.to_string().starts_with("Include cycle detected: "));
+    }
+
+    #[test]
+    fn reads_a_whole_file_as_one_command() {
+        let dir = test_dir("send-file");
+        let path = dir.join("tellraw.txt");
+        std::fs::write(&path, "/tellraw @a {\"text\":\"a\nb\"}\r\n").unwrap();
+        assert_eq!(
+            load_single_command(&path).unwrap(),
+            "/tellraw @a {\"text\":\"a\nb\"}"
+        );
+        std::fs::write(&path, "\n\n").unwrap();
+        assert!(load_single_command(&path).is_err());
+        assert!(load_single_command(&dir.join("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn expands_environment_variables() {
+        assert_eq!(
+            expand_env("/give $PLAYER diamond, ${PLAYER}_alt", vars).unwrap(),
+            "/give Steve diamond, Steve_alt"
+        );
+        assert_eq!(
+            expand_env("/say $$PLAYER costs $ 5 $", vars).unwrap(),
+            "/say $PLAYER costs $ 5 $"
+        );
+        let e = expand_env("/kick $NOBODY", vars).unwrap_err();
+        assert_eq!(e.to_string(), "unresolved variable $NOBODY");
+        assert!(expand_env("/kick ${PLAYER", vars).is_err());
+    }
+
+    #[test]
+    fn summarizes_a_mixed_batch() {
+        let mut summary = BatchSummary::default();
+        for failed in [false, true, false, false, true] {
+            summary.record(failed);
+        }
+        assert_eq!(summary.to_string(), "Executed 5 commands: 3 ok, 2 errors");
+        let mut summary = BatchSummary::default();
+        summary.record(true);
+        assert_eq!(summary.to_string(), "Executed 1 command: 0 ok, 1 error");
+    }
+}
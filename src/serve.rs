@@ -0,0 +1,188 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::connection;
+use crate::rcon::RconClient;
+
+/// Opens a new RCON connection when the old one can't be used anymore
+pub type Reconnect = dyn Fn() -> Result<RconClient> + Send + Sync;
+
+// JSON-RPC 2.0 error codes, with -32000 for failures of the RCON command itself
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const COMMAND_FAILED: i64 = -32000;
+
+#[derive(Debug, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> RpcError {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A validated request. Only the `command` method exists for now.
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub id: Value,
+    pub cmd: String,
+}
+
+/// Validate one request line, like `{"id":1,"method":"command","params":{"cmd":"/list"}}`.
+/// On failure the request id is returned too when it could be read.
+pub fn parse_request(line: &str) -> Result<Request, (Value, RpcError)> {
+    let value: Value = serde_json::from_str(line)
+        .map_err(|e| (Value::Null, RpcError::new(PARSE_ERROR, e.to_string())))?;
+    let Value::Object(request) = value else {
+        return Err((
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "request must be an object"),
+        ));
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let fail = |code, message: &str| Err((id.clone(), RpcError::new(code, message)));
+    match request.get("method") {
+        Some(Value::String(method)) if method == "command" => {}
+        Some(Value::String(method)) => {
+            return fail(METHOD_NOT_FOUND, &format!("unknown method '{method}'"))
+        }
+        _ => return fail(INVALID_REQUEST, "method must be a string"),
+    }
+    match request
+        .get("params")
+        .and_then(|params| params.get("cmd"))
+        .and_then(Value::as_str)
+    {
+        Some(cmd) if !cmd.trim().is_empty() => Ok(Request {
+            id,
+            cmd: cmd.trim().to_string(),
+        }),
+        _ => fail(INVALID_PARAMS, "params.cmd must be a non-empty string"),
+    }
+}
+
+/// Serialize a response line, `result.body` on success and `error` otherwise
+pub fn format_response(id: &Value, result: Result<String, RpcError>) -> String {
+    let response = match result {
+        Ok(body) => json!({ "jsonrpc": "2.0", "id": id, "result": { "body": body } }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    };
+    response.to_string()
+}
+
+fn handle_line(client: &Mutex<RconClient>, reconnect: &Reconnect, line: &str) -> String {
+    match parse_request(line) {
+        Ok(request) => {
+            let mut client = client.lock().unwrap();
+            let result = connection::send_or_reconnect(&mut client, &request.cmd, reconnect)
+                .map_err(|e| RpcError::new(COMMAND_FAILED, e.to_string()));
+            format_response(&request.id, result)
+        }
+        Err((id, e)) => format_response(&id, Err(e)),
+    }
+}
+
+fn handle_connection(
+    client: &Mutex<RconClient>,
+    reconnect: &Reconnect,
+    stream: TcpStream,
+) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}", handle_line(client, reconnect, &line))?;
+    }
+    Ok(())
+}
+
+/// Accept newline delimited JSON requests on `addr`. Every connection gets its own thread
+/// and they take turns on the RCON connection. Requests aren't authenticated, so only
+/// loopback addresses are accepted unless `allow_remote` is set.
+pub fn run(
+    addr: &str,
+    allow_remote: bool,
+    client: RconClient,
+    reconnect: Box<Reconnect>,
+) -> Result<()> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("Failed to listen on {addr}"))?;
+    let local_addr = listener.local_addr()?;
+    if !local_addr.ip().is_loopback() && !allow_remote {
+        anyhow::bail!(
+            "--serve {addr} would accept commands from other hosts without a password, \
+             listen on a loopback address or pass --serve-allow-remote"
+        );
+    }
+    println!("Serving JSON requests on {local_addr}");
+    let client = Arc::new(Mutex::new(client));
+    let reconnect: Arc<Reconnect> = Arc::from(reconnect);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                continue;
+            }
+        };
+        let client = Arc::clone(&client);
+        let reconnect = Arc::clone(&reconnect);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(&client, &*reconnect, stream) {
+                eprintln!("Error: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_command_requests() {
+        let request = parse_request(r#"{"id":1,"method":"command","params":{"cmd":" /list "}}"#);
+        assert_eq!(
+            request,
+            Ok(Request {
+                id: json!(1),
+                cmd: "/list".to_string()
+            })
+        );
+        let (id, e) = parse_request(r#"{"id":"a","method":"stop"}"#).unwrap_err();
+        assert_eq!((id, e.code), (json!("a"), METHOD_NOT_FOUND));
+        let (id, e) = parse_request("{").unwrap_err();
+        assert_eq!((id, e.code), (Value::Null, PARSE_ERROR));
+    }
+
+    #[test]
+    fn formats_results_and_errors() {
+        assert_eq!(
+            format_response(&json!(1), Ok("There are 0 players".to_string())),
+            r#"{"id":1,"jsonrpc":"2.0","result":{"body":"There are 0 players"}}"#
+        );
+        let error = RpcError::new(COMMAND_FAILED, "Connection closed by the server");
+        let response: Value =
+            serde_json::from_str(&format_response(&json!(2), Err(error))).unwrap();
+        assert_eq!(response["error"]["code"], COMMAND_FAILED);
+    }
+}
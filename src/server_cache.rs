@@ -0,0 +1,268 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// Server state learned from command responses, used for completion
+#[derive(Debug, Default)]
+pub struct ServerCache {
+    pub players: Vec<String>,
+    /// Player UUIDs by name, from `/list uuids`
+    pub uuids: BTreeMap<String, String>,
+    pub banned_ips: Vec<String>,
+    /// Data packs from `/datapack list`, quoted where the server requires it
+    pub available_datapacks: Vec<String>,
+    pub enabled_datapacks: Vec<String>,
+    /// Candidates by argument name from --completion-spec, used instead of the bundled ones
+    pub overrides: BTreeMap<String, Vec<String>>,
+}
+
+impl ServerCache {
+    /// Update the cache from the response of a command that lists server state
+    pub fn observe(&mut self, cmd: &str, body: &str, command_char: &str) {
+        let cmd = cmd.strip_prefix(command_char).unwrap_or(cmd);
+        let words: Vec<&str> = cmd.split_whitespace().collect();
+        match words.as_slice() {
+            ["list"] => {
+                if let Some(players) = parse_player_list(body) {
+                    self.players = players;
+                }
+            }
+            ["list", "uuids"] => {
+                if let Some(entries) = parse_player_uuids(body) {
+                    self.players = entries.iter().map(|(name, _)| name.clone()).collect();
+                    self.uuids.extend(entries);
+                }
+            }
+            ["banlist", "ips"] => self.banned_ips = parse_banned_ips(body),
+            ["datapack", "list", ..] => {
+                let (available, enabled) = parse_datapack_list(body);
+                if let Some(available) = available {
+                    self.available_datapacks = available;
+                }
+                if let Some(enabled) = enabled {
+                    self.enabled_datapacks = enabled;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The UUID seen for a player, whose name may be typed in any case like the server
+    /// allows
+    pub fn uuid(&self, name: &str) -> Option<&str> {
+        self.uuids
+            .iter()
+            .find(|(player, _)| player.eq_ignore_ascii_case(name))
+            .map(|(_, uuid)| uuid.as_str())
+    }
+
+    /// Dynamic candidates for an argument, derived from its name like the static ones
+    pub fn candidates(&self, name: &str) -> Vec<String> {
+        match name {
+            "target" | "targets" | "player" | "source" | "destination" | "name_or_ip" => {
+                self.players.clone()
+            }
+            "ip" => self.banned_ips.clone(),
+            "uuid" => self.uuids.values().cloned().collect(),
+            "available_pack" => self.available_datapacks.clone(),
+            "enabled_pack" => self.enabled_datapacks.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Parse `There are 2 of a max of 20 players online: Steve, Alex`
+pub fn parse_player_list(body: &str) -> Option<Vec<String>> {
+    let (_, names) = body.split_once(':')?;
+    Some(
+        names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Parse `/list uuids`, which reads
+/// `There are 1 of a max of 20 players online: Steve (069a79f4-44e9-4726-a5be-fca90e38aaf5)`
+pub fn parse_player_uuids(body: &str) -> Option<Vec<(String, String)>> {
+    let (_, entries) = body.split_once(':')?;
+    let re_entry = Regex::new(r"([^\s,()]+) \(([0-9a-fA-F-]{36})\)").unwrap();
+    Some(
+        re_entry
+            .captures_iter(entries)
+            .map(|cap| (cap[1].to_string(), cap[2].to_string()))
+            .collect(),
+    )
+}
+
+/// The `/list uuids` header followed by a `name  uuid` line per player, with the UUIDs
+/// aligned
+pub fn format_player_uuids(body: &str, entries: &[(String, String)]) -> String {
+    let header = body.split_once(':').map_or(body, |(header, _)| header);
+    let width = entries
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec![format!("{header}:")];
+    lines.extend(
+        entries
+            .iter()
+            .map(|(name, uuid)| format!("  {name:width$}  {uuid}")),
+    );
+    lines.join("\n")
+}
+
+/// Extract the addresses from `/banlist ips`, whose entries read
+/// `1.2.3.4 was banned by Server: reason` and may be run together
+pub fn parse_banned_ips(body: &str) -> Vec<String> {
+    let re_entry = Regex::new(r"(\d{1,3}(?:\.\d{1,3}){3}) was banned by").unwrap();
+    re_entry
+        .captures_iter(body)
+        .map(|cap| cap[1].to_string())
+        .collect()
+}
+
+/// Extract the available and enabled packs from `/datapack list`, which reads
+/// `There are 2 data pack(s) enabled: [vanilla (built-in)], [file/castle (world)]` and the
+/// same for `available`, or `There are no more data packs available`. Each list is None
+/// when the response doesn't mention it, as with `/datapack list enabled`.
+pub fn parse_datapack_list(body: &str) -> (Option<Vec<String>>, Option<Vec<String>>) {
+    let re_section = Regex::new(
+        r"There are (?:\d+ data pack\(s\)|no (?:more )?data packs) (available|enabled)(?::\s*((?:\[[^\]]*\](?:,\s*)?)*))?",
+    )
+    .unwrap();
+    let re_entry = Regex::new(r"\[([^\]]*?)(?:\s*\([^()]*\))?\]").unwrap();
+    let mut available = None;
+    let mut enabled = None;
+    for cap in re_section.captures_iter(body) {
+        let packs = cap.get(2).map_or_else(Vec::new, |entries| {
+            re_entry
+                .captures_iter(entries.as_str())
+                .map(|entry| quote_pack_name(&entry[1]))
+                .collect()
+        });
+        match &cap[1] {
+            "available" => available = Some(packs),
+            _ => enabled = Some(packs),
+        }
+    }
+    (available, enabled)
+}
+
+/// Pack names like `file/castle.zip` have characters the command only accepts in quotes
+fn quote_pack_name(name: &str) -> String {
+    let unquoted = |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+');
+    match name.chars().all(unquoted) {
+        true => name.to_string(),
+        false => format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\"")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observes_commands_sent_with_another_command_char() {
+        let mut cache = ServerCache::default();
+        cache.observe(
+            "!list",
+            "There are 2 of a max of 20 players online: Steve, Alex",
+            "!",
+        );
+        assert_eq!(cache.players, ["Steve", "Alex"]);
+        cache.observe("list", "There are 1 of a max of 20 players online: Bob", "");
+        assert_eq!(cache.players, ["Bob"]);
+    }
+
+    #[test]
+    fn learns_banned_ips_run_together() {
+        let body = "There are 2 ban(s):1.2.3.4 was banned by Server: spam10.0.0.7 was banned by \
+Steve: griefing";
+        let mut cache = ServerCache::default();
+        cache.observe("/banlist ips", body, "/");
+        assert_eq!(cache.banned_ips, ["1.2.3.4", "10.0.0.7"]);
+        assert_eq!(cache.candidates("ip"), ["1.2.3.4", "10.0.0.7"]);
+    }
+
+    #[test]
+    fn parses_available_and_enabled_datapacks() {
+        let body = "There are 2 data pack(s) enabled: [vanilla (built-in)], [file/castle.zip \
+(world)]There are 1 data pack(s) available: [bundle (feature)]";
+        assert_eq!(
+            parse_datapack_list(body),
+            (
+                Some(vec!["bundle".to_string()]),
+                Some(vec![
+                    "vanilla".to_string(),
+                    "\"file/castle.zip\"".to_string()
+                ])
+            )
+        );
+        assert_eq!(
+            parse_datapack_list("There are no more data packs available"),
+            (Some(Vec::new()), None)
+        );
+        assert_eq!(parse_datapack_list("Unknown command"), (None, None));
+    }
+
+    #[test]
+    fn keeps_the_list_a_response_leaves_out() {
+        let mut cache = ServerCache::default();
+        cache.observe(
+            "/datapack list",
+            "There are 1 data pack(s) enabled: [vanilla (built-in)]\
+There are 1 data pack(s) available: [bundle (feature)]",
+            "/",
+        );
+        cache.observe(
+            "/datapack list enabled",
+            "There are 0 data pack(s) enabled: ",
+            "/",
+        );
+        assert_eq!(cache.available_datapacks, ["bundle"]);
+        assert!(cache.enabled_datapacks.is_empty());
+    }
+
+    const UUIDS: &str = "There are 2 of a max of 20 players online: \
+Steve (069a79f4-44e9-4726-a5be-fca90e38aaf5), Alex_2 (61699b2e-d327-4a01-9f1e-0ea8c3f06bc6)";
+
+    #[test]
+    fn parses_and_formats_player_uuids() {
+        let entries = parse_player_uuids(UUIDS).unwrap();
+        assert_eq!(
+            entries,
+            [
+                (
+                    "Steve".to_string(),
+                    "069a79f4-44e9-4726-a5be-fca90e38aaf5".to_string()
+                ),
+                (
+                    "Alex_2".to_string(),
+                    "61699b2e-d327-4a01-9f1e-0ea8c3f06bc6".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            format_player_uuids(UUIDS, &entries),
+            "There are 2 of a max of 20 players online:\n  \
+Steve   069a79f4-44e9-4726-a5be-fca90e38aaf5\n  \
+Alex_2  61699b2e-d327-4a01-9f1e-0ea8c3f06bc6"
+        );
+        assert_eq!(parse_player_uuids("No players"), None);
+    }
+
+    #[test]
+    fn looks_up_uuids_in_any_case() {
+        let mut cache = ServerCache::default();
+        cache.observe("/list uuids", UUIDS, "/");
+        assert_eq!(
+            cache.uuid("steve"),
+            Some("069a79f4-44e9-4726-a5be-fca90e38aaf5")
+        );
+        assert_eq!(cache.uuid("Herobrine"), None);
+        assert_eq!(cache.players, ["Steve", "Alex_2"]);
+    }
+}
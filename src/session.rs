@@ -0,0 +1,105 @@
+use crate::rcon::{RconClient, RconError};
+
+type CommandCallback = Box<dyn FnMut(&str, Result<&str, &RconError>)>;
+
+/// An RCON connection for embedding the CLI's logic, with hooks that observe every
+/// command and its response, e.g. for logging or metrics
+pub struct Session {
+    client: RconClient,
+    callbacks: Vec<CommandCallback>,
+}
+
+impl Session {
+    pub fn new(client: RconClient) -> Session {
+        Session {
+            client,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Call `callback` with each command and its response body or error, after it was
+    /// sent. Callbacks run in the order they were added.
+    pub fn on_command(
+        mut self,
+        callback: impl FnMut(&str, Result<&str, &RconError>) + 'static,
+    ) -> Session {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    pub fn send_command(&mut self, cmd: &str) -> Result<String, RconError> {
+        let result = self.client.send_command(cmd);
+        for callback in &mut self.callbacks {
+            callback(cmd, result.as_deref());
+        }
+        result
+    }
+
+    /// The underlying client, e.g. to change its timeout. Commands sent through it
+    /// directly don't reach the callbacks.
+    pub fn client_mut(&mut self) -> &mut RconClient {
+        &mut self.client
+    }
+
+    /// Swap in a new connection, keeping the callbacks, e.g. after reconnecting
+    pub fn replace_client(&mut self, client: RconClient) -> RconClient {
+        std::mem::replace(&mut self.client, client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rcon::test_server::{client_for, packet};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn callbacks_see_every_command_in_order() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut session = Session::new(client_for(|id, cmd| {
+            packet(id, format!("echo:{cmd}").as_bytes())
+        }))
+        .on_command({
+            let seen = Rc::clone(&seen);
+            move |cmd, response| {
+                seen.borrow_mut()
+                    .push(format!("{cmd} -> {}", response.unwrap()))
+            }
+        })
+        .on_command({
+            let seen = Rc::clone(&seen);
+            move |cmd, _| seen.borrow_mut().push(format!("second {cmd}"))
+        });
+        assert_eq!(session.send_command("/list").unwrap(), "echo:/list");
+        session.send_command("/seed").unwrap();
+        assert_eq!(
+            *seen.borrow(),
+            [
+                "/list -> echo:/list",
+                "second /list",
+                "/seed -> echo:/seed",
+                "second /seed"
+            ]
+        );
+    }
+
+    #[test]
+    fn callbacks_survive_replacing_the_client() {
+        let count = Rc::new(RefCell::new(0));
+        let mut session = Session::new(client_for(|id, cmd| {
+            packet(id, format!("echo:{cmd}").as_bytes())
+        }))
+        .on_command({
+            let count = Rc::clone(&count);
+            move |_, _| *count.borrow_mut() += 1
+        });
+        session.send_command("/list").unwrap();
+        session.replace_client(client_for(|id, cmd| {
+            packet(id, format!("echo:{cmd}").as_bytes())
+        }));
+        session.send_command("/list").unwrap();
+        session.client_mut().send_command("/list").unwrap();
+        assert_eq!(*count.borrow(), 2);
+    }
+}
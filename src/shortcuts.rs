@@ -0,0 +1,162 @@
+use regex::Regex;
+
+use crate::edition::Edition;
+
+/// A meta-command that runs a status query and condenses its response
+#[derive(Debug, PartialEq)]
+pub struct Shortcut {
+    pub name: &'static str,
+    /// The query, written with `/` like the bundled usages
+    command: &'static str,
+    pub label: &'static str,
+    /// Extracts the value from the response, the first capture group is shown
+    pattern: &'static str,
+    /// Only Bedrock servers know the query
+    bedrock_only: bool,
+}
+
+pub const SHORTCUTS: &[Shortcut] = &[
+    Shortcut {
+        name: "difficulty",
+        command: "/difficulty",
+        label: "Difficulty",
+        pattern: r"(?i)difficulty is (\w+)",
+        bedrock_only: false,
+    },
+    Shortcut {
+        name: "weather",
+        command: "/weather query",
+        label: "Weather",
+        pattern: r"(?i)weather state is:?\s*(\w+)",
+        bedrock_only: true,
+    },
+    Shortcut {
+        name: "time",
+        command: "/time query daytime",
+        label: "Time",
+        pattern: r"(?i)time is (\d+)",
+        bedrock_only: false,
+    },
+    Shortcut {
+        name: "seed",
+        command: "/seed",
+        label: "Seed",
+        pattern: r"Seed: \[?(-?\d+)\]?",
+        bedrock_only: false,
+    },
+];
+
+pub fn find(name: &str) -> Option<&'static Shortcut> {
+    SHORTCUTS.iter().find(|shortcut| shortcut.name == name)
+}
+
+impl Shortcut {
+    /// The query to send, with the server's command prefix
+    pub fn command(&self, command_char: &str) -> String {
+        let command = self.command.strip_prefix('/').unwrap_or(self.command);
+        format!("{command_char}{command}")
+    }
+
+    /// Whether servers of `edition` can answer the query. Java has no `/weather query`.
+    pub fn supports(&self, edition: Edition) -> bool {
+        !self.bedrock_only || edition == Edition::Bedrock
+    }
+
+    /// One line summary of the response, None when it isn't in the expected shape
+    pub fn summarize(&self, body: &str) -> Option<String> {
+        let re = Regex::new(self.pattern).unwrap();
+        let value = re.captures(body)?.get(1)?.as_str();
+        Some(format!("{}: {value}", self.label))
+    }
+}
+
+/// The query `:where` runs, the entity's position as a list of doubles
+pub fn where_command(selector: &str, command_char: &str) -> String {
+    format!("{command_char}data get entity {selector} Pos")
+}
+
+/// The coordinates in a `Pos` response like
+/// `Steve has the following entity data: [12.5d, 64.0d, -3.25d]`
+pub fn parse_position(body: &str) -> Option<[f64; 3]> {
+    let start = body.rfind('[')?;
+    let end = start + body[start..].find(']')?;
+    let values: Vec<f64> = body[start + 1..end]
+        .split(',')
+        .map(|value| value.trim().trim_end_matches(['d', 'D']).parse().ok())
+        .collect::<Option<_>>()?;
+    values.try_into().ok()
+}
+
+/// `Position: x 12.50, y 64.00, z -3.25 (block 12 64 -4)`, the block being what `~ ~ ~`
+/// refers to
+pub fn format_position([x, y, z]: [f64; 3]) -> String {
+    format!(
+        "Position: x {x:.2}, y {y:.2}, z {z:.2} (block {} {} {})",
+        x.floor(),
+        y.floor(),
+        z.floor()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_use_the_command_char() {
+        assert_eq!(find("difficulty").unwrap().command("!"), "!difficulty");
+        assert_eq!(find("seed").unwrap().command("/"), "/seed");
+        assert_eq!(where_command("@p", "!"), "!data get entity @p Pos");
+    }
+
+    #[test]
+    fn weather_needs_bedrock() {
+        let weather = find("weather").unwrap();
+        assert!(!weather.supports(Edition::Java));
+        assert!(weather.supports(Edition::Bedrock));
+        assert!(find("seed").unwrap().supports(Edition::Java));
+    }
+
+    #[test]
+    fn summarizes_query_responses() {
+        let summarize = |name, body| find(name).unwrap().summarize(body);
+        assert_eq!(
+            summarize("difficulty", "The difficulty is Normal"),
+            Some("Difficulty: Normal".to_string())
+        );
+        assert_eq!(
+            summarize("seed", "Seed: [-4172144997902289642]"),
+            Some("Seed: -4172144997902289642".to_string())
+        );
+        assert_eq!(
+            summarize("time", "The time is 6000"),
+            Some("Time: 6000".to_string())
+        );
+        assert_eq!(summarize("time", "Unknown command"), None);
+    }
+
+    #[test]
+    fn parses_pos_responses() {
+        assert_eq!(
+            parse_position("Steve has the following entity data: [12.5d, 64.0d, -3.25d]"),
+            Some([12.5, 64.0, -3.25])
+        );
+        assert_eq!(
+            parse_position("[Steve] has the following entity data: [1d, 2d, 3d]"),
+            Some([1.0, 2.0, 3.0])
+        );
+        assert_eq!(parse_position("No entity was found"), None);
+        assert_eq!(
+            parse_position("Steve has the following entity data: [1d, 2d]"),
+            None
+        );
+    }
+
+    #[test]
+    fn formats_positions_with_their_block() {
+        assert_eq!(
+            format_position([12.5, 64.0, -3.25]),
+            "Position: x 12.50, y 64.00, z -3.25 (block 12 64 -4)"
+        );
+    }
+}
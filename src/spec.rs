@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::{help_parser, Argument};
+
+/// Subcommand trees for `/data`, `/loot` and other commands with nested grammars
+const BUNDLED_GRAMMAR: &str = include_str!("../data/grammar.toml");
+
+/// Completion data for a server, from `--completion-spec`. In TOML:
+///
+/// ```toml
+/// # Candidates by argument name, used instead of the bundled ones
+/// [arguments]
+/// item = ["mymod:ruby", "mymod:sapphire"]
+///
+/// # Usage in the /help format by command path, replacing what /help says
+/// [commands]
+/// "/spell" = "(cast|list)"
+/// "/spell cast" = "<targets> <spell> [<power>]"
+/// ```
+///
+/// A `.json` file holds the same two objects.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompletionSpec {
+    pub arguments: BTreeMap<String, Vec<String>>,
+    pub commands: BTreeMap<String, String>,
+}
+
+impl CompletionSpec {
+    pub fn load(path: &Path) -> Result<CompletionSpec> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let invalid = || format!("Invalid completion spec {}", path.display());
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&contents).with_context(invalid),
+            _ => toml::from_str(&contents).with_context(invalid),
+        }
+    }
+
+    /// The bundled grammar, applied before `--completion-spec` so that can override it
+    pub fn bundled() -> CompletionSpec {
+        toml::from_str(BUNDLED_GRAMMAR).expect("bundled grammar is valid")
+    }
+
+    /// Add the spec's commands and subcommands to the parsed ones, replacing those with
+    /// the same path. Paths are written with `/` and use `command_char` once applied.
+    pub fn apply(&self, commands: &mut HashMap<String, Vec<Argument>>, command_char: &str) {
+        for (path, usage) in &self.commands {
+            commands.insert(
+                with_command_char(path, command_char),
+                help_parser::parse_arguments(usage),
+            );
+        }
+    }
+
+    /// Like `apply`, but only for the commands the server has
+    pub fn apply_known(&self, commands: &mut HashMap<String, Vec<Argument>>, command_char: &str) {
+        for (path, usage) in &self.commands {
+            let path = with_command_char(path, command_char);
+            let root = path.split(' ').next().unwrap_or_default();
+            if commands.contains_key(root) {
+                commands.insert(path, help_parser::parse_arguments(usage));
+            }
+        }
+    }
+}
+
+fn with_command_char(path: &str, command_char: &str) -> String {
+    match path.strip_prefix('/') {
+        Some(rest) => format!("{command_char}{rest}"),
+        None => format!("{command_char}{path}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = "[arguments]\nspell = [\"fireball\", \"heal\"]\n\n[commands]\n\
+\"/spell\" = \"(cast|list)\"\n\"/spell cast\" = \"<targets> <spell> [<power>]\"\n";
+
+    fn spec_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("mc-rcon-{}-{name}", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_toml_and_json_specs() {
+        let toml_path = spec_file("spec.toml", SPEC);
+        let json_path = spec_file(
+            "spec.json",
+            r#"{"arguments": {"spell": ["fireball", "heal"]},
+"commands": {"/spell": "(cast|list)", "/spell cast": "<targets> <spell> [<power>]"}}"#,
+        );
+        let from_toml = CompletionSpec::load(&toml_path).unwrap();
+        assert_eq!(from_toml.arguments["spell"], ["fireball", "heal"]);
+        assert_eq!(CompletionSpec::load(&json_path).unwrap(), from_toml);
+        let bad_path = spec_file("bad-spec.toml", "[unknown]\n");
+        assert!(CompletionSpec::load(&bad_path).is_err());
+        for path in [toml_path, json_path, bad_path] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn applies_commands_with_the_command_char() {
+        let spec: CompletionSpec = toml::from_str(SPEC).unwrap();
+        let mut commands = HashMap::new();
+        spec.apply(&mut commands, "!");
+        let names: Vec<Option<&str>> = commands["!spell cast"].iter().map(Argument::name).collect();
+        assert_eq!(names, [Some("targets"), Some("spell"), Some("power")]);
+        assert!(commands.contains_key("!spell"));
+    }
+
+    #[test]
+    fn apply_known_skips_commands_the_server_lacks() {
+        let spec: CompletionSpec = toml::from_str(SPEC).unwrap();
+        let mut commands = HashMap::new();
+        spec.apply_known(&mut commands, "/");
+        assert!(commands.is_empty());
+        assert!(CompletionSpec::bundled()
+            .commands
+            .contains_key("/loot give"));
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::terminal;
+
+/// Remembers the lines of earlier responses for `:tail`, so only lines not seen in any
+/// earlier poll are printed
+#[derive(Debug, Default)]
+pub struct LineTracker {
+    seen: HashSet<String>,
+}
+
+impl LineTracker {
+    /// The lines of `body` not seen before, in order, which are remembered from now on.
+    /// Blank lines are never new.
+    pub fn new_lines<'a>(&mut self, body: &'a str) -> Vec<&'a str> {
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter(|line| self.seen.insert(line.to_string()))
+            .collect()
+    }
+}
+
+/// Parse the `:tail` interval, a positive number of seconds
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    match value.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(format!(
+            "Expected a positive number of seconds, got '{value}'"
+        )),
+    }
+}
+
+/// Sleep for `duration`, waking early when Ctrl-C is pressed. Returns whether it was.
+pub fn sleep_unless_interrupted(duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while !terminal::interrupted() {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return false;
+        }
+        std::thread::sleep(left.min(Duration::from_millis(50)));
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prints_only_lines_not_seen_before() {
+        let mut tracker = LineTracker::default();
+        assert_eq!(
+            tracker.new_lines("Steve joined\n\nAlex joined"),
+            ["Steve joined", "Alex joined"]
+        );
+        assert!(tracker.new_lines("Alex joined\nSteve joined").is_empty());
+        assert_eq!(
+            tracker.new_lines("Alex joined\nHerobrine joined\n "),
+            ["Herobrine joined"]
+        );
+    }
+
+    #[test]
+    fn intervals_are_positive_seconds() {
+        assert_eq!(parse_interval("0.5"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_interval("2"), Ok(Duration::from_secs(2)));
+        for value in ["0", "-1", "inf", "soon"] {
+            assert_eq!(
+                parse_interval(value),
+                Err(format!(
+                    "Expected a positive number of seconds, got '{value}'"
+                ))
+            );
+        }
+    }
+}
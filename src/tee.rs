@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::display::strip_ansi;
+
+/// The `--tee` file, once opened
+static TEE: OnceLock<Mutex<Tee>> = OnceLock::new();
+
+struct Tee {
+    file: File,
+    keep_ansi: bool,
+}
+
+/// Start copying everything printed with `println` to `path`, appending to it
+pub fn open(path: &Path, keep_ansi: bool) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open tee file {}", path.display()))?;
+    let _ = TEE.set(Mutex::new(Tee { file, keep_ansi }));
+    Ok(())
+}
+
+/// Print a line to stdout, and to the tee file when one is open. Writing the copy may
+/// fail without stopping the session.
+pub fn println(line: &str) {
+    println!("{line}");
+    let Some(tee) = TEE.get() else {
+        return;
+    };
+    let mut tee = tee.lock().unwrap_or_else(|e| e.into_inner());
+    let copy = match tee.keep_ansi {
+        true => format!("{line}\n"),
+        false => format!("{}\n", strip_ansi(line)),
+    };
+    if let Err(e) = tee.file.write_all(copy.as_bytes()) {
+        log::warn!("Failed to write tee file: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The tee file is process wide, so this is the only test opening one
+    #[test]
+    fn copies_printed_lines_without_ansi() {
+        let path = std::env::temp_dir().join(format!("mc-rcon-{}-tee", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        open(&path, false).unwrap();
+        let lines = ["\x1b[32mThere are 0 players online\x1b[0m", "Seed: [42]"];
+        for line in lines {
+            println(line);
+        }
+        let printed: Vec<String> = lines.iter().map(|line| strip_ansi(line)).collect();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            printed.join("\n") + "\n"
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+}
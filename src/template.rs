@@ -0,0 +1,178 @@
+use regex::Regex;
+use rustyline::{
+    CharSearch, Cmd, ConditionalEventHandler, Event, EventContext, Movement, RepeatCount,
+};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use crate::tokenizer::argument_words;
+use crate::{signature_string, Argument};
+
+/// Placeholders as `signature_string` renders them
+fn placeholder_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(r"\[<[^<>\s]+>\]|<[^<>\s]+>|\([^()\s]*\|[^()\s]*\)|\[[^\[\]\s]*\|[^\[\]\s]*\]")
+            .unwrap()
+    })
+}
+
+/// Placeholders for the arguments of the command at the start of `line` that aren't
+/// typed yet, with a leading space when the line doesn't end with one
+pub fn argument_template(commands: &HashMap<String, Vec<Argument>>, line: &str) -> Option<String> {
+    let mut words = argument_words(line);
+    // A trailing empty word only means the line ends with a space
+    if words.last() == Some(&"") {
+        words.pop();
+    }
+    let (args, command_words) = (1..=words.len()).rev().find_map(|len| {
+        commands
+            .get(&words[..len].join(" "))
+            .map(|args| (args, len))
+    })?;
+    // Skip the arguments already typed, a partly typed one included
+    let mut typed = words.len() - command_words;
+    let remaining: Vec<Argument> = args
+        .iter()
+        .skip_while(|arg| {
+            let skip = typed > 0;
+            typed = typed.saturating_sub(arg.width());
+            skip
+        })
+        .cloned()
+        .collect();
+    if remaining.is_empty() {
+        return None;
+    }
+    let separator = match line.ends_with(' ') {
+        true => "",
+        false => " ",
+    };
+    Some(format!("{separator}{}", signature_string(&remaining)))
+}
+
+/// Optional placeholders, with the space before them
+fn optional_placeholder_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r" ?(?:\[<[^<>\s]+>\]|\[[^\[\]\s]*\|[^\[\]\s]*\])").unwrap())
+}
+
+/// The command `line` would become for `--preview`: what's typed, with placeholders for
+/// the arguments still missing and without the optional ones. None when that's just the
+/// line itself.
+pub fn preview(commands: &HashMap<String, Vec<Argument>>, line: &str) -> Option<String> {
+    // A line with placeholders already holds the whole template
+    let full = match next_placeholder(line, 0) {
+        Some(_) => line.to_string(),
+        None => format!(
+            "{line}{}",
+            argument_template(commands, line).unwrap_or_default()
+        ),
+    };
+    let preview = optional_placeholder_regex().replace_all(&full, "");
+    let preview = preview.trim_end();
+    (preview != line.trim_end()).then(|| preview.to_string())
+}
+
+/// Where the first placeholder at or after byte `pos` of `line` is
+pub fn next_placeholder(line: &str, pos: usize) -> Option<Range<usize>> {
+    placeholder_regex()
+        .find_at(line, pos)
+        .map(|placeholder| placeholder.range())
+}
+
+/// Key handler that inserts the argument template of the command being typed, with the
+/// cursor on its first placeholder. On a placeholder it removes it so its value can be
+/// typed, elsewhere it moves to the next placeholder.
+pub struct TemplateHandler {
+    commands: HashMap<String, Vec<Argument>>,
+}
+
+impl TemplateHandler {
+    pub fn new(commands: HashMap<String, Vec<Argument>>) -> TemplateHandler {
+        TemplateHandler { commands }
+    }
+}
+
+impl ConditionalEventHandler for TemplateHandler {
+    fn handle(
+        &self,
+        _evt: &Event,
+        _n: RepeatCount,
+        _positive: bool,
+        ctx: &EventContext,
+    ) -> Option<Cmd> {
+        let (line, pos) = (ctx.line(), ctx.pos());
+        // Counts given here are replaced by the key's repeat count, so the cursor moves by
+        // character search rather than by a number of characters
+        if let Some(range) = next_placeholder(line, pos) {
+            let placeholder = &line[range.clone()];
+            let (first, last) = (placeholder.chars().next()?, placeholder.chars().last()?);
+            return Some(match range.start == pos {
+                true => Cmd::Kill(Movement::ViCharSearch(1, CharSearch::Forward(last))),
+                false => Cmd::Move(Movement::ViCharSearch(1, CharSearch::Forward(first))),
+            });
+        }
+        if pos != line.len() {
+            return Some(Cmd::Noop);
+        }
+        // Unlike an insert, a replace leaves the cursor before the new text
+        let template = argument_template(&self.commands, line);
+        Some(template.map_or(Cmd::Noop, |template| {
+            Cmd::Replace(Movement::EndOfLine, Some(template))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::help_parser::parse_commands;
+
+    fn commands() -> HashMap<String, Vec<Argument>> {
+        parse_commands(
+            "/give <targets> <item> [<count>]\n/time (add|query|set)".to_string(),
+            "/",
+        )
+    }
+
+    #[test]
+    fn inserts_the_missing_arguments() {
+        let commands = commands();
+        assert_eq!(
+            argument_template(&commands, "/give"),
+            Some(" <targets> <item> [<count>]".to_string())
+        );
+        assert_eq!(
+            argument_template(&commands, "/give @p "),
+            Some("<item> [<count>]".to_string())
+        );
+        assert_eq!(
+            argument_template(&commands, "/time "),
+            Some("(add|query|set)".to_string())
+        );
+        assert_eq!(argument_template(&commands, "/give @p stone 1"), None);
+        assert_eq!(argument_template(&commands, "/nope"), None);
+    }
+
+    #[test]
+    fn finds_each_placeholder_in_turn() {
+        let line = "/give <targets> <item> [<count>]";
+        assert_eq!(next_placeholder(line, 0), Some(6..15));
+        assert_eq!(next_placeholder(line, 15), Some(16..22));
+        assert_eq!(next_placeholder(line, 22), Some(23..32));
+        assert_eq!(next_placeholder(line, 32), None);
+    }
+
+    #[test]
+    fn previews_without_the_optional_arguments() {
+        let commands = commands();
+        assert_eq!(
+            preview(&commands, "/give @p"),
+            Some("/give @p <item>".to_string())
+        );
+        assert_eq!(preview(&commands, "/give @p stone"), None);
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{IsTerminal, Write};
+
+#[cfg(unix)]
+static ORIGINAL: std::sync::OnceLock<libc::termios> = std::sync::OnceLock::new();
+
+/// Remember the terminal mode so `restore` can undo raw mode left behind by the editor
+pub fn save() {
+    #[cfg(unix)]
+    if std::io::stdin().is_terminal() {
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        // SAFETY: tcgetattr fully initializes `termios` when it returns 0
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) } == 0 {
+            let _ = ORIGINAL.set(unsafe { termios.assume_init() });
+        }
+    }
+}
+
+/// Put the terminal back the way it was found: the saved mode and default colors
+pub fn restore() {
+    #[cfg(unix)]
+    if let Some(termios) = ORIGINAL.get() {
+        // SAFETY: `termios` was filled in by tcgetattr for the same descriptor
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios) };
+    }
+    let mut stdout = std::io::stdout();
+    if stdout.is_terminal() {
+        let _ = write!(stdout, "\x1b[0m"); // reset
+        let _ = stdout.flush();
+    }
+}
+
+/// Restore the terminal before the panic message is printed
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        default_hook(info);
+    }));
+}
+
+/// Columns of the terminal on stdout, from `COLUMNS` or 80 when it can't be asked
+pub fn width() -> usize {
+    #[cfg(unix)]
+    {
+        let mut size = std::mem::MaybeUninit::<libc::winsize>::uninit();
+        // SAFETY: TIOCGWINSZ fills in `size` when it returns 0
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, size.as_mut_ptr()) } == 0 {
+            let size = unsafe { size.assume_init() };
+            if size.ws_col > 0 {
+                return size.ws_col as usize;
+            }
+        }
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(80)
+}
+
+#[cfg(unix)]
+static INTERRUPTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn note_interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, std::sync::atomic::Ordering::SeqCst);
+}
+
+/// While alive, Ctrl-C is noted for `interrupted` instead of ending the process
+pub struct InterruptGuard {
+    #[cfg(unix)]
+    previous: libc::sighandler_t,
+}
+
+/// Catch Ctrl-C outside the line editor, e.g. to stop a loop of commands
+pub fn catch_interrupts() -> InterruptGuard {
+    #[cfg(unix)]
+    {
+        INTERRUPTED.store(false, std::sync::atomic::Ordering::SeqCst);
+        let handler = note_interrupt as extern "C" fn(libc::c_int);
+        // SAFETY: the handler only stores to an atomic
+        let previous = unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+        InterruptGuard { previous }
+    }
+    #[cfg(not(unix))]
+    InterruptGuard {}
+}
+
+/// Whether Ctrl-C was pressed since `catch_interrupts`
+pub fn interrupted() -> bool {
+    #[cfg(unix)]
+    return INTERRUPTED.load(std::sync::atomic::Ordering::SeqCst);
+    #[cfg(not(unix))]
+    false
+}
+
+impl Drop for InterruptGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        // SAFETY: puts back the handler replaced in `catch_interrupts`
+        unsafe {
+            libc::signal(libc::SIGINT, self.previous)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_still_reach_the_default_hook() {
+        install_panic_hook();
+        let result = std::panic::catch_unwind(|| panic!("restoring the terminal first"));
+        assert!(result.is_err());
+        // Nothing was saved when stdin isn't a terminal, so this only resets colors
+        restore();
+    }
+}
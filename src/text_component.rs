@@ -0,0 +1,161 @@
+use serde_json::Value;
+
+/// ANSI escape for a named Minecraft chat color, or a `#RRGGBB` hex color
+fn ansi_color(color: &str) -> Option<String> {
+    let code = match color {
+        "black" => "30",
+        "dark_blue" => "34",
+        "dark_green" => "32",
+        "dark_aqua" => "36",
+        "dark_red" => "31",
+        "dark_purple" => "35",
+        "gold" => "33",
+        "gray" => "37",
+        "dark_gray" => "90",
+        "blue" => "94",
+        "green" => "92",
+        "aqua" => "96",
+        "red" => "91",
+        "light_purple" => "95",
+        "yellow" => "93",
+        "white" => "97",
+        hex => {
+            let hex = hex.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(format!(
+                "\x1b[38;2;{};{};{}m",
+                channel(0)?,
+                channel(2)?,
+                channel(4)?
+            ));
+        }
+    };
+    Some(format!("\x1b[{code}m"))
+}
+
+fn push_text(out: &mut String, text: &str, color: Option<&str>) {
+    match color.and_then(ansi_color) {
+        Some(escape) if !text.is_empty() => {
+            out.push_str(&escape);
+            out.push_str(text);
+            out.push_str("\x1b[0m"); // reset
+        }
+        _ => out.push_str(text),
+    }
+}
+
+/// Render a component and its children, returning the color its siblings inherit
+fn render<'a>(value: &'a Value, inherited: Option<&'a str>, out: &mut String) -> Option<&'a str> {
+    match value {
+        Value::String(text) => {
+            push_text(out, text, inherited);
+            inherited
+        }
+        // The first element of an array is the parent of the remaining ones
+        Value::Array(parts) => {
+            if let Some((first, rest)) = parts.split_first() {
+                let color = render(first, inherited, out);
+                for part in rest {
+                    render(part, color, out);
+                }
+            }
+            inherited
+        }
+        Value::Object(component) => {
+            let color = component.get("color").and_then(Value::as_str).or(inherited);
+            if let Some(text) = component
+                .get("text")
+                .or_else(|| component.get("translate"))
+                .and_then(Value::as_str)
+            {
+                push_text(out, text, color);
+            }
+            if let Some(extra) = component.get("extra").and_then(Value::as_array) {
+                for part in extra {
+                    render(part, color, out);
+                }
+            }
+            color
+        }
+        other => {
+            push_text(out, &other.to_string(), inherited);
+            inherited
+        }
+    }
+}
+
+/// Keys of which a component object has at least one
+const CONTENT_KEYS: &[&str] = &["text", "translate", "score", "selector", "keybind", "extra"];
+
+/// Whether a JSON value is a text component: a string, an object with content, or a
+/// non-empty array of components
+fn is_text_component(value: &Value) -> bool {
+    match value {
+        Value::String(_) => true,
+        Value::Object(component) => CONTENT_KEYS.iter().any(|key| component.contains_key(*key)),
+        Value::Array(parts) => !parts.is_empty() && parts.iter().all(is_text_component),
+        _ => false,
+    }
+}
+
+/// Render a response body that is a JSON text component as colored plain text.
+/// Returns None for any other body, including JSON that isn't a component.
+pub fn format_text_component(body: &str) -> Option<String> {
+    let body = body.trim();
+    if !body.starts_with('{') && !body.starts_with('[') {
+        return None;
+    }
+    let value: Value = serde_json::from_str(body).ok()?;
+    if !is_text_component(&value) {
+        return None;
+    }
+    let mut rendered = String::new();
+    render(&value, None, &mut rendered);
+    Some(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_a_colored_text_component() {
+        assert_eq!(
+            format_text_component(r#"{"text":"hi","color":"red"}"#).unwrap(),
+            "\x1b[91mhi\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn renders_text_and_extra_with_colors() {
+        let body = r##"{"text":"Hello ","color":"gold","extra":[{"text":"world","color":"#FF0000"},"!"]}"##;
+        assert_eq!(
+            format_text_component(body).unwrap(),
+            "\x1b[33mHello \x1b[0m\x1b[38;2;255;0;0mworld\x1b[0m\x1b[33m!\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn array_parts_inherit_the_first_ones_color() {
+        let body = r#"[{"text":"a","color":"red"},"b"]"#;
+        assert_eq!(
+            format_text_component(body).unwrap(),
+            "\x1b[91ma\x1b[0m\x1b[91mb\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn renders_translate_keys() {
+        let body = r#"{"translate":"chat.type.text"}"#;
+        assert_eq!(format_text_component(body).unwrap(), "chat.type.text");
+    }
+
+    #[test]
+    fn leaves_other_bodies_alone() {
+        assert_eq!(format_text_component("There are 0 players online"), None);
+        assert_eq!(format_text_component("{not json"), None);
+        assert_eq!(format_text_component(r#"{"foo":1}"#), None);
+        assert_eq!(format_text_component("[1,2,3]"), None);
+        assert_eq!(format_text_component("[]"), None);
+    }
+}
@@ -0,0 +1,59 @@
+/// Split a command line on spaces, keeping double-quoted arguments (with `\"` escapes)
+/// together. Like `str::split(' ')`, a trailing space yields an empty last word, which
+/// the completer relies on to know a new argument has started. Quotes are kept in the
+/// returned slices.
+pub fn tokenize(input: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (idx, c) in input.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && in_quotes {
+            escaped = true;
+        } else if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == ' ' && !in_quotes {
+            words.push(&input[start..idx]);
+            start = idx + 1;
+        }
+    }
+    words.push(&input[start..]);
+    words
+}
+
+/// The words the completer counts as arguments. Repeated spaces separate two words only
+/// once, so a completed argument followed by extra spaces still moves on to the next
+/// position. The empty last word from a trailing space is kept.
+pub fn argument_words(input: &str) -> Vec<&str> {
+    let mut words = tokenize(input);
+    let last = words.pop();
+    words.retain(|word| !word.is_empty());
+    words.extend(last);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_quoted_arguments_together() {
+        assert_eq!(
+            tokenize(r#"/say "hello \"big\" world" now"#),
+            ["/say", r#""hello \"big\" world""#, "now"]
+        );
+        assert_eq!(tokenize("/give @p "), ["/give", "@p", ""]);
+        assert_eq!(tokenize(r#"/say "open "#), ["/say", r#""open "#]);
+    }
+
+    #[test]
+    fn repeated_spaces_separate_arguments_once() {
+        assert_eq!(
+            argument_words("/gamemode  survival  "),
+            ["/gamemode", "survival", ""]
+        );
+        assert_eq!(argument_words("/gamemode surv"), ["/gamemode", "surv"]);
+    }
+}
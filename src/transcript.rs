@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// Session log written by `--log-file`. Commands are `> ` lines and the response
+/// lines are indented, so a response can never be mistaken for a command.
+pub struct Transcript {
+    file: File,
+}
+
+impl Transcript {
+    pub fn open(path: &Path) -> Result<Transcript> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file {}", path.display()))?;
+        Ok(Transcript { file })
+    }
+
+    /// Append a command and its response, or the error it failed with.
+    /// Logging failures are reported but don't stop the session.
+    pub fn record(&mut self, cmd: &str, response: &str) {
+        if let Err(e) = self.file.write_all(format_entry(cmd, response).as_bytes()) {
+            log::warn!("Failed to write log file: {e}");
+        }
+    }
+}
+
+pub fn format_entry(cmd: &str, response: &str) -> String {
+    let mut entry = format!("> {cmd}\n");
+    for line in response.lines() {
+        entry.push_str(&format!("  {line}\n"));
+    }
+    entry
+}
+
+/// The commands sent in a transcript, in order
+pub fn extract_commands(transcript: &str) -> Vec<String> {
+    transcript
+        .lines()
+        .filter_map(|line| line.strip_prefix("> "))
+        .map(str::trim)
+        .filter(|cmd| !cmd.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Pause before the command at `index`, the first one is sent right away
+pub fn replay_delay(index: usize, delay: Option<Duration>) -> Option<Duration> {
+    delay.filter(|delay| index > 0 && !delay.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_the_commands_of_a_transcript() {
+        let transcript = [
+            format_entry("/list", "There are 0 players\n> not a command"),
+            format_entry("/seed", ""),
+        ]
+        .concat();
+        assert_eq!(
+            transcript,
+            "> /list\n  There are 0 players\n  > not a command\n> /seed\n"
+        );
+        assert_eq!(extract_commands(&transcript), ["/list", "/seed"]);
+    }
+
+    #[test]
+    fn sends_the_first_command_right_away() {
+        let delay = Some(Duration::from_millis(200));
+        assert_eq!(replay_delay(0, delay), None);
+        assert_eq!(replay_delay(1, delay), delay);
+        assert_eq!(replay_delay(1, Some(Duration::ZERO)), None);
+    }
+}